@@ -1,12 +1,15 @@
-use colored::{ColoredString, Colorize};
+use colored::{Color, ColoredString, Colorize};
 use fnv::FnvHashMap;
 
-use crate::communication::{Communicator, UCICommunicator};
+use crate::{
+    communication::{Communicator, UCICommunicator},
+    error::LotisaError,
+};
 
 use super::{
-    create_default_piece_lookup, generate_legal_moves, generate_moves, generate_zobrist,
-    DefaultRestrictor, DefaultWinConditions, Piece, PieceLookup, PieceMap, PieceMapLookup,
-    Restrictor, WinConditions,
+    generate_legal_moves, generate_moves, generate_zobrist, hash_board, hash_checks, hash_piece,
+    hash_pocket, hash_team, in_check, zobrist_len, KingCaptureRestrictor, PawnConfig, PawnPiece,
+    PieceLookup, Restrictor, Royalty, WinConditions, ZobristKeys, DEFAULT_ZOBRIST_SEED,
 };
 
 //use super::Action;
@@ -34,11 +37,49 @@ pub fn create_board_state(buffer_amount: i16, (rows, cols): (i16, i16)) -> Board
     return state;
 }
 
+// The colour each team's pieces are shown in, with white and black for the first two teams.
+const TEAM_COLORS: [Color; 8] = [
+    Color::White,
+    Color::Black,
+    Color::Red,
+    Color::Blue,
+    Color::Green,
+    Color::Yellow,
+    Color::Magenta,
+    Color::Cyan,
+];
+
+pub fn get_team_color(team: i16) -> Color {
+    TEAM_COLORS[team as usize % TEAM_COLORS.len()]
+}
+
+/*
+    Each row of `state` holds the board's columns with half of the buffer on either side, so this is the distance between rows.
+    Pieces need it to create their deltas before a board exists.
+*/
+pub fn get_row_gap(buffer_amount: i16, cols: i16) -> i16 {
+    cols + buffer_amount
+}
+
 #[derive(Debug, Clone)]
 pub enum StoredMovePieceChange {
-    PieceMove { from: i16, to: i16 },
-    PieceRemove { info: PersistentPieceInfo },
-    PieceCreate { info: PersistentPieceInfo },
+    PieceMove {
+        from: i16,
+        to: i16,
+        first_move: bool,
+    },
+    PieceRemove {
+        info: PersistentPieceInfo,
+    },
+    PieceCreate {
+        info: PersistentPieceInfo,
+    },
+    // `amount` pieces of `piece_type` were added to (or taken out of) the pocket of `team`.
+    PocketChange {
+        team: i16,
+        piece_type: i16,
+        amount: i16,
+    },
 }
 
 #[derive(Debug, Clone)]
@@ -76,6 +117,8 @@ pub enum StoredMoveType {
 pub struct StoredMove {
     pub move_type: StoredMoveType,
     pub action: Action,
+    // The halfmove clock from before this move was made.
+    pub halfmove_clock: i16,
 }
 
 #[derive(Debug)]
@@ -95,6 +138,30 @@ pub struct Action {
     pub info: i16,
 }
 
+// The "info" of a drop, which doesn't clash with the special moves of any piece (or with promotions).
+pub const DROP: i16 = -4;
+
+impl Action {
+    /*
+        Drops put a piece from a pocket onto an empty square.
+        They don't come from a square, so they're stored as moving from the square they land on.
+    */
+    pub fn drop(to: i16, team: i16, piece_type: i16) -> Action {
+        Action {
+            from: to,
+            to,
+            team,
+            piece_type,
+            capture: false,
+            info: DROP,
+        }
+    }
+
+    pub fn is_drop(&self) -> bool {
+        self.from == self.to && self.info == DROP
+    }
+}
+
 pub type PieceList = FnvHashMap<i16, Vec<i16>>;
 
 #[derive(Copy, Clone)]
@@ -118,10 +185,28 @@ pub struct Board {
     pub col_gap: i16,
     pub moving_team: i16,
     pub piece_lookup: Box<dyn PieceLookup>,
+    // The royalty of each piece type, so `in_check` doesn't have to look it up for every move.
+    pub royalties: Vec<Royalty>,
+    // How the pawns of each team move, taken from the pawn's piece (see `PawnConfig`).
+    pub pawn_configs: Vec<PawnConfig>,
     pub win_conditions: Box<dyn WinConditions>,
     pub restrictors: Vec<Box<dyn Restrictor>>,
     pub history: Vec<StoredMove>,
     pub zobrist: Vec<usize>,
+    pub hash: usize,
+    // The hash from before each move in `history`, so undoing a move doesn't need to rehash.
+    pub hashes: Vec<usize>,
+    pub halfmove_clock: i16,
+    pub fullmove_number: i16,
+    /*
+        Pieces in hand for drop variants like Crazyhouse, indexed by team and then piece type.
+        Boards without drops have None, and captured pieces only go to a pocket when there are pockets.
+    */
+    pub pockets: Option<Vec<Vec<i16>>>,
+    // How many more checks each team needs to give to win in Three-check, with None on boards that don't count them.
+    pub remaining_checks: Option<Vec<i16>>,
+    // Whether castling is written in UCI as the king taking its own rook, the way UCI_Chess960 expects.
+    pub chess960: bool,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -147,6 +232,8 @@ pub struct PersistentPieceInfo {
         As for FEN parsing, "first_move" will be inferred base on information regarding castling rights or pawn placement, so compatibility will be affirmed there.
     */
     pub first_move: bool,
+    // Whether the piece is a promoted pawn, which goes back to a pocket as a pawn when it's captured.
+    pub promoted: bool,
 }
 
 // TODO: Add reverse piece list to speed up removing items
@@ -163,8 +250,23 @@ impl Board {
     ) -> Board {
         let state = create_board_state(buffer_amount, (rows, cols));
 
-        let row_gap = rows + buffer_amount;
-        let col_gap = cols + (2 * buffer_amount);
+        let row_gap = get_row_gap(buffer_amount, cols);
+        // The number of rows in `state`, so `row_gap * col_gap` covers every position.
+        let col_gap = rows + (2 * buffer_amount);
+
+        let zobrist = generate_zobrist(piece_types, teams, row_gap * col_gap, DEFAULT_ZOBRIST_SEED);
+        let hash = zobrist[0];
+        let royalties = (0..piece_types)
+            .map(|piece_type| piece_lookup.lookup(piece_type).get_royalty())
+            .collect();
+        let pawn_configs = (0..teams)
+            .map(|team| {
+                piece_lookup
+                    .lookup(0)
+                    .get_pawn_config(team)
+                    .unwrap_or_else(|| PawnPiece::default().get_config(team).clone())
+            })
+            .collect();
 
         return Board {
             state,
@@ -181,8 +283,17 @@ impl Board {
             row_gap,
             col_gap,
             piece_lookup,
+            royalties,
+            pawn_configs,
             history: Vec::with_capacity(500),
-            zobrist: generate_zobrist(piece_types, teams, row_gap * col_gap),
+            zobrist,
+            hash,
+            hashes: Vec::with_capacity(500),
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            pockets: None,
+            remaining_checks: None,
+            chess960: false,
         };
     }
 
@@ -197,8 +308,8 @@ impl Board {
 
     pub fn get_previous_team(&self, team: i16) -> i16 {
         let team = team - 1;
-        if team >= self.teams {
-            0
+        if team < 0 {
+            self.teams - 1
         } else {
             team
         }
@@ -209,7 +320,7 @@ impl Board {
     }
 
     pub fn previous_team(&self) -> i16 {
-        self.get_next_team(self.moving_team)
+        self.get_previous_team(self.moving_team)
     }
 
     pub fn display_board(&self) -> Vec<ColoredString> {
@@ -240,11 +351,7 @@ impl Board {
                 let piece_type = self.get_piece_type(piece, team);
                 let piece_trait = self.piece_lookup.lookup(piece_type).duplicate();
                 let piece_icon = piece_trait.get_icon();
-                items.push(match team {
-                    0 => piece_icon.white(),
-                    1 => piece_icon.black(),
-                    _ => "".red(),
-                });
+                items.push(piece_icon.color(get_team_color(team)));
                 items.push(" ".white());
             }
 
@@ -261,37 +368,251 @@ impl Board {
         println!("\n");
     }
 
+    /*
+        Every restrictor has to allow the move, so they can be stacked to combine rules.
+        They're taken off the board while they run (instead of being duplicated for every move), so they can keep caches between moves.
+    */
     pub fn is_legal(&mut self, action: Action, required_team: i16) -> bool {
-        let restrictors = self
-            .restrictors
-            .iter()
-            .map(|restrictor| restrictor.duplicate())
-            .collect::<Vec<_>>();
-        for restrictor in restrictors {
-            if !restrictor.can_add(self, &action, required_team) {
-                return false;
-            }
-        }
-
-        return true;
+        /*
+            Each restrictor is taken out of the board while it runs, so it can check moves against the others (like `CaptureRestrictor` does).
+            `KingCaptureRestrictor` lets every move through, so it stands in for it until then.
+        */
+        (0..self.restrictors.len()).all(|index| {
+            let restrictor = std::mem::replace(
+                &mut self.restrictors[index],
+                Box::new(KingCaptureRestrictor),
+            );
+            let can_add = restrictor.can_add(self, &action, required_team);
+            self.restrictors[index] = restrictor;
+            can_add
+        })
     }
 
     pub fn make_move(&mut self, action: Action) {
-        let PieceInfo { piece_type, .. } = self.get_piece_info(action.from);
+        self.hashes.push(self.hash);
+        let piece_type = if action.is_drop() {
+            self.make_drop(action);
+            action.piece_type
+        } else {
+            let PieceInfo { piece_type, .. } = self.get_piece_info(action.from);
 
-        let piece_trait = self.piece_lookup.lookup(piece_type).duplicate();
-        piece_trait.make_move(self, action);
+            let piece_trait = self.piece_lookup.lookup(piece_type).duplicate();
+            piece_trait.make_move(self, action);
+
+            if action.piece_type == 0 && action.info >= 0 {
+                self.set_promoted(action.to, true);
+            }
+            if self.pockets.is_some() {
+                self.pocket_captures(action.team);
+            }
+            piece_type
+        };
+
+        /*
+            The halfmove clock resets on pawn moves and on anything that removes a piece from the board.
+            Castling is stored as a capture of the rook, so we look at the stored move instead of `action.capture`.
+        */
+        let resets_clock = match &self.history.last().unwrap().move_type {
+            StoredMoveType::Standard { pieces, .. } => {
+                piece_type == 0
+                    || pieces
+                        .iter()
+                        .any(|piece| matches!(piece, StoredMovePieceChange::PieceRemove { .. }))
+            }
+            StoredMoveType::Custom { pieces, .. } => {
+                piece_type == 0 || pieces.len() != self.pieces.len()
+            }
+        };
+        if resets_clock {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+
+        self.hash ^= hash_team(self, self.moving_team);
         self.moving_team = self.next_team();
+        self.hash ^= hash_team(self, self.moving_team);
+        if self.moving_team == 0 {
+            self.fullmove_number += 1;
+        }
+
+        if self.remaining_checks.is_some() && in_check(self, self.moving_team, self.row_gap) {
+            self.add_remaining_checks(action.team, -1);
+        }
     }
 
     pub fn undo_move(&mut self) -> StoredMove {
+        // The check is taken back before the move is, while the checked king is still in check. The hash is restored below.
+        if self.remaining_checks.is_some() && in_check(self, self.moving_team, self.row_gap) {
+            let team = self.history.last().unwrap().action.team;
+            if let Some(remaining_checks) = &mut self.remaining_checks {
+                remaining_checks[team as usize] += 1;
+            }
+        }
+
         let undo = self.history.pop().unwrap();
         let piece_trait = self.piece_lookup.lookup(undo.action.piece_type).duplicate();
         piece_trait.undo_move(self, &undo);
+        if undo.action.piece_type == 0 && undo.action.info >= 0 {
+            self.set_promoted(undo.action.from, false);
+        }
+        self.halfmove_clock = undo.halfmove_clock;
+        if self.moving_team == 0 {
+            self.fullmove_number -= 1;
+        }
         self.moving_team = self.previous_team();
+        self.hash = match self.hashes.pop() {
+            Some(hash) => hash,
+            // Moves pushed onto `history` by hand (like the en passant move from a FEN) have no stored hash.
+            None => hash_board(self, self.moving_team, &self.zobrist),
+        };
         undo
     }
 
+    fn set_promoted(&mut self, pos: i16, promoted: bool) {
+        if let Some(piece) = self.pieces.iter_mut().find(|piece| piece.pos == pos) {
+            piece.promoted = promoted;
+        }
+    }
+
+    pub fn get_pocket(&self, team: i16, piece_type: i16) -> i16 {
+        match &self.pockets {
+            Some(pockets) => pockets[team as usize][piece_type as usize],
+            None => 0,
+        }
+    }
+
+    // Changes how many of `piece_type` are in the pocket of `team`, keeping the hash up to date.
+    pub fn add_to_pocket(&mut self, team: i16, piece_type: i16, amount: i16) {
+        let count = self.get_pocket(team, piece_type);
+        if let Some(pockets) = &mut self.pockets {
+            pockets[team as usize][piece_type as usize] = count + amount;
+        }
+
+        self.hash ^= hash_pocket(self, team, piece_type, count);
+        self.hash ^= hash_pocket(self, team, piece_type, count + amount);
+    }
+
+    // Changes how many checks `team` still needs to give, keeping the hash up to date.
+    pub fn add_remaining_checks(&mut self, team: i16, amount: i16) {
+        let remaining = match &mut self.remaining_checks {
+            Some(remaining_checks) => {
+                remaining_checks[team as usize] += amount;
+                remaining_checks[team as usize]
+            }
+            None => return,
+        };
+
+        self.hash ^= hash_checks(self, team, remaining - amount);
+        self.hash ^= hash_checks(self, team, remaining);
+    }
+
+    fn make_drop(&mut self, action: Action) {
+        let piece = self.get_piece_value(action.piece_type, action.team);
+        // Pawns dropped where they could double move from can still double move, like in Crazyhouse.
+        let first_move = action.piece_type == 0 && {
+            let config = &self.pawn_configs[action.team as usize];
+            config.is_double_move_rank(config.get_rank(self, action.to))
+        };
+        let info = PersistentPieceInfo {
+            pos: action.to,
+            first_move,
+            promoted: false,
+        };
+
+        self.state[action.to as usize] = piece;
+        self.pieces.push(info);
+        self.hash ^= hash_piece(self, action.to, piece, first_move);
+        self.add_to_pocket(action.team, action.piece_type, -1);
+
+        self.history.push(StoredMove {
+            action,
+            halfmove_clock: self.halfmove_clock,
+            move_type: StoredMoveType::Standard {
+                states: vec![ResetSquare {
+                    pos: action.to,
+                    state: 1,
+                }],
+                pieces: vec![
+                    StoredMovePieceChange::PieceCreate { info },
+                    StoredMovePieceChange::PocketChange {
+                        team: action.team,
+                        piece_type: action.piece_type,
+                        amount: -1,
+                    },
+                ],
+            },
+        });
+    }
+
+    /*
+        Moves every piece the last move captured into the pocket of `team`, with promoted pieces going back as pawns.
+        The stored move keeps the pocket changes, so undoing the move takes them back out.
+    */
+    fn pocket_captures(&mut self, team: i16) {
+        let captured = match &self.history.last().unwrap().move_type {
+            StoredMoveType::Standard { states, pieces } => pieces
+                .iter()
+                .filter_map(|piece| match piece {
+                    StoredMovePieceChange::PieceRemove { info } => {
+                        let state = states.iter().find(|state| state.pos == info.pos)?;
+                        let piece_team = self.get_team(state.state);
+                        Some(if info.promoted {
+                            0
+                        } else {
+                            self.get_piece_type(state.state, piece_team)
+                        })
+                    }
+                    _ => None,
+                })
+                .collect::<Vec<_>>(),
+            StoredMoveType::Custom { .. } => vec![],
+        };
+
+        for piece_type in captured {
+            self.add_to_pocket(team, piece_type, 1);
+            if let StoredMoveType::Standard { pieces, .. } =
+                &mut self.history.last_mut().unwrap().move_type
+            {
+                pieces.push(StoredMovePieceChange::PocketChange {
+                    team,
+                    piece_type,
+                    amount: 1,
+                });
+            }
+        }
+    }
+
+    pub fn rehash(&mut self) {
+        self.hash = hash_board(self, self.moving_team, &self.zobrist);
+    }
+
+    /*
+        Replaces the default zobrist keys, either with ones generated from another seed or with an existing table.
+        Meant to be chained onto `Board::new`, before any moves are made.
+    */
+    pub fn with_zobrist(mut self, keys: ZobristKeys) -> Board {
+        let positions = self.row_gap * self.col_gap;
+        self.zobrist = match keys {
+            ZobristKeys::Seed(seed) => {
+                generate_zobrist(self.piece_types, self.teams, positions, seed)
+            }
+            ZobristKeys::Table(table) => {
+                let len = zobrist_len(self.piece_types, self.teams, positions);
+                assert!(
+                    table.len() >= len,
+                    "Zobrist table has {} keys, but this board needs {}",
+                    table.len(),
+                    len
+                );
+                table
+            }
+        };
+
+        self.rehash();
+        self
+    }
+
     /*
         Index 0 represents an out of bounds square and index 1 represents an empty square, so we add plus two to the index
     */
@@ -365,163 +686,25 @@ impl Board {
     }
 
     pub fn load_uci_pgn(uci_pgn: &str) -> UCICommunicator {
+        Board::try_load_uci_pgn(uci_pgn).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /*
+        Plays UCI moves from the starting position, skipping move numbers like "1.".
+    */
+    pub fn try_load_uci_pgn(uci_pgn: &str) -> Result<UCICommunicator, LotisaError> {
         let mut uci = Board::load_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w kqKQ -");
 
-        for action in uci_pgn.split(" ") {
-            if action.chars().nth(0).unwrap().is_numeric() {
+        for action in uci_pgn.split_whitespace() {
+            if action.starts_with(|char: char| char.is_ascii_digit()) {
                 continue;
             }
 
-            let action = uci.decode(action.to_string());
+            let action = uci.try_decode(action)?;
             uci.board.make_move(action);
         }
 
-        uci
-    }
-
-    pub fn load_fen(fen: &str) -> UCICommunicator {
-        let fen_parts = fen.split(" ").collect::<Vec<_>>();
-
-        let mut uci = UCICommunicator {
-            board: Board::load_fen_pieces(fen_parts[0]),
-        };
-
-        let castling = fen_parts[2].chars().collect::<Vec<_>>();
-
-        // TODO: For each type of castling, change "first_move"s
-
-        if castling[0] != '-' {
-            for (castling_type, pos) in [('k', 98), ('q', 91), ('K', 28), ('Q', 21)] {
-                if !castling.contains(&castling_type) {
-                    let pieces_position =
-                        uci.board.pieces.iter().position(|piece| piece.pos == pos);
-                    if let Some(pieces_position) = pieces_position {
-                        uci.board.pieces[pieces_position].first_move = false;
-                    }
-                }
-            }
-        }
-
-        if fen_parts[3] != "-" {
-            let pos = uci.decode_pos(fen_parts[3].to_string());
-            let row_gap = uci.board.row_gap;
-            let PieceInfo { team, .. } = uci.board.get_piece_info(pos);
-
-            let from = match team {
-                0 => pos + row_gap * 2,
-                1 => pos - row_gap * 2,
-                _ => pos,
-            };
-
-            let action = Action {
-                from,
-                to: pos,
-                piece_type: 0,
-                team,
-                capture: false,
-                info: -2,
-            };
-
-            uci.board.history.push(StoredMove {
-                move_type: StoredMoveType::Standard {
-                    states: vec![
-                        ResetSquare {
-                            pos: from,
-                            state: uci.board.get_piece_value(0, team),
-                        },
-                        ResetSquare {
-                            pos,
-                            state: uci.board.get_piece_type(0, uci.board.get_next_team(team)),
-                        },
-                    ],
-                    pieces: vec![
-                        StoredMovePieceChange::PieceRemove {
-                            info: PersistentPieceInfo {
-                                pos,
-                                first_move: false,
-                            },
-                        },
-                        StoredMovePieceChange::PieceMove { from, to: pos },
-                    ],
-                },
-                action,
-            });
-        }
-
-        // TODO: Add last move to history for en passant
-
-        uci.board.moving_team = match fen_parts[1] {
-            "w" => 0,
-            "b" => 1,
-            _ => 0,
-        };
-
-        uci
-    }
-
-    pub fn load_fen_pieces(fen: &str) -> Board {
-        let fen_chunks = fen.split("/");
-        let mut pieces: Vec<PersistentPieceInfo> = Vec::with_capacity(32);
-        let mut board = Board::new(
-            6,
-            2,
-            2,
-            (8, 8),
-            create_default_piece_lookup(10),
-            Box::new(DefaultWinConditions),
-            vec![Box::new(DefaultRestrictor)],
-        );
-
-        let min_row = board.buffer_amount;
-        let max_row = board.rows + board.buffer_amount;
-
-        let pawn_min_row = min_row + 1;
-        let pawn_max_row = max_row - 2;
-
-        for (row_ind, chunk) in fen_chunks.enumerate() {
-            let mut col_ind: usize = 0;
-            for col in chunk.chars() {
-                if col.is_numeric() {
-                    let empty_spaces = col.to_digit(10).unwrap();
-                    col_ind += empty_spaces as usize;
-                    continue;
-                }
-                let team = if col.is_ascii_uppercase() { 0 } else { 1 };
-                let piece_type = match col.to_ascii_lowercase() {
-                    'p' => 0,
-                    'n' => 1,
-                    'b' => 2,
-                    'r' => 3,
-                    'q' => 4,
-                    'k' => 5,
-                    _ => 0,
-                };
-                let piece = board.get_piece_value(piece_type, team);
-                let piece_pos = (col_ind + 1) + 10 * (row_ind + 2);
-                board.state[piece_pos] = piece;
-                col_ind += 1;
-
-                let piece_pos_i16 = piece_pos as i16;
-
-                pieces.push(PersistentPieceInfo {
-                    pos: piece_pos_i16,
-                    first_move: if piece_type == 0 {
-                        let row = board.get_row(piece_pos_i16);
-                        match team {
-                            0 => row == pawn_max_row,
-                            1 => row == pawn_min_row,
-                            _ => false,
-                        }
-                    } else {
-                        true
-                    },
-                });
-            }
-        }
-
-        board.pieces = pieces;
-
-        board
+        Ok(uci)
     }
 
     pub fn generate_moves(&mut self) -> Vec<Action> {
@@ -0,0 +1,66 @@
+pub const CHESS960_POSITIONS: u16 = 960;
+
+// Where the two knights go among the five squares left after the bishops and queen, indexed by the last digit of the position number.
+const KNIGHT_PLACEMENTS: [(usize, usize); 10] = [
+    (0, 1),
+    (0, 2),
+    (0, 3),
+    (0, 4),
+    (1, 2),
+    (1, 3),
+    (1, 4),
+    (2, 3),
+    (2, 4),
+    (3, 4),
+];
+
+/*
+    The back rank of a Chess960 starting position, numbered the standard (Scharnagl) way from 0 to 959.
+    Position 518 is the standard chess setup.
+*/
+pub fn get_chess960_back_rank(index: u16) -> Option<[char; 8]> {
+    if index >= CHESS960_POSITIONS {
+        return None;
+    }
+
+    let mut back_rank = [' '; 8];
+    let mut index = index as usize;
+
+    // Light squared bishop on b, d, f or h, then the dark squared one on a, c, e or g.
+    back_rank[(2 * (index % 4)) + 1] = 'b';
+    index /= 4;
+    back_rank[2 * (index % 4)] = 'b';
+    index /= 4;
+
+    let mut place_on_empty = |nth: usize, piece: char| {
+        let col = (0..8)
+            .filter(|col| back_rank[*col] == ' ')
+            .nth(nth)
+            .unwrap();
+        back_rank[col] = piece;
+    };
+
+    place_on_empty(index % 6, 'q');
+    index /= 6;
+
+    // Placing the first knight shifts the empty squares, so the second one is placed first.
+    let (first_knight, second_knight) = KNIGHT_PLACEMENTS[index];
+    place_on_empty(second_knight, 'n');
+    place_on_empty(first_knight, 'n');
+
+    // The king always ends up between the rooks.
+    place_on_empty(0, 'r');
+    place_on_empty(0, 'k');
+    place_on_empty(0, 'r');
+
+    Some(back_rank)
+}
+
+pub fn get_chess960_fen(index: u16) -> Option<String> {
+    let back_rank = get_chess960_back_rank(index)?.iter().collect::<String>();
+    Some(format!(
+        "{}/pppppppp/8/8/8/8/PPPPPPPP/{} w KQkq - 0 1",
+        back_rank,
+        back_rank.to_ascii_uppercase()
+    ))
+}
@@ -0,0 +1,815 @@
+use crate::{
+    communication::{decode_file, encode_file, encode_uci_pos, Communicator, UCICommunicator},
+    error::LotisaError,
+};
+
+use super::{
+    create_default_piece_lookup, get_row_gap, zobrist_len, Action, Board, DefaultRestrictor,
+    DefaultWinConditions, PersistentPieceInfo, PieceInfo, PieceLookup, ResetSquare, Restrictor,
+    StoredMove, StoredMovePieceChange, StoredMoveType, WinConditions, MAX_ZOBRIST_LEN,
+};
+
+/*
+    FEN letters for the default piece types, indexed by piece type.
+    White (team 0) uses the uppercase letter and black (team 1) the lowercase one.
+*/
+pub const FEN_PIECES: [char; 6] = ['p', 'n', 'b', 'r', 'q', 'k'];
+
+pub const STARTPOS_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+const DOUBLE_MOVE: i16 = -2;
+
+/*
+    What a FEN is loaded onto when it doesn't say otherwise: the board size, the number of teams and the piece set.
+    A variant FEN can start with a "<cols>x<rows>" field (with ":<teams>" when there aren't two teams) to change the size and teams.
+*/
+pub struct BoardLayout {
+    pub rows: i16,
+    pub cols: i16,
+    pub teams: i16,
+    pub piece_types: i16,
+    // Has to be at least as far as any piece leaps, see `BetzaDefinition::get_reach`.
+    pub buffer_amount: i16,
+    // Creates the piece lookup from the board's row gap, since pieces need it for their deltas.
+    pub create_piece_lookup: Box<dyn Fn(i16) -> Box<dyn PieceLookup> + Send>,
+    pub win_conditions: Box<dyn WinConditions>,
+    pub restrictors: Vec<Box<dyn Restrictor>>,
+    // Whether boards get pockets, so captured pieces can be dropped back in (like in Crazyhouse).
+    pub drops: bool,
+    // How many checks each team needs to give to win (three in Three-check), with None for boards that don't count them.
+    pub checks: Option<i16>,
+}
+
+impl BoardLayout {
+    pub fn new(
+        piece_types: i16,
+        teams: i16,
+        (rows, cols): (i16, i16),
+        create_piece_lookup: Box<dyn Fn(i16) -> Box<dyn PieceLookup> + Send>,
+    ) -> BoardLayout {
+        BoardLayout {
+            rows,
+            cols,
+            teams,
+            piece_types,
+            buffer_amount: 2,
+            create_piece_lookup,
+            win_conditions: Box::new(DefaultWinConditions),
+            restrictors: vec![Box::new(DefaultRestrictor)],
+            drops: false,
+            checks: None,
+        }
+    }
+
+    pub fn with_drops(mut self) -> BoardLayout {
+        self.drops = true;
+        self
+    }
+
+    pub fn with_checks(mut self, checks: i16) -> BoardLayout {
+        self.checks = Some(checks);
+        self
+    }
+
+    pub fn with_win_conditions(mut self, win_conditions: Box<dyn WinConditions>) -> BoardLayout {
+        self.win_conditions = win_conditions;
+        self
+    }
+
+    pub fn with_restrictors(mut self, restrictors: Vec<Box<dyn Restrictor>>) -> BoardLayout {
+        self.restrictors = restrictors;
+        self
+    }
+
+    pub fn with_buffer_amount(mut self, buffer_amount: i16) -> BoardLayout {
+        self.buffer_amount = buffer_amount;
+        self
+    }
+
+    /*
+        Whether a board of this layout can be made with this size and teams.
+        Positions and piece values have to fit in an i16, and the zobrist table can't go past `MAX_ZOBRIST_LEN`.
+    */
+    fn fits(&self, (rows, cols): (i16, i16), teams: i16) -> bool {
+        let buffer_amount = self.buffer_amount as usize;
+        let positions = (cols as usize + buffer_amount) * (rows as usize + 2 * buffer_amount);
+        let piece_values = self.piece_types as usize * teams as usize + 2;
+        positions <= i16::MAX as usize
+            && piece_values <= i16::MAX as usize
+            && zobrist_len(self.piece_types, teams, positions as i16) <= MAX_ZOBRIST_LEN
+    }
+
+    /*
+        An empty board of this layout, with the size and teams given by the FEN instead of the layout's own.
+    */
+    fn create_board(&self, (rows, cols): (i16, i16), teams: i16) -> Board {
+        let mut board = Board::new(
+            self.piece_types,
+            self.buffer_amount,
+            teams,
+            (rows, cols),
+            (self.create_piece_lookup)(get_row_gap(self.buffer_amount, cols)),
+            self.win_conditions.duplicate(),
+            self.restrictors
+                .iter()
+                .map(|restrictor| restrictor.duplicate())
+                .collect(),
+        );
+        if self.drops {
+            board.pockets = Some(vec![vec![0; self.piece_types as usize]; teams as usize]);
+        }
+        if let Some(checks) = self.checks {
+            board.remaining_checks = Some(vec![checks; teams as usize]);
+        }
+        board
+    }
+}
+
+impl Default for BoardLayout {
+    fn default() -> Self {
+        BoardLayout::new(6, 2, (8, 8), Box::new(create_default_piece_lookup))
+    }
+}
+
+enum FenSquare {
+    Empty(i16),
+    Piece {
+        letter: char,
+        team: Option<i16>,
+        promoted: bool,
+    },
+}
+
+/*
+    Empty runs can have more than one digit ("12"), and a piece of any team can be written as its letter followed by the team in parentheses ("k(2)").
+    Promoted pieces in drop variants end with a "~" ("Q~").
+*/
+fn parse_fen_row(chunk: &str) -> Result<Vec<FenSquare>, LotisaError> {
+    let mut squares = vec![];
+    let mut chars = chunk.chars().peekable();
+    while let Some(char) = chars.next() {
+        if char.is_ascii_digit() {
+            let mut digits = char.to_string();
+            while let Some(digit) = chars.next_if(|char| char.is_ascii_digit()) {
+                digits.push(digit);
+            }
+
+            match digits.parse::<i16>() {
+                Ok(empty_spaces) if !digits.starts_with('0') => {
+                    squares.push(FenSquare::Empty(empty_spaces))
+                }
+                _ => {
+                    return Err(LotisaError::InvalidFen(format!(
+                        "invalid empty run \"{}\" in row \"{}\"",
+                        digits, chunk
+                    )))
+                }
+            }
+            continue;
+        }
+
+        if !char.is_ascii_alphabetic() {
+            return Err(LotisaError::InvalidFen(format!("unknown piece '{}'", char)));
+        }
+
+        let mut team = None;
+        if chars.next_if_eq(&'(').is_some() {
+            let mut digits = String::new();
+            while let Some(digit) = chars.next_if(|char| char.is_ascii_digit()) {
+                digits.push(digit);
+            }
+
+            team = match (digits.parse::<i16>(), chars.next()) {
+                (Ok(team), Some(')')) => Some(team),
+                _ => {
+                    return Err(LotisaError::InvalidFen(format!(
+                        "invalid team for '{}' in row \"{}\"",
+                        char, chunk
+                    )))
+                }
+            };
+        }
+
+        let promoted = chars.next_if_eq(&'~').is_some();
+        squares.push(FenSquare::Piece {
+            letter: char,
+            team,
+            promoted,
+        });
+    }
+
+    Ok(squares)
+}
+
+/*
+    Reads a "<cols>x<rows>" or "<cols>x<rows>:<teams>" field, returning None if the field isn't one.
+*/
+fn parse_fen_header(field: &str) -> Option<Result<(i16, i16, i16), LotisaError>> {
+    let (size, teams) = match field.split_once(':') {
+        Some((size, teams)) => (size, Some(teams)),
+        None => (field, None),
+    };
+    let (cols, rows) = size.split_once('x')?;
+    if cols.is_empty() || !cols.chars().all(|char| char.is_ascii_digit()) {
+        return None;
+    }
+
+    let invalid = || LotisaError::InvalidFen(format!("invalid board size \"{}\"", field));
+    let parse = |value: &str| match value.parse::<i16>() {
+        Ok(value) if value > 0 => Ok(value),
+        _ => Err(invalid()),
+    };
+
+    Some(parse(cols).and_then(|cols| Ok((cols, parse(rows)?, teams.map_or(Ok(2), parse)?))))
+}
+
+impl Board {
+    // Panics on a bad FEN, so it's only for FENs we know are valid.
+    pub fn load_fen(fen: &str) -> UCICommunicator {
+        Board::try_load_fen(fen).unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    pub fn try_load_fen(fen: &str) -> Result<UCICommunicator, LotisaError> {
+        Board::try_load_variant_fen(fen, &BoardLayout::default())
+    }
+
+    /*
+        The halfmove clock and fullmove number can be left off (like in EPD), but every other field is needed.
+        On top of standard FEN, this reads an optional size header ("10x8", "8x8:4") in front of the placement, and a team number as the side to move.
+        Boards with pockets can have them at the end of the placement, like "RNBQKBNR[Qnp]".
+        Boards that count checks can have the checks left after the en passant square ("3+3"), or the checks given at the end ("+1+0").
+    */
+    pub fn try_load_variant_fen(
+        fen: &str,
+        layout: &BoardLayout,
+    ) -> Result<UCICommunicator, LotisaError> {
+        let mut fen_parts = fen.split_whitespace().collect::<Vec<_>>();
+
+        let (mut rows, mut cols, mut teams) = (layout.rows, layout.cols, layout.teams);
+        if let Some(header) = fen_parts.first().and_then(|field| parse_fen_header(field)) {
+            (cols, rows, teams) = header?;
+            if !layout.fits((rows, cols), teams) {
+                return Err(LotisaError::InvalidFen(format!(
+                    "board size \"{}\" is too large",
+                    fen_parts[0]
+                )));
+            }
+            fen_parts.remove(0);
+        }
+
+        let checks = fen_parts
+            .iter()
+            .skip(4)
+            .position(|field| field.contains('+'))
+            .map(|index| fen_parts.remove(index + 4));
+
+        if fen_parts.len() < 4 || fen_parts.len() > 6 {
+            return Err(LotisaError::InvalidFen(format!(
+                "expected 4 to 6 fields, found {}",
+                fen_parts.len()
+            )));
+        }
+
+        let mut uci = UCICommunicator {
+            board: layout.create_board((rows, cols), teams),
+        };
+        let (placement, pockets) = match fen_parts[0].split_once('[') {
+            Some((placement, pockets)) => match pockets.strip_suffix(']') {
+                Some(pockets) => (placement, Some(pockets)),
+                None => {
+                    return Err(LotisaError::InvalidFen(format!(
+                        "unclosed pocket \"[{}\"",
+                        pockets
+                    )))
+                }
+            },
+            None => (fen_parts[0], None),
+        };
+        uci.board.load_fen_placement(placement)?;
+        if let Some(pockets) = pockets {
+            uci.board.load_fen_pockets(pockets)?;
+        }
+
+        uci.board.moving_team = match fen_parts[1] {
+            "w" => 0,
+            "b" => 1,
+            side => match side.parse::<i16>() {
+                Ok(team) if team >= 0 && team < teams => team,
+                _ => {
+                    return Err(LotisaError::InvalidFen(format!(
+                        "unknown side to move \"{}\"",
+                        side
+                    )))
+                }
+            },
+        };
+
+        /*
+            Pieces that castle (and the pieces they castle with) only keep their "first_move" if the FEN still grants them a castling right.
+            Every other piece keeps the default inferred by `load_fen_placement`.
+        */
+        let castling_pieces = (0..uci.board.piece_types)
+            .filter_map(|piece_type| {
+                let castling_piece = uci
+                    .board
+                    .piece_lookup
+                    .lookup(piece_type)
+                    .get_castling_piece()?;
+                Some([piece_type, castling_piece])
+            })
+            .flatten()
+            .collect::<Vec<_>>();
+        for index in 0..uci.board.pieces.len() {
+            let PieceInfo { piece_type, .. } =
+                uci.board.get_piece_info(uci.board.pieces[index].pos);
+            if castling_pieces.contains(&piece_type) {
+                uci.board.pieces[index].first_move = false;
+            }
+        }
+
+        if fen_parts[2] != "-" {
+            for castling_type in fen_parts[2].chars() {
+                let team = if castling_type.is_ascii_uppercase() {
+                    0
+                } else {
+                    1
+                };
+                // Shredder-FEN and X-FEN name the rook's file instead, for Chess960 positions.
+                let file = decode_file(&castling_type.to_ascii_lowercase().to_string())
+                    .filter(|col| *col < uci.board.cols);
+                match (castling_type.to_ascii_lowercase(), file) {
+                    ('k', _) => uci.board.add_castling_right(team, 1),
+                    ('q', _) => uci.board.add_castling_right(team, -1),
+                    (_, Some(col)) => uci.board.add_castling_file(team, col),
+                    _ => {
+                        return Err(LotisaError::InvalidFen(format!(
+                            "unknown castling right '{}'",
+                            castling_type
+                        )))
+                    }
+                }
+            }
+        }
+
+        if fen_parts[3] != "-" {
+            let pos = uci.try_decode_pos(fen_parts[3])?;
+
+            /*
+                The double move was made by the team that isn't moving now, so its pawn is one step past the square it skipped over.
+                That square has to be empty and one rank past where that team's pawns double move from.
+            */
+            let team = uci.board.previous_team();
+            let pawn = uci.board.get_piece_value(0, team);
+            let config = &uci.board.pawn_configs[team as usize];
+            let forward = config.get_forward(uci.board.row_gap);
+            let skipped_rank = config.get_rank(&uci.board, pos) - 1;
+            if uci.board.state[pos as usize] != 1
+                || !config.is_double_move_rank(skipped_rank)
+                || uci.board.state[(pos + forward) as usize] != pawn
+            {
+                return Err(LotisaError::InvalidFen(format!(
+                    "no pawn can be taken en passant on \"{}\"",
+                    fen_parts[3]
+                )));
+            }
+            let (from, to) = (pos - forward, pos + forward);
+
+            let action = Action {
+                from,
+                to,
+                piece_type: 0,
+                team,
+                capture: false,
+                info: DOUBLE_MOVE,
+            };
+
+            uci.board.history.push(StoredMove {
+                move_type: StoredMoveType::Standard {
+                    states: vec![
+                        ResetSquare {
+                            pos: from,
+                            state: uci.board.get_piece_value(0, team),
+                        },
+                        ResetSquare { pos: to, state: 1 },
+                    ],
+                    pieces: vec![StoredMovePieceChange::PieceMove {
+                        from,
+                        to,
+                        first_move: true,
+                    }],
+                },
+                action,
+                halfmove_clock: 0,
+            });
+        }
+
+        if let Some(checks) = checks {
+            uci.board.load_fen_checks(checks)?;
+        }
+
+        if let Some(halfmove_clock) = fen_parts.get(4) {
+            uci.board.halfmove_clock = match halfmove_clock.parse::<i16>() {
+                Ok(halfmove_clock) if halfmove_clock >= 0 => halfmove_clock,
+                _ => {
+                    return Err(LotisaError::InvalidFen(format!(
+                        "invalid halfmove clock \"{}\"",
+                        halfmove_clock
+                    )))
+                }
+            };
+        }
+
+        if let Some(fullmove_number) = fen_parts.get(5) {
+            uci.board.fullmove_number = fullmove_number.parse::<i16>().map_err(|_| {
+                LotisaError::InvalidFen(format!("invalid fullmove number \"{}\"", fullmove_number))
+            })?;
+        }
+
+        uci.board.rehash();
+
+        Ok(uci)
+    }
+
+    // The piece type and team of a FEN letter, with the team from a "(2)" suffix if it had one.
+    fn get_fen_piece(&self, letter: char, team: Option<i16>) -> Result<(i16, i16), LotisaError> {
+        let team = match team {
+            Some(team) if team >= self.teams => {
+                return Err(LotisaError::InvalidFen(format!(
+                    "team {} doesn't exist with {} teams",
+                    team, self.teams
+                )))
+            }
+            Some(team) => team,
+            None if letter.is_ascii_uppercase() => 0,
+            None => 1,
+        };
+        let piece_type = self
+            .piece_lookup
+            .get_piece_type(letter.to_ascii_lowercase())
+            .filter(|piece_type| *piece_type < self.piece_types)
+            .ok_or_else(|| LotisaError::InvalidFen(format!("unknown piece '{}'", letter)))?;
+
+        Ok((piece_type, team))
+    }
+
+    // Reads the checks each team has left ("3+3"), or the checks each team has given ("+1+0").
+    fn load_fen_checks(&mut self, fen: &str) -> Result<(), LotisaError> {
+        let invalid = || LotisaError::InvalidFen(format!("invalid checks \"{}\"", fen));
+        let remaining_checks = match &mut self.remaining_checks {
+            Some(remaining_checks) => remaining_checks,
+            None => {
+                return Err(LotisaError::InvalidFen(
+                    "this board doesn't count checks".to_string(),
+                ))
+            }
+        };
+
+        let (given, checks) = match fen.strip_prefix('+') {
+            Some(checks) => (true, checks),
+            None => (false, fen),
+        };
+        let checks = checks
+            .split('+')
+            .map(|checks| checks.parse::<i16>().map_err(|_| invalid()))
+            .collect::<Result<Vec<_>, _>>()?;
+        if checks.len() != remaining_checks.len() {
+            return Err(invalid());
+        }
+
+        for (remaining, checks) in remaining_checks.iter_mut().zip(checks) {
+            *remaining = if given { *remaining - checks } else { checks };
+        }
+
+        Ok(())
+    }
+
+    // Fills the pockets with the pieces between the brackets of a placement like "RNBQKBNR[Qnp]".
+    fn load_fen_pockets(&mut self, fen: &str) -> Result<(), LotisaError> {
+        if self.pockets.is_none() {
+            return Err(LotisaError::InvalidFen(
+                "this board doesn't have pockets".to_string(),
+            ));
+        }
+
+        for square in parse_fen_row(fen)? {
+            match square {
+                FenSquare::Piece { letter, team, .. } => {
+                    let (piece_type, team) = self.get_fen_piece(letter, team)?;
+                    self.add_to_pocket(team, piece_type, 1);
+                }
+                FenSquare::Empty(_) => {
+                    return Err(LotisaError::InvalidFen(format!(
+                        "invalid pocket \"{}\"",
+                        fen
+                    )))
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Fills an empty board with the pieces of a FEN's placement field.
+    fn load_fen_placement(&mut self, fen: &str) -> Result<(), LotisaError> {
+        let mut pieces: Vec<PersistentPieceInfo> = Vec::with_capacity(32);
+
+        let fen_chunks = fen.split("/").collect::<Vec<_>>();
+        if fen_chunks.len() != self.rows as usize {
+            return Err(LotisaError::InvalidFen(format!(
+                "expected {} rows, found {}",
+                self.rows,
+                fen_chunks.len()
+            )));
+        }
+
+        for (row_ind, chunk) in fen_chunks.into_iter().enumerate() {
+            let invalid_row = || {
+                LotisaError::InvalidFen(format!(
+                    "row \"{}\" doesn't have {} columns",
+                    chunk, self.cols
+                ))
+            };
+
+            let mut col_ind: i16 = 0;
+            for square in parse_fen_row(chunk)? {
+                let (letter, team, promoted) = match square {
+                    FenSquare::Empty(empty_spaces) => {
+                        col_ind += empty_spaces;
+                        continue;
+                    }
+                    FenSquare::Piece {
+                        letter,
+                        team,
+                        promoted,
+                    } => (letter, team, promoted),
+                };
+                if col_ind >= self.cols {
+                    return Err(invalid_row());
+                }
+
+                let (piece_type, team) = self.get_fen_piece(letter, team)?;
+                let piece = self.get_piece_value(piece_type, team);
+                let piece_pos = ((row_ind as i16 + self.buffer_amount) * self.row_gap)
+                    + col_ind
+                    + (self.buffer_amount / 2);
+                self.state[piece_pos as usize] = piece;
+                col_ind += 1;
+
+                pieces.push(PersistentPieceInfo {
+                    pos: piece_pos,
+                    // Pawns on their own back rank (like in Horde) can double move too.
+                    first_move: if piece_type == 0 {
+                        let config = &self.pawn_configs[team as usize];
+                        let rank = config.get_rank(self, piece_pos);
+                        config.is_double_move_rank(rank) || rank == 1
+                    } else {
+                        true
+                    },
+                    promoted,
+                });
+            }
+
+            if col_ind != self.cols {
+                return Err(invalid_row());
+            }
+        }
+
+        self.pieces = pieces;
+
+        Ok(())
+    }
+
+    // The index of the king of `team` (its piece that can castle) in `pieces`, with `first_move` or not.
+    fn find_king(&self, team: i16) -> Option<usize> {
+        self.pieces.iter().position(|piece| {
+            let PieceInfo {
+                piece_type,
+                team: piece_team,
+                ..
+            } = self.get_piece_info(piece.pos);
+            piece_team == team
+                && self
+                    .piece_lookup
+                    .lookup(piece_type)
+                    .get_castling_piece()
+                    .is_some()
+        })
+    }
+
+    // The rooks of `team` (the pieces its king castles with) on the same row as `king_pos`.
+    fn get_castling_rooks(&self, team: i16, king_pos: i16) -> Vec<usize> {
+        let PieceInfo { piece_type, .. } = self.get_piece_info(king_pos);
+        let rook = match self.piece_lookup.lookup(piece_type).get_castling_piece() {
+            Some(rook_type) => self.get_piece_value(rook_type, team),
+            None => return vec![],
+        };
+        let row = self.get_row(king_pos);
+        (0..self.pieces.len())
+            .filter(|index| {
+                let pos = self.pieces[*index].pos;
+                self.state[pos as usize] == rook && self.get_row(pos) == row
+            })
+            .collect()
+    }
+
+    // Of `rooks`, the farthest one from `king_pos` in `direction`.
+    fn get_outermost_rook(&self, rooks: &[usize], king_pos: i16, direction: i16) -> Option<usize> {
+        rooks
+            .iter()
+            .copied()
+            .filter(|index| (self.pieces[*index].pos - king_pos) * direction > 0)
+            .max_by_key(|index| (self.pieces[*index].pos - king_pos) * direction)
+    }
+
+    /*
+        Gives the king of `team` (and the outermost rook in `direction` on the king's row) back their first move.
+        `direction` is 1 for kingside and -1 for queenside.
+    */
+    fn add_castling_right(&mut self, team: i16, direction: i16) {
+        let king_index = match self.find_king(team) {
+            Some(king_index) => king_index,
+            None => return,
+        };
+
+        let king_pos = self.pieces[king_index].pos;
+        let rooks = self.get_castling_rooks(team, king_pos);
+        if let Some(rook_index) = self.get_outermost_rook(&rooks, king_pos, direction) {
+            self.pieces[king_index].first_move = true;
+            self.pieces[rook_index].first_move = true;
+        }
+    }
+
+    // Like `add_castling_right`, but for the rook on the king's row in column `col`.
+    fn add_castling_file(&mut self, team: i16, col: i16) {
+        let king_index = match self.find_king(team) {
+            Some(king_index) => king_index,
+            None => return,
+        };
+
+        let king_pos = self.pieces[king_index].pos;
+        let rooks = self.get_castling_rooks(team, king_pos);
+        let rook_index = rooks.into_iter().find(|index| {
+            let pos = self.pieces[*index].pos;
+            self.get_col(pos, self.get_row(pos)) - (self.buffer_amount / 2) == col
+        });
+        if let Some(rook_index) = rook_index {
+            self.pieces[king_index].first_move = true;
+            self.pieces[rook_index].first_move = true;
+        }
+    }
+
+    /*
+        Castling rights in X-FEN: "K" and "Q" for the outermost rooks, and the rook's file for any other rook (which only happens in Chess960).
+        Standard positions come out the same as in a normal FEN.
+    */
+    pub fn get_castling_rights(&self, team: i16) -> String {
+        let king_pos = match self.find_king(team) {
+            Some(king_index) if self.pieces[king_index].first_move => self.pieces[king_index].pos,
+            _ => return String::new(),
+        };
+
+        let rooks = self.get_castling_rooks(team, king_pos);
+
+        let mut rights = String::new();
+        for (direction, letter) in [(1, 'k'), (-1, 'q')] {
+            let outermost = self.get_outermost_rook(&rooks, king_pos, direction);
+            for index in &rooks {
+                let piece = self.pieces[*index];
+                if !piece.first_move || (piece.pos - king_pos) * direction <= 0 {
+                    continue;
+                }
+
+                if Some(*index) == outermost {
+                    rights.push(letter);
+                } else {
+                    let col = self.get_col(piece.pos, self.get_row(piece.pos));
+                    rights.push_str(&encode_file(col - (self.buffer_amount / 2)));
+                }
+            }
+        }
+
+        if team == 0 {
+            rights.to_ascii_uppercase()
+        } else {
+            rights
+        }
+    }
+
+    // The FEN letter of a piece: uppercase for team 0, lowercase for team 1, and lowercase with a "(2)" suffix for the other teams.
+    fn get_fen_letter(&self, piece_type: i16, team: i16) -> String {
+        let piece_char = self.piece_lookup.get_letter(piece_type).unwrap_or('?');
+        match team {
+            0 => piece_char.to_ascii_uppercase().to_string(),
+            1 => piece_char.to_string(),
+            _ => format!("{}({})", piece_char, team),
+        }
+    }
+
+    pub fn to_fen(&self) -> String {
+        let mut rows: Vec<String> = Vec::with_capacity(self.rows as usize);
+        for row in self.buffer_amount..(self.rows + self.buffer_amount) {
+            let mut fen_row = String::new();
+            let mut empty_spaces = 0;
+            for col in (self.buffer_amount / 2)..(self.cols + (self.buffer_amount / 2)) {
+                let pos = (row * self.row_gap) + col;
+                let piece = self.state[pos as usize];
+                if piece == 1 {
+                    empty_spaces += 1;
+                    continue;
+                }
+
+                if empty_spaces > 0 {
+                    fen_row.push_str(&empty_spaces.to_string());
+                    empty_spaces = 0;
+                }
+
+                let PieceInfo {
+                    piece_type, team, ..
+                } = self.get_piece_info(pos);
+                fen_row.push_str(&self.get_fen_letter(piece_type, team));
+                // Promoted pieces only matter when they can be captured into a pocket.
+                let is_promoted = self
+                    .pieces
+                    .iter()
+                    .any(|piece| piece.pos == pos && piece.promoted);
+                if self.pockets.is_some() && is_promoted {
+                    fen_row.push('~');
+                }
+            }
+
+            if empty_spaces > 0 {
+                fen_row.push_str(&empty_spaces.to_string());
+            }
+            rows.push(fen_row);
+        }
+
+        let mut placement = rows.join("/");
+        if let Some(pockets) = &self.pockets {
+            placement.push('[');
+            for (team, pocket) in pockets.iter().enumerate() {
+                for (piece_type, count) in pocket.iter().enumerate() {
+                    let letter = self.get_fen_letter(piece_type as i16, team as i16);
+                    placement.push_str(&letter.repeat((*count).max(0) as usize));
+                }
+            }
+            placement.push(']');
+        }
+
+        let moving_team = match self.moving_team {
+            0 => "w".to_string(),
+            1 => "b".to_string(),
+            team => team.to_string(),
+        };
+
+        let mut castling = format!(
+            "{}{}",
+            self.get_castling_rights(0),
+            self.get_castling_rights(1)
+        );
+        if castling.is_empty() {
+            castling = "-".to_string();
+        }
+
+        let en_passant = match self.history.last() {
+            Some(StoredMove { action, .. })
+                if action.piece_type == 0 && action.info == DOUBLE_MOVE =>
+            {
+                encode_uci_pos(self, (action.from + action.to) / 2, self.buffer_amount)
+            }
+            _ => "-".to_string(),
+        };
+
+        let checks = match &self.remaining_checks {
+            Some(remaining_checks) => format!(
+                " {}",
+                remaining_checks
+                    .iter()
+                    .map(|remaining| remaining.to_string())
+                    .collect::<Vec<_>>()
+                    .join("+")
+            ),
+            None => String::new(),
+        };
+
+        // Standard boards keep to standard FEN, anything else says its size so it can be loaded again.
+        let header = if self.rows == 8 && self.cols == 8 && self.teams == 2 {
+            String::new()
+        } else if self.teams == 2 {
+            format!("{}x{} ", self.cols, self.rows)
+        } else {
+            format!("{}x{}:{} ", self.cols, self.rows, self.teams)
+        };
+
+        format!(
+            "{}{} {} {} {}{} {} {}",
+            header,
+            placement,
+            moving_team,
+            castling,
+            en_passant,
+            checks,
+            self.halfmove_clock,
+            self.fullmove_number
+        )
+    }
+}
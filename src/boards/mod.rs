@@ -1,9 +1,17 @@
 mod board;
+mod chess960;
+mod fen;
 mod movegen;
 mod perft;
+mod polyglot;
+mod variant;
 mod zobrist;
 
 pub use board::*;
+pub use chess960::*;
+pub use fen::*;
 pub use movegen::*;
 pub use perft::*;
+pub use polyglot::*;
+pub use variant::*;
 pub use zobrist::*;
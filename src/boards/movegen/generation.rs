@@ -1,13 +1,6 @@
-use std::collections::HashMap;
+use crate::boards::{Action, Board, PersistentPieceInfo};
 
-use fnv::FnvHashMap;
-
-use crate::boards::{Action, ActionType, Board, PersistentPieceInfo};
-
-use super::{
-    piece_types::{BishopPiece, KingPiece, KnightPiece, PawnPiece, Piece, QueenPiece, RookPiece},
-    Restrictor,
-};
+use super::piece_types::Royalty;
 
 pub struct PieceGenInfo {
     pub pos: i16,
@@ -38,36 +31,88 @@ pub fn generate_moves(board: &mut Board, required_team: i16) -> Vec<Action> {
         piece_trait.add_actions(&mut actions, board, &piece_info);
     }
 
+    add_drop_actions(&mut actions, board, required_team);
+
     actions
 }
 
-pub fn in_check(board: &mut Board, moving_team: i16, row_gap: i16) -> bool {
-    let king = board.get_piece_value(5, moving_team);
-    let king = board
-        .pieces
-        .iter()
-        .find(|piece| board.state[piece.pos as usize] == king)
-        .map(|piece| piece.pos)
-        .unwrap();
-    let king_vec = vec![king];
+/*
+    Drops of every piece in the pocket of `team` onto every empty square.
+    Pawns can't be dropped onto their team's back rank or anywhere they'd promote (see `PawnConfig::can_drop_on`).
+*/
+pub fn add_drop_actions(actions: &mut Vec<Action>, board: &Board, team: i16) {
+    let pocket = match &board.pockets {
+        Some(pockets) => &pockets[team as usize],
+        None => return,
+    };
+
+    let config = &board.pawn_configs[team as usize];
+    for (piece_type, count) in pocket.iter().enumerate() {
+        if *count <= 0 {
+            continue;
+        }
+
+        for (pos, state) in board.state.iter().enumerate() {
+            let pos = pos as i16;
+            if *state != 1 {
+                continue;
+            }
+
+            if piece_type == 0 && !config.can_drop_on(board, pos) {
+                continue;
+            }
+
+            actions.push(Action::drop(pos, team, piece_type as i16));
+        }
+    }
+}
+
+pub fn get_royalty(board: &Board, piece_type: i16) -> Royalty {
+    board.royalties[piece_type as usize]
+}
+
+// The positions of the royal pieces of `team`, along with their royalty.
+pub fn get_royal_pieces(board: &Board, team: i16) -> Vec<(i16, Royalty)> {
+    let mut royals: Vec<(i16, Royalty)> = Vec::with_capacity(1);
+    for piece in &board.pieces {
+        let state = board.state[piece.pos as usize];
+        if board.get_team(state) != team {
+            continue;
+        }
+
+        let royalty = get_royalty(board, board.get_piece_type(state, team));
+        if royalty.is_royal() {
+            royals.push((piece.pos, royalty));
+        }
+    }
+    royals
+}
+
+// The first royal piece of `team`, which is its king in most variants.
+pub fn get_king_pos(board: &Board, team: i16) -> Option<i16> {
+    get_royal_pieces(board, team).first().map(|(pos, _)| *pos)
+}
+
+// Whether any piece that isn't on `team` controls one of `targets`.
+pub fn is_attacked(board: &mut Board, team: i16, targets: &Vec<i16>, row_gap: i16) -> bool {
     for PersistentPieceInfo { pos, .. } in board.pieces.clone() {
         let pos_usize = pos as usize;
         let piece = board.state[pos_usize];
-        let team = board.get_team(piece);
-        if team == moving_team {
+        let piece_team = board.get_team(piece);
+        if piece_team == team {
             continue;
         }
 
-        let piece_type = board.get_piece_type(piece, team);
+        let piece_type = board.get_piece_type(piece, piece_team);
         let piece_info = PieceGenInfo {
             pos,
             row_gap,
-            team,
+            team: piece_team,
             piece_type,
         };
 
         let piece_handler = board.piece_lookup.lookup(piece_type).duplicate();
-        if piece_handler.can_control(board, &piece_info, &king_vec) {
+        if piece_handler.can_control(board, &piece_info, targets) {
             return true;
         }
     }
@@ -75,8 +120,38 @@ pub fn in_check(board: &mut Board, moving_team: i16, row_gap: i16) -> bool {
     false
 }
 
+/*
+    Teams without a royal piece (like white in Horde, or after a king is captured in Antichess) are never in check.
+    Any royal piece being attacked is check, while shared royals (see `Royalty`) only are once all of them are.
+*/
+pub fn in_check(board: &mut Board, moving_team: i16, row_gap: i16) -> bool {
+    let royals = get_royal_pieces(board, moving_team);
+    if royals.is_empty() {
+        return false;
+    }
+
+    let royal = royals
+        .iter()
+        .filter(|(_, royalty)| *royalty == Royalty::Royal)
+        .map(|(pos, _)| *pos)
+        .collect::<Vec<_>>();
+    if !royal.is_empty() && is_attacked(board, moving_team, &royal, row_gap) {
+        return true;
+    }
+
+    let mut shared = royals
+        .iter()
+        .filter(|(_, royalty)| *royalty == Royalty::Shared)
+        .peekable();
+    shared.peek().is_some()
+        && shared.all(|(pos, _)| is_attacked(board, moving_team, &vec![*pos], row_gap))
+}
+
 pub fn generate_legal_moves(board: &mut Board, required_team: i16) -> Vec<Action> {
-    let Board { row_gap, .. } = board;
+    // Games a variant has already decided (like a king reaching the hill) don't have any moves left.
+    if board.win_conditions.duplicate().is_game_over(board) {
+        return vec![];
+    }
 
     let actions = generate_moves(board, required_team);
     let mut new_actions: Vec<Action> = vec![];
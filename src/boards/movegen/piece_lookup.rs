@@ -15,6 +15,8 @@
 
 use fnv::FnvHashMap;
 
+use crate::boards::FEN_PIECES;
+
 use super::{BishopPiece, KingPiece, KnightPiece, PawnPiece, Piece, QueenPiece, RookPiece};
 
 struct PieceMapInfo {
@@ -26,29 +28,70 @@ struct PieceMapInfo {
     king: Box<dyn Piece>,
 }
 
-pub trait PieceLookup {
+pub trait PieceLookup: Send {
     fn lookup(&self, piece_type: i16) -> &Box<dyn Piece>;
+
+    /*
+        The lowercase FEN letter of a piece type, which is uppercased for team 0.
+        Lookups with pieces past the default six need to override this (and `get_piece_type`) for FENs to name them.
+    */
+    fn get_letter(&self, piece_type: i16) -> Option<char> {
+        if piece_type < 0 {
+            return None;
+        }
+        FEN_PIECES.get(piece_type as usize).copied()
+    }
+
+    fn get_piece_type(&self, letter: char) -> Option<i16> {
+        FEN_PIECES
+            .iter()
+            .position(|piece| *piece == letter)
+            .map(|piece_type| piece_type as i16)
+    }
 }
 
 pub type PieceMap = FnvHashMap<i16, Box<dyn Piece>>;
 
 pub struct PieceMapLookup {
     pub map: PieceMap,
+    // FEN letters for piece types that don't use the default ones.
+    pub letters: FnvHashMap<i16, char>,
 }
 
 impl PieceMapLookup {
     pub fn new(map: PieceMap) -> PieceMapLookup {
-        PieceMapLookup { map }
+        PieceMapLookup {
+            map,
+            letters: FnvHashMap::default(),
+        }
     }
 
     pub fn template(mut map: PieceMap, edit: Box<dyn Fn(&mut PieceMap) -> ()>) -> PieceMapLookup {
         edit(&mut map);
-        PieceMapLookup { map }
+        PieceMapLookup::new(map)
+    }
+
+    pub fn with_letter(mut self, piece_type: i16, letter: char) -> PieceMapLookup {
+        self.letters.insert(piece_type, letter.to_ascii_lowercase());
+        self
+    }
+
+    /*
+        Registers a piece (like a `BetzaPiece`) under a piece type, replacing whatever was there.
+    */
+    pub fn with_piece(
+        mut self,
+        piece_type: i16,
+        letter: char,
+        piece: Box<dyn Piece>,
+    ) -> PieceMapLookup {
+        self.map.insert(piece_type, piece);
+        self.with_letter(piece_type, letter)
     }
 
     pub fn default_map(row_gap: i16) -> PieceMap {
         let mut map: PieceMap = FnvHashMap::with_capacity_and_hasher(6, Default::default());
-        map.insert(0, Box::new(PawnPiece) as Box<dyn Piece>);
+        map.insert(0, Box::new(PawnPiece::default()) as Box<dyn Piece>);
         map.insert(1, Box::new(KnightPiece::new(row_gap)) as Box<dyn Piece>);
         map.insert(2, Box::new(BishopPiece::new(row_gap)) as Box<dyn Piece>);
         map.insert(3, Box::new(RookPiece::new(row_gap)) as Box<dyn Piece>);
@@ -69,6 +112,30 @@ impl PieceLookup for PieceMapLookup {
     fn lookup(&self, piece_type: i16) -> &Box<dyn Piece> {
         self.map.get(&piece_type).unwrap()
     }
+
+    fn get_letter(&self, piece_type: i16) -> Option<char> {
+        if let Some(letter) = self.letters.get(&piece_type) {
+            return Some(*letter);
+        }
+        if !self.map.contains_key(&piece_type) {
+            return None;
+        }
+        FEN_PIECES.get(piece_type as usize).copied()
+    }
+
+    fn get_piece_type(&self, letter: char) -> Option<i16> {
+        if let Some((piece_type, _)) = self.letters.iter().find(|(_, other)| **other == letter) {
+            return Some(*piece_type);
+        }
+
+        // A default letter only counts if its piece type hasn't been given a different one.
+        let piece_type = FEN_PIECES.iter().position(|piece| *piece == letter)? as i16;
+        if self.map.contains_key(&piece_type) && !self.letters.contains_key(&piece_type) {
+            Some(piece_type)
+        } else {
+            None
+        }
+    }
 }
 
 pub struct DefaultPieceLookup {
@@ -79,7 +146,7 @@ impl DefaultPieceLookup {
     fn new(row_gap: i16) -> Self {
         DefaultPieceLookup {
             info: PieceMapInfo {
-                pawn: Box::new(PawnPiece),
+                pawn: Box::new(PawnPiece::default()),
                 knight: Box::new(KnightPiece::new(row_gap)),
                 bishop: Box::new(BishopPiece::new(row_gap)),
                 rook: Box::new(RookPiece::new(row_gap)),
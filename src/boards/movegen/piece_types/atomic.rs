@@ -0,0 +1,143 @@
+use crate::boards::{
+    hash_piece, Action, Board, PieceGenInfo, ResetSquare, StoredMove, StoredMovePieceChange,
+    StoredMoveType,
+};
+
+use super::{PawnConfig, Piece, Royalty};
+
+/*
+    Wraps a piece for Atomic, where every capture explodes: the capturing piece is removed along with every piece around the square it landed on, except for pawns.
+    The explosion is added onto the stored move of the wrapped piece, so the default `undo_move` brings everything back.
+*/
+pub struct AtomicPiece {
+    piece: Box<dyn Piece>,
+}
+
+impl AtomicPiece {
+    pub fn new(piece: Box<dyn Piece>) -> Self {
+        AtomicPiece { piece }
+    }
+}
+
+// The squares next to `pos`, which are caught in an explosion there.
+pub fn get_adjacent_squares(board: &Board, pos: i16) -> [i16; 8] {
+    let row_gap = board.row_gap;
+    [
+        pos - row_gap - 1,
+        pos - row_gap,
+        pos - row_gap + 1,
+        pos - 1,
+        pos + 1,
+        pos + row_gap - 1,
+        pos + row_gap,
+        pos + row_gap + 1,
+    ]
+}
+
+/*
+    Removes the piece on `pos` for an explosion, storing it in the last move so it can be undone.
+    The piece that captured has to be put back before it's moved back, so it goes to the front of the stored pieces.
+*/
+fn explode_square(board: &mut Board, pos: i16, is_capturer: bool) {
+    let state = board.state[pos as usize];
+    let index = match board.pieces.iter().position(|piece| piece.pos == pos) {
+        Some(index) => index,
+        None => return,
+    };
+    let info = board.pieces.swap_remove(index);
+    board.state[pos as usize] = 1;
+    board.hash ^= hash_piece(board, pos, state, info.first_move);
+
+    if let StoredMoveType::Standard { states, pieces } =
+        &mut board.history.last_mut().unwrap().move_type
+    {
+        let removed = StoredMovePieceChange::PieceRemove { info };
+        if is_capturer {
+            pieces.insert(0, removed);
+        } else {
+            states.push(ResetSquare { pos, state });
+            pieces.push(removed);
+        }
+    }
+}
+
+pub fn explode(board: &mut Board, pos: i16) {
+    // The square the capture landed on already gets reset by the capture itself.
+    explode_square(board, pos, true);
+
+    for target in get_adjacent_squares(board, pos) {
+        let state = board.state[target as usize];
+        if state <= 1 {
+            continue;
+        }
+
+        let team = board.get_team(state);
+        if board.get_piece_type(state, team) != 0 {
+            explode_square(board, target, false);
+        }
+    }
+}
+
+impl Piece for AtomicPiece {
+    fn can_control(
+        &self,
+        board: &mut Board,
+        piece_info: &PieceGenInfo,
+        targets: &Vec<i16>,
+    ) -> bool {
+        self.piece.can_control(board, piece_info, targets)
+    }
+
+    fn add_actions(&self, actions: &mut Vec<Action>, board: &mut Board, piece_info: &PieceGenInfo) {
+        self.piece.add_actions(actions, board, piece_info);
+    }
+
+    fn get_material_value(&self) -> i32 {
+        self.piece.get_material_value()
+    }
+
+    fn get_icon(&self) -> &str {
+        self.piece.get_icon()
+    }
+
+    fn get_royalty(&self) -> Royalty {
+        self.piece.get_royalty()
+    }
+
+    fn is_castling(&self, action: &Action) -> bool {
+        self.piece.is_castling(action)
+    }
+
+    fn get_castling_piece(&self) -> Option<i16> {
+        self.piece.get_castling_piece()
+    }
+
+    fn get_pawn_config(&self, team: i16) -> Option<PawnConfig> {
+        self.piece.get_pawn_config(team)
+    }
+
+    fn make_move(&self, board: &mut Board, action: Action) {
+        self.piece.make_move(board, action);
+
+        // Castling is written as the king capturing its rook, so we look for a removed piece instead of `action.capture`.
+        let captured = match &board.history.last().unwrap().move_type {
+            StoredMoveType::Standard { pieces, .. } => pieces
+                .iter()
+                .any(|piece| matches!(piece, StoredMovePieceChange::PieceRemove { .. })),
+            StoredMoveType::Custom { .. } => false,
+        };
+        if captured {
+            explode(board, action.to);
+        }
+    }
+
+    fn undo_move(&self, board: &mut Board, undo: &StoredMove) {
+        self.piece.undo_move(board, undo);
+    }
+
+    fn duplicate(&self) -> Box<dyn Piece> {
+        Box::new(AtomicPiece {
+            piece: self.piece.duplicate(),
+        })
+    }
+}
@@ -0,0 +1,537 @@
+use super::{
+    add_actions_delta, add_actions_sliding, can_control_delta, can_control_sliding, Piece,
+};
+use crate::{
+    boards::{Action, ActionType, Board, PieceGenInfo},
+    error::LotisaError,
+};
+
+/*
+    Pieces defined by a Betza string instead of a Rust struct, so variants can add pieces without writing any code.
+    A definition is a list of atoms (like "N" or "R"), each with lowercase modifiers in front of it:
+
+    - Atoms: W (wazir), F (ferz), D (dabbaba), N (knight), A (alfil), H (threeleaper), C or L (camel), Z (zebra), G (tripper),
+      and the compounds K (WF), R (WW), B (FF) and Q (RB).
+    - Repeating an atom makes it a rider ("NN" is the nightrider), and a number after it limits how far it rides ("R4").
+    - m and c make an atom move-only or capture-only, and i only lets it be used on the piece's first move.
+    - p makes a rider hop over exactly one piece first (a cannon), and g makes it land right behind that piece (a grasshopper).
+    - f, b, l, r, v and s only keep the forward, backward, left, right, vertical or sideways directions.
+      "fl", "fr", "bl" and "br" are single diagonal directions, and "ff", "bb", "ll" and "rr" are the narrow (vertical or sideways) halves.
+
+    So the Xiangqi cannon is "mRcpR", the Berolina pawn is "mfFcfWimfF2" and the Archbishop is "BN".
+    Forward is up the board for even teams and down for odd teams.
+*/
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BetzaMode {
+    MoveCapture,
+    Move,
+    Capture,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BetzaHop {
+    // Moves or captures anywhere past the hurdle (a cannon).
+    Cannon,
+    // Lands on the square right past the hurdle (a grasshopper).
+    Grasshopper,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BetzaAtom {
+    // (column, row) steps, where a positive row is forward.
+    pub vectors: Vec<(i16, i16)>,
+    // How many steps the atom can take in one direction, with None for an unlimited rider.
+    pub range: Option<i16>,
+    pub mode: BetzaMode,
+    pub hop: Option<BetzaHop>,
+    pub initial: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct BetzaDefinition {
+    pub atoms: Vec<BetzaAtom>,
+    pub value: i32,
+    pub icon: String,
+}
+
+fn get_atom_vectors(atom: char) -> Option<(Vec<(i16, i16)>, bool)> {
+    // The leap of each atom, and whether the atom is already a rider.
+    let (leaps, rider): (Vec<(i16, i16)>, bool) = match atom {
+        'W' => (vec![(1, 0)], false),
+        'F' => (vec![(1, 1)], false),
+        'D' => (vec![(2, 0)], false),
+        'N' => (vec![(2, 1)], false),
+        'A' => (vec![(2, 2)], false),
+        'H' => (vec![(3, 0)], false),
+        'C' | 'L' => (vec![(3, 1)], false),
+        'Z' => (vec![(3, 2)], false),
+        'G' => (vec![(3, 3)], false),
+        'K' => (vec![(1, 0), (1, 1)], false),
+        'R' => (vec![(1, 0)], true),
+        'B' => (vec![(1, 1)], true),
+        'Q' => (vec![(1, 0), (1, 1)], true),
+        _ => return None,
+    };
+
+    let mut vectors = vec![];
+    for (col, row) in leaps {
+        for (col, row) in [(col, row), (row, col)] {
+            for (col_sign, row_sign) in [(1, 1), (1, -1), (-1, 1), (-1, -1)] {
+                let vector = (col * col_sign, row * row_sign);
+                if !vectors.contains(&vector) {
+                    vectors.push(vector);
+                }
+            }
+        }
+    }
+
+    Some((vectors, rider))
+}
+
+fn matches_direction(direction: &str, (col, row): (i16, i16)) -> bool {
+    let vertical = row.abs() > col.abs();
+    let sideways = col.abs() > row.abs();
+    match direction {
+        "f" => row > 0,
+        "b" => row < 0,
+        "l" => col < 0,
+        "r" => col > 0,
+        "v" => vertical,
+        "s" => sideways,
+        "ff" => row > 0 && vertical,
+        "bb" => row < 0 && vertical,
+        "ll" => col < 0 && sideways,
+        "rr" => col > 0 && sideways,
+        "fl" => row > 0 && col < 0,
+        "fr" => row > 0 && col > 0,
+        "bl" => row < 0 && col < 0,
+        "br" => row < 0 && col > 0,
+        _ => false,
+    }
+}
+
+/*
+    A rough material value, since a definition doesn't say how strong it is: leapers are worth about 375 per direction (a knight is 3000),
+    and riders more depending on how far they go.
+*/
+fn estimate_value(atoms: &Vec<BetzaAtom>) -> i32 {
+    let mut value = 0;
+    for atom in atoms {
+        for (col, row) in &atom.vectors {
+            let mut vector_value = match atom.range {
+                None if *col == 0 || *row == 0 => 1250,
+                None => 800,
+                Some(range) => (375 * range as i32).min(1000),
+            };
+            if atom.mode != BetzaMode::MoveCapture || atom.hop.is_some() {
+                vector_value /= 2;
+            }
+            if atom.initial {
+                vector_value /= 4;
+            }
+            value += vector_value;
+        }
+    }
+    value
+}
+
+impl BetzaDefinition {
+    pub fn parse(betza: &str) -> Result<BetzaDefinition, LotisaError> {
+        let invalid =
+            |reason: &str| LotisaError::InvalidPiece(format!("{} in \"{}\"", reason, betza));
+
+        let mut atoms = vec![];
+        let mut chars = betza.chars().peekable();
+        while chars.peek().is_some() {
+            let mut modifiers = String::new();
+            while let Some(modifier) = chars.next_if(|char| char.is_ascii_lowercase()) {
+                modifiers.push(modifier);
+            }
+
+            let atom = chars.next().ok_or_else(|| invalid("missing atom"))?;
+            let (vectors, mut rider) = get_atom_vectors(atom)
+                .ok_or_else(|| invalid(&format!("unknown atom '{}'", atom)))?;
+
+            let mut range = if rider { None } else { Some(1) };
+            if chars.next_if_eq(&atom).is_some() {
+                rider = true;
+                range = None;
+            }
+
+            let mut digits = String::new();
+            while let Some(digit) = chars.next_if(|char| char.is_ascii_digit()) {
+                digits.push(digit);
+            }
+            if !digits.is_empty() {
+                let limit = digits
+                    .parse::<i16>()
+                    .map_err(|_| invalid(&format!("invalid range \"{}\"", digits)))?;
+                rider = true;
+                range = if limit == 0 { None } else { Some(limit) };
+            }
+
+            let mut directions: Vec<&str> = vec![];
+            let mut mode = BetzaMode::MoveCapture;
+            let (mut can_move, mut can_capture) = (false, false);
+            let mut hop = None;
+            let mut initial = false;
+
+            let mut index = 0;
+            while index < modifiers.len() {
+                let pair = modifiers.get(index..index + 2).unwrap_or("");
+                if matches!(pair, "ff" | "bb" | "ll" | "rr" | "fl" | "fr" | "bl" | "br") {
+                    directions.push(pair);
+                    index += 2;
+                    continue;
+                }
+
+                match &modifiers[index..index + 1] {
+                    "m" => can_move = true,
+                    "c" => can_capture = true,
+                    "i" => initial = true,
+                    "p" => hop = Some(BetzaHop::Cannon),
+                    "g" => hop = Some(BetzaHop::Grasshopper),
+                    direction @ ("f" | "b" | "l" | "r" | "v" | "s") => directions.push(direction),
+                    modifier => return Err(invalid(&format!("unknown modifier '{}'", modifier))),
+                }
+                index += 1;
+            }
+
+            if can_move && !can_capture {
+                mode = BetzaMode::Move;
+            } else if can_capture && !can_move {
+                mode = BetzaMode::Capture;
+            }
+            if hop.is_some() && !rider {
+                return Err(invalid(&format!("'{}' has to ride to hop", atom)));
+            }
+
+            let vectors = vectors
+                .into_iter()
+                .filter(|vector| {
+                    directions.is_empty()
+                        || directions
+                            .iter()
+                            .any(|direction| matches_direction(direction, *vector))
+                })
+                .collect::<Vec<_>>();
+            if vectors.is_empty() {
+                return Err(invalid(&format!(
+                    "'{}{}' has no directions",
+                    modifiers, atom
+                )));
+            }
+
+            atoms.push(BetzaAtom {
+                vectors,
+                range,
+                mode,
+                hop,
+                initial,
+            });
+        }
+
+        if atoms.is_empty() {
+            return Err(invalid("no atoms"));
+        }
+
+        Ok(BetzaDefinition {
+            value: estimate_value(&atoms),
+            atoms,
+            icon: "?".to_string(),
+        })
+    }
+
+    pub fn with_value(mut self, value: i32) -> BetzaDefinition {
+        self.value = value;
+        self
+    }
+
+    pub fn with_icon(mut self, icon: &str) -> BetzaDefinition {
+        self.icon = icon.to_string();
+        self
+    }
+
+    /*
+        The furthest the piece leaps in one step.
+        Moves are deltas on the board's state, so the board's buffer has to be at least this big for them to not wrap around the edges.
+    */
+    pub fn get_reach(&self) -> i16 {
+        self.atoms
+            .iter()
+            .flat_map(|atom| atom.vectors.iter())
+            .map(|(col, row)| col.abs().max(row.abs()))
+            .max()
+            .unwrap_or(1)
+    }
+
+    pub fn create(&self, row_gap: i16) -> BetzaPiece {
+        BetzaPiece {
+            atoms: self
+                .atoms
+                .iter()
+                .map(|atom| {
+                    // Index 0 is for teams moving up the board, and 1 is for teams moving down it.
+                    let deltas = [-row_gap, row_gap].map(|forward| {
+                        atom.vectors
+                            .iter()
+                            .map(|(col, row)| col + (row * forward))
+                            .collect::<Vec<_>>()
+                    });
+                    BetzaDeltas {
+                        deltas,
+                        range: atom.range,
+                        mode: atom.mode,
+                        hop: atom.hop,
+                        initial: atom.initial,
+                    }
+                })
+                .collect(),
+            value: self.value,
+            icon: self.icon.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct BetzaDeltas {
+    deltas: [Vec<i16>; 2],
+    range: Option<i16>,
+    mode: BetzaMode,
+    hop: Option<BetzaHop>,
+    initial: bool,
+}
+
+impl BetzaDeltas {
+    fn can_move(&self) -> bool {
+        self.mode != BetzaMode::Capture
+    }
+
+    fn can_capture(&self) -> bool {
+        self.mode != BetzaMode::Move
+    }
+}
+
+fn push_action(
+    actions: &mut Vec<Action>,
+    atom: &BetzaDeltas,
+    piece_info: &PieceGenInfo,
+    target: i16,
+    action_type: ActionType,
+) {
+    let capture = match action_type {
+        ActionType::MOVE if atom.can_move() => false,
+        ActionType::CAPTURE if atom.can_capture() => true,
+        _ => return,
+    };
+
+    actions.push(Action {
+        from: piece_info.pos,
+        to: target,
+        piece_type: piece_info.piece_type,
+        capture,
+        info: 0,
+        team: piece_info.team,
+    });
+}
+
+/*
+    Riders with a limited range or that can only move or only capture, which `add_actions_sliding` doesn't handle.
+*/
+fn add_actions_ranged(
+    actions: &mut Vec<Action>,
+    atom: &BetzaDeltas,
+    deltas: &Vec<i16>,
+    board: &Board,
+    piece_info: &PieceGenInfo,
+) {
+    let PieceGenInfo { pos, team, .. } = *piece_info;
+    let range = atom.range.unwrap_or(i16::MAX);
+
+    for delta in deltas {
+        let mut current_pos = pos;
+        for _ in 0..range {
+            current_pos += delta;
+            let action_type = board.can_move_capture(current_pos, team);
+            let blocked = !matches!(action_type, ActionType::MOVE);
+            push_action(actions, atom, piece_info, current_pos, action_type);
+            if blocked {
+                break;
+            }
+        }
+    }
+}
+
+/*
+    The square after the first piece in the direction of `delta`, which is where a hopper's moves start.
+*/
+fn find_hurdle(board: &Board, pos: i16, delta: i16, range: i16) -> Option<i16> {
+    let mut current_pos = pos;
+    for _ in 0..range {
+        current_pos += delta;
+        match board.can_control(current_pos) {
+            ActionType::MOVE => {}
+            ActionType::CAPTURE => return Some(current_pos + delta),
+            ActionType::FAIL => return None,
+        }
+    }
+    None
+}
+
+fn add_actions_hopping(
+    actions: &mut Vec<Action>,
+    atom: &BetzaDeltas,
+    hop: BetzaHop,
+    deltas: &Vec<i16>,
+    board: &Board,
+    piece_info: &PieceGenInfo,
+) {
+    let PieceGenInfo { pos, team, .. } = *piece_info;
+    let range = atom.range.unwrap_or(i16::MAX);
+
+    for delta in deltas {
+        let mut current_pos = match find_hurdle(board, pos, *delta, range) {
+            Some(current_pos) => current_pos,
+            None => continue,
+        };
+
+        loop {
+            let action_type = board.can_move_capture(current_pos, team);
+            let blocked = !matches!(action_type, ActionType::MOVE);
+            push_action(actions, atom, piece_info, current_pos, action_type);
+            if blocked || hop == BetzaHop::Grasshopper {
+                break;
+            }
+            current_pos += delta;
+        }
+    }
+}
+
+fn can_control_ranged(
+    atom: &BetzaDeltas,
+    deltas: &[i16],
+    board: &Board,
+    piece_info: &PieceGenInfo,
+    targets: &[i16],
+) -> bool {
+    let range = atom.range.unwrap_or(i16::MAX);
+
+    for delta in deltas {
+        let mut current_pos = match atom.hop {
+            Some(_) => match find_hurdle(board, piece_info.pos, *delta, range) {
+                Some(current_pos) => current_pos,
+                None => continue,
+            },
+            None => piece_info.pos + delta,
+        };
+
+        let steps = match atom.hop {
+            Some(BetzaHop::Grasshopper) => 1,
+            Some(BetzaHop::Cannon) => i16::MAX,
+            None => range,
+        };
+        for _ in 0..steps {
+            match board.can_control(current_pos) {
+                ActionType::MOVE => {
+                    if targets.contains(&current_pos) {
+                        return true;
+                    }
+                }
+                ActionType::CAPTURE => {
+                    if targets.contains(&current_pos) {
+                        return true;
+                    }
+                    break;
+                }
+                ActionType::FAIL => break,
+            }
+            current_pos += delta;
+        }
+    }
+
+    false
+}
+
+fn is_first_move(board: &Board, pos: i16) -> bool {
+    board
+        .pieces
+        .iter()
+        .find(|piece| piece.pos == pos)
+        .is_some_and(|piece| piece.first_move)
+}
+
+#[derive(Clone)]
+pub struct BetzaPiece {
+    atoms: Vec<BetzaDeltas>,
+    value: i32,
+    icon: String,
+}
+
+impl Piece for BetzaPiece {
+    fn add_actions(&self, actions: &mut Vec<Action>, board: &mut Board, piece_info: &PieceGenInfo) {
+        let orientation = (piece_info.team % 2) as usize;
+        let mut first_move = None;
+
+        for atom in &self.atoms {
+            if atom.initial
+                && !*first_move.get_or_insert_with(|| is_first_move(board, piece_info.pos))
+            {
+                continue;
+            }
+
+            let deltas = &atom.deltas[orientation];
+            match (atom.hop, atom.range, atom.mode) {
+                (Some(hop), ..) => {
+                    add_actions_hopping(actions, atom, hop, deltas, board, piece_info)
+                }
+                (None, Some(1), BetzaMode::MoveCapture) => {
+                    add_actions_delta(actions, deltas, board, piece_info)
+                }
+                (None, None, BetzaMode::MoveCapture) => {
+                    add_actions_sliding(actions, deltas, board, piece_info)
+                }
+                _ => add_actions_ranged(actions, atom, deltas, board, piece_info),
+            }
+        }
+    }
+
+    fn can_control(
+        &self,
+        board: &mut Board,
+        piece_info: &PieceGenInfo,
+        targets: &Vec<i16>,
+    ) -> bool {
+        let orientation = (piece_info.team % 2) as usize;
+
+        for atom in &self.atoms {
+            if !atom.can_capture() || (atom.initial && !is_first_move(board, piece_info.pos)) {
+                continue;
+            }
+
+            let deltas = &atom.deltas[orientation];
+            let can_control = match (atom.hop, atom.range) {
+                (None, Some(1)) => can_control_delta(deltas, board, piece_info, targets),
+                (None, None) => can_control_sliding(deltas, board, piece_info, targets),
+                _ => can_control_ranged(atom, deltas, board, piece_info, targets),
+            };
+            if can_control {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    fn get_material_value(&self) -> i32 {
+        self.value
+    }
+
+    fn get_icon(&self) -> &str {
+        &self.icon
+    }
+
+    fn duplicate(&self) -> Box<dyn Piece> {
+        Box::new(self.clone())
+    }
+}
@@ -0,0 +1,102 @@
+use super::{BishopPiece, KnightPiece, Piece, RookPiece};
+use crate::boards::{Action, Board, PieceGenInfo};
+
+// The pieces that can be made by name in a variant file, like `piece = "archbishop"`.
+pub const COMPOUND_PIECES: [&str; 2] = ["archbishop", "chancellor"];
+
+/*
+    A piece that moves like any of the pieces it's made of, such as the Archbishop, which moves as both a Bishop and a Knight.
+    Moves are generated by each part in turn, so the parts shouldn't share any moves.
+*/
+pub struct CompoundPiece {
+    pieces: Vec<Box<dyn Piece>>,
+    material_value: i32,
+    icon: String,
+}
+
+impl CompoundPiece {
+    pub fn new(pieces: Vec<Box<dyn Piece>>, material_value: i32, icon: &str) -> Self {
+        CompoundPiece {
+            pieces,
+            material_value,
+            icon: icon.to_string(),
+        }
+    }
+
+    // Bishop and Knight, also called the Cardinal.
+    pub fn archbishop(row_gap: i16) -> Self {
+        CompoundPiece::new(
+            vec![
+                Box::new(BishopPiece::new(row_gap)),
+                Box::new(KnightPiece::new(row_gap)),
+            ],
+            8000,
+            "A",
+        )
+    }
+
+    // Rook and Knight, also called the Marshal.
+    pub fn chancellor(row_gap: i16) -> Self {
+        CompoundPiece::new(
+            vec![
+                Box::new(RookPiece::new(row_gap)),
+                Box::new(KnightPiece::new(row_gap)),
+            ],
+            9000,
+            "C",
+        )
+    }
+
+    pub fn from_name(name: &str, row_gap: i16) -> Option<Self> {
+        match name {
+            "archbishop" => Some(CompoundPiece::archbishop(row_gap)),
+            "chancellor" => Some(CompoundPiece::chancellor(row_gap)),
+            _ => None,
+        }
+    }
+
+    pub fn with_value(mut self, material_value: i32) -> Self {
+        self.material_value = material_value;
+        self
+    }
+
+    pub fn with_icon(mut self, icon: &str) -> Self {
+        self.icon = icon.to_string();
+        self
+    }
+}
+
+impl Piece for CompoundPiece {
+    fn add_actions(&self, actions: &mut Vec<Action>, board: &mut Board, piece_info: &PieceGenInfo) {
+        for piece in &self.pieces {
+            piece.add_actions(actions, board, piece_info);
+        }
+    }
+
+    fn can_control(
+        &self,
+        board: &mut Board,
+        piece_info: &PieceGenInfo,
+        targets: &Vec<i16>,
+    ) -> bool {
+        self.pieces
+            .iter()
+            .any(|piece| piece.can_control(board, piece_info, targets))
+    }
+
+    fn get_material_value(&self) -> i32 {
+        self.material_value
+    }
+
+    fn get_icon(&self) -> &str {
+        &self.icon
+    }
+
+    fn duplicate(&self) -> Box<dyn Piece> {
+        Box::new(CompoundPiece {
+            pieces: self.pieces.iter().map(|piece| piece.duplicate()).collect(),
+            material_value: self.material_value,
+            icon: self.icon.clone(),
+        })
+    }
+}
@@ -1,12 +1,30 @@
-use super::{base_make_move, can_control_delta, add_actions_delta, MakeMoveResults, Piece};
+use super::{add_actions_delta, base_make_move, Piece, Royalty};
 use crate::boards::{
-    in_check, Action, ActionType, Board, PersistentPieceInfo, PieceGenInfo, PieceInfo, ResetSquare,
+    hash_piece, in_check, Action, Board, PersistentPieceInfo, PieceGenInfo, PieceInfo, ResetSquare,
     StoredMove, StoredMovePieceChange, StoredMoveType,
 };
 
 const NORMAL_MOVE: i16 = 0;
 const CASTLING_MOVE: i16 = 1;
 
+// The piece type kings castle with.
+const CASTLING_PIECE: i16 = 3;
+
+/*
+    Castling ends the same way in standard chess and Chess960: the king lands on the c or g file and the rook next to it, towards the center.
+    On other board sizes, those files are counted from the edges of the board.
+    Returns the new king and rook positions.
+*/
+pub fn get_castling_squares(board: &Board, king_pos: i16, rook_pos: i16) -> (i16, i16) {
+    let row = board.get_row(king_pos);
+    let row_start = (row * board.row_gap) + (board.buffer_amount / 2);
+    if rook_pos > king_pos {
+        (row_start + board.cols - 2, row_start + board.cols - 3)
+    } else {
+        (row_start + 2, row_start + 3)
+    }
+}
+
 fn add_actions_castling(
     actions: &mut Vec<Action>,
     sliders: &Vec<i16>,
@@ -41,75 +59,79 @@ fn add_actions_castling(
     let row_gap = board.row_gap;
 
     for slider in sliders {
+        // The first piece in this direction has to be a rook of ours that hasn't moved yet.
+        let mut rook_pos = pos + slider;
+        while board.state[rook_pos as usize] == 1 {
+            rook_pos += slider;
+        }
+        if board.state[rook_pos as usize] == 0 {
+            continue;
+        }
+
+        let PieceInfo {
+            team: rook_team,
+            piece_type: rook_piece_type,
+            ..
+        } = board.get_piece_info(rook_pos);
+        if rook_team != team || rook_piece_type != CASTLING_PIECE {
+            continue;
+        }
+
+        let can_castle = board
+            .pieces
+            .iter()
+            .any(|piece| piece.pos == rook_pos && piece.first_move);
+        if !can_castle {
+            continue;
+        }
+
+        // In Chess960, the king and rook can start on either side of their new squares, so everything they cross has to be empty.
+        let (king_to, rook_to) = get_castling_squares(board, pos, rook_pos);
+        let min_pos = pos.min(rook_pos).min(king_to).min(rook_to);
+        let max_pos = pos.max(rook_pos).max(king_to).max(rook_to);
+        let is_blocked = (min_pos..=max_pos)
+            .any(|square| square != pos && square != rook_pos && board.state[square as usize] != 1);
+        if is_blocked {
+            continue;
+        }
+
+        // Only the squares the king passes through (or lands on) need to be safe.
+        let king_dir = (king_to - pos).signum();
+        let mut targets = vec![];
         let mut current_pos = pos;
-        loop {
-            current_pos += slider;
-            match board.state[current_pos as usize] {
-                0 => {
-                    break;
-                }
-                1 => {
-                    let targets = vec![current_pos];
-                    let can_be_attacked = opposing_pieces.iter().any(|piece| {
-                        let PieceInfo {
-                            team: attacker_team,
-                            piece_type: attacker_piece_type,
-                            ..
-                        } = board.get_piece_info(piece.pos);
-                        let piece_trait =
-                            board.piece_lookup.lookup(piece_info.piece_type).duplicate();
-                        let piece_gen_info = PieceGenInfo {
-                            pos: piece.pos,
-                            team: attacker_team,
-                            row_gap,
-                            piece_type: attacker_piece_type,
-                        };
-                        piece_trait.can_control(board, &piece_gen_info, &targets)
-                    });
-                    if can_be_attacked {
-                        break;
-                    }
-                }
-                _ => {
-                    let PieceInfo {
-                        team: target_team,
-                        piece_type: target_piece_type,
-                        ..
-                    } = board.get_piece_info(current_pos);
-                    if team != target_team {
-                        break;
-                    }
-
-                    if target_piece_type != 3 {
-                        // Can only castle with rooks.
-                        break;
-                    }
-
-                    let mut can_castle = true;
-                    for piece in &board.pieces {
-                        if piece.pos == current_pos {
-                            if !piece.first_move {
-                                can_castle = false;
-                            }
-                        }
-                    }
-
-                    if !can_castle {
-                        break;
-                    }
-
-                    actions.push(Action {
-                        from: pos,
-                        to: current_pos,
-                        piece_type,
-                        team,
-                        capture: true,
-                        info: CASTLING_MOVE,
-                    });
-                    break;
-                }
-            }
+        while current_pos != king_to {
+            current_pos += king_dir;
+            targets.push(current_pos);
+        }
+
+        let can_be_attacked = !targets.is_empty()
+            && opposing_pieces.iter().any(|piece| {
+                let PieceInfo {
+                    team: attacker_team,
+                    piece_type: attacker_piece_type,
+                    ..
+                } = board.get_piece_info(piece.pos);
+                let piece_trait = board.piece_lookup.lookup(attacker_piece_type).duplicate();
+                let piece_gen_info = PieceGenInfo {
+                    pos: piece.pos,
+                    team: attacker_team,
+                    row_gap,
+                    piece_type: attacker_piece_type,
+                };
+                piece_trait.can_control(board, &piece_gen_info, &targets)
+            });
+        if can_be_attacked {
+            continue;
         }
+
+        actions.push(Action {
+            from: pos,
+            to: rook_pos,
+            piece_type,
+            team,
+            capture: true,
+            info: CASTLING_MOVE,
+        });
     }
 }
 
@@ -133,6 +155,14 @@ impl KingPiece {
             sliders: vec![1, -1],
         }
     }
+
+    // A king for variants that don't have castling.
+    pub fn without_castling(row_gap: i16) -> Self {
+        KingPiece {
+            sliders: vec![],
+            ..KingPiece::new(row_gap)
+        }
+    }
 }
 
 impl Piece for KingPiece {
@@ -160,7 +190,7 @@ impl Piece for KingPiece {
             let row_dif = (piece_row - row).abs();
             let col_dif = (piece_col - col).abs();
 
-            if row_dif + col_dif <= 2 {
+            if row_dif <= 1 && col_dif <= 1 {
                 return true;
             }
         }
@@ -176,10 +206,26 @@ impl Piece for KingPiece {
         1000
     }
 
+    fn get_royalty(&self) -> Royalty {
+        Royalty::Royal
+    }
+
+    fn is_castling(&self, action: &Action) -> bool {
+        action.info == CASTLING_MOVE
+    }
+
+    fn get_castling_piece(&self) -> Option<i16> {
+        if self.sliders.is_empty() {
+            None
+        } else {
+            Some(CASTLING_PIECE)
+        }
+    }
+
     fn duplicate(&self) -> Box<dyn Piece> {
         Box::new(KingPiece {
             deltas: self.deltas.clone(),
-            sliders: self.deltas.clone(),
+            sliders: self.sliders.clone(),
         })
     }
 
@@ -199,6 +245,12 @@ impl Piece for KingPiece {
             let mut pieces = vec![StoredMovePieceChange::PieceMove {
                 from: action.from,
                 to: action.to,
+                first_move: board
+                    .pieces
+                    .iter()
+                    .find(|piece| piece.pos == action.from)
+                    .unwrap()
+                    .first_move,
             }];
 
             if action.capture {
@@ -214,6 +266,7 @@ impl Piece for KingPiece {
 
             let past_move = StoredMove {
                 action,
+                halfmove_clock: board.halfmove_clock,
                 move_type: StoredMoveType::Standard { states, pieces },
             };
 
@@ -222,9 +275,7 @@ impl Piece for KingPiece {
             let old_pieces = board.pieces.clone();
             let old_state = board.state.clone();
 
-            let castle_dir = (action.to - action.from).signum();
-            let new_king_pos = action.from + (2 * castle_dir);
-            let new_rook_pos = action.from + castle_dir;
+            let (new_king_pos, new_rook_pos) = get_castling_squares(board, action.from, action.to);
 
             let from_usize = action.from as usize;
             let to_usize = action.to as usize;
@@ -250,6 +301,21 @@ impl Piece for KingPiece {
                 .position(|piece| piece.pos == action.to)
                 .unwrap();
 
+            board.hash ^= hash_piece(
+                board,
+                action.from,
+                from_state,
+                board.pieces[from_pos_all].first_move,
+            );
+            board.hash ^= hash_piece(
+                board,
+                action.to,
+                to_state,
+                board.pieces[to_pos_all].first_move,
+            );
+            board.hash ^= hash_piece(board, new_king_pos, from_state, false);
+            board.hash ^= hash_piece(board, new_rook_pos, to_state, false);
+
             board.pieces[from_pos_all].pos = new_king_pos;
             board.pieces[from_pos_all].first_move = false;
             board.pieces[to_pos_all].pos = new_rook_pos;
@@ -257,6 +323,7 @@ impl Piece for KingPiece {
 
             let past_move = StoredMove {
                 action,
+                halfmove_clock: board.halfmove_clock,
                 move_type: StoredMoveType::Custom {
                     pieces: old_pieces,
                     state: old_state,
@@ -1,6 +1,10 @@
 use crate::boards::{Action, ActionType, Board, StoredMove};
 
+mod atomic;
+mod betza;
+mod compound;
 mod deltas;
+mod royal;
 mod sliders;
 mod util;
 
@@ -11,7 +15,11 @@ mod pawn;
 mod queen;
 mod rook;
 
+pub use atomic::*;
+pub use betza::*;
+pub use compound::*;
 pub use deltas::*;
+pub use royal::*;
 pub use sliders::*;
 pub use util::*;
 
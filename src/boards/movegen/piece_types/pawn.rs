@@ -1,20 +1,244 @@
 use crate::boards::{
-    Action, Board, PieceGenInfo, ResetSquare, StoredMove, StoredMovePieceChange, StoredMoveType,
+    get_royalty, hash_piece, Action, Board, PieceGenInfo, ResetSquare, StoredMove,
+    StoredMovePieceChange, StoredMoveType,
 };
 
 use super::{base_make_move, MakeMoveResults, Piece};
 
 const NORMAL_MOVE: i16 = -1;
 const DOUBLE_MOVE: i16 = -2;
-const EN_PASSANT: i16 = -3;
+// The info of en passant captures, which land on an empty square behind the captured pawn.
+pub const EN_PASSANT: i16 = -3;
+
+/*
+    How the pawns of one team move.
+    Ranks count from 1 on the team's own side of the board along `direction`, so rank 2 is where pawns usually start.
+*/
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PawnConfig {
+    // The (rows, columns) pawns step forward by, with rows counting down the board. Only one of them can be non-zero.
+    pub direction: (i16, i16),
+    // The ranks pawns can double move from. None uses the pawn's first move instead.
+    pub double_move_ranks: Option<Vec<i16>>,
+    /*
+        The ranks pawns can promote on, where ranks of 0 or less count back from the last rank (so 0 is always the last rank).
+        Promoting is only forced on the last rank, so on the others pawns can also stay pawns (like in Grand Chess).
+    */
+    pub promotion_zone: Vec<i16>,
+    // The piece types pawns can promote to, with None being every type but pawns and royal pieces.
+    pub promotions: Option<Vec<i16>>,
+}
 
-fn add_promotion(board: &Board, actions: &mut Vec<Action>, action: Action, promotion_row: i16) {
-    if board.get_row(action.to) == promotion_row {
-        for promotion_piece_type in 0..board.piece_types {
-            if promotion_piece_type == 0 {
-                continue;
+impl PawnConfig {
+    pub fn new(direction: (i16, i16)) -> Self {
+        PawnConfig {
+            direction,
+            double_move_ranks: None,
+            promotion_zone: vec![],
+            promotions: None,
+        }
+    }
+
+    // Pawns moving up the board, like white's.
+    pub fn up() -> Self {
+        PawnConfig::new((-1, 0))
+    }
+
+    // Pawns moving down the board, like black's.
+    pub fn down() -> Self {
+        PawnConfig::new((1, 0))
+    }
+
+    pub fn with_double_move_ranks(mut self, double_move_ranks: Vec<i16>) -> Self {
+        self.double_move_ranks = Some(double_move_ranks);
+        self
+    }
+
+    pub fn with_promotion_zone(mut self, promotion_zone: Vec<i16>) -> Self {
+        self.promotion_zone = promotion_zone;
+        self
+    }
+
+    pub fn with_promotions(mut self, promotions: Vec<i16>) -> Self {
+        self.promotions = Some(promotions);
+        self
+    }
+
+    fn is_vertical(&self) -> bool {
+        self.direction.0 != 0
+    }
+
+    pub fn get_forward(&self, row_gap: i16) -> i16 {
+        self.direction.0 * row_gap + self.direction.1
+    }
+
+    // The offset to the squares on either side of the pawn, which it captures diagonally in front of.
+    fn get_sideways(&self, row_gap: i16) -> i16 {
+        if self.is_vertical() {
+            1
+        } else {
+            row_gap
+        }
+    }
+
+    fn get_last_rank(&self, board: &Board) -> i16 {
+        if self.is_vertical() {
+            board.rows
+        } else {
+            board.cols
+        }
+    }
+
+    // The file of `pos` across the way pawns move, counting from 1 on the left for pawns moving up or down and from the bottom for the others.
+    pub fn get_file(&self, board: &Board, pos: i16) -> i16 {
+        let row = board.get_row(pos);
+        if self.is_vertical() {
+            board.get_col(pos, row) - (board.buffer_amount / 2) + 1
+        } else {
+            board.rows + board.buffer_amount - row
+        }
+    }
+
+    // Whether `rank` is in the promotion zone, where ranks of 0 or less count back from the last rank.
+    fn is_promotion_rank(&self, board: &Board, rank: i16) -> bool {
+        let last_rank = self.get_last_rank(board);
+        self.promotion_zone.iter().any(|zone_rank| {
+            let zone_rank = if *zone_rank <= 0 {
+                last_rank + zone_rank
+            } else {
+                *zone_rank
+            };
+            zone_rank == rank
+        })
+    }
+
+    // Whether a pawn can be dropped on `pos`, which isn't on the team's own back rank, its last rank or where it would promote.
+    pub fn can_drop_on(&self, board: &Board, pos: i16) -> bool {
+        let rank = self.get_rank(board, pos);
+        rank != 1 && rank != self.get_last_rank(board) && !self.is_promotion_rank(board, rank)
+    }
+
+    // Whether pawns can double move from `rank`, which is the second rank unless `double_move_ranks` says otherwise.
+    pub fn is_double_move_rank(&self, rank: i16) -> bool {
+        match &self.double_move_ranks {
+            Some(ranks) => ranks.contains(&rank),
+            None => rank == 2,
+        }
+    }
+
+    pub fn get_rank(&self, board: &Board, pos: i16) -> i16 {
+        let row = board.get_row(pos);
+        match self.direction {
+            (row_step, _) if row_step < 0 => board.rows + board.buffer_amount - row,
+            (row_step, _) if row_step > 0 => row - board.buffer_amount + 1,
+            (_, col_step) => {
+                let col = board.get_col(pos, row) - (board.buffer_amount / 2);
+                if col_step > 0 {
+                    col + 1
+                } else {
+                    board.cols - col
+                }
             }
-            if promotion_piece_type == 5 {
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct PawnPiece {
+    // How each team's pawns move, by team. Teams past the end use the config of the team as many teams before it as there are configs.
+    configs: Vec<PawnConfig>,
+    /*
+        The most pieces of each type (by index) a team can have for a pawn to promote into one more.
+        Grand Chess uses this so pawns can only promote to pieces that have been captured.
+    */
+    promotion_limits: Option<Vec<i16>>,
+}
+
+impl Default for PawnPiece {
+    fn default() -> Self {
+        PawnPiece::with_configs(vec![PawnConfig::up(), PawnConfig::down()]).with_promotion_ranks(1)
+    }
+}
+
+impl PawnPiece {
+    pub fn with_configs(configs: Vec<PawnConfig>) -> Self {
+        PawnPiece {
+            configs,
+            promotion_limits: None,
+        }
+    }
+
+    pub fn with_promotions(promotions: Vec<i16>) -> Self {
+        let mut pawn = PawnPiece::default();
+        for config in &mut pawn.configs {
+            config.promotions = Some(promotions.clone());
+        }
+        pawn
+    }
+
+    // Lets pawns of every team promote on the last `promotion_ranks` ranks.
+    pub fn with_promotion_ranks(mut self, promotion_ranks: i16) -> Self {
+        for config in &mut self.configs {
+            config.promotion_zone = (0..promotion_ranks).map(|rank| -rank).collect();
+        }
+        self
+    }
+
+    pub fn with_promotion_limits(mut self, promotion_limits: Vec<i16>) -> Self {
+        self.promotion_limits = Some(promotion_limits);
+        self
+    }
+
+    pub fn with_double_move_ranks(mut self, double_move_ranks: Vec<i16>) -> Self {
+        for config in &mut self.configs {
+            config.double_move_ranks = Some(double_move_ranks.clone());
+        }
+        self
+    }
+
+    pub fn get_config(&self, team: i16) -> &PawnConfig {
+        &self.configs[team as usize % self.configs.len()]
+    }
+
+    // Whether `pos` is on the back rank of `team`, where only Horde has pawns.
+    fn is_on_back_rank(&self, board: &Board, pos: i16, team: i16) -> bool {
+        self.get_config(team).get_rank(board, pos) == 1
+    }
+
+    fn can_promote_to(&self, board: &Board, piece_type: i16, team: i16) -> bool {
+        match &self.get_config(team).promotions {
+            Some(promotions) if !promotions.contains(&piece_type) => return false,
+            None if piece_type == 0 || get_royalty(board, piece_type).is_royal() => return false,
+            _ => {}
+        }
+
+        let limit = match &self.promotion_limits {
+            Some(limits) => match limits.get(piece_type as usize) {
+                Some(limit) => *limit,
+                None => return true,
+            },
+            None => return true,
+        };
+        let piece = board.get_piece_value(piece_type, team);
+        let count = board
+            .pieces
+            .iter()
+            .filter(|info| board.state[info.pos as usize] == piece)
+            .count();
+        (count as i16) < limit
+    }
+
+    // Adds `action`, or an action for each piece it can promote to if it goes into the promotion zone.
+    fn add_promotion(&self, board: &Board, actions: &mut Vec<Action>, action: Action) {
+        let config = self.get_config(action.team);
+        let rank = config.get_rank(board, action.to);
+        if !config.is_promotion_rank(board, rank) {
+            actions.push(action);
+            return;
+        }
+
+        for promotion_piece_type in 0..board.piece_types {
+            if !self.can_promote_to(board, promotion_piece_type, action.team) {
                 continue;
             }
 
@@ -27,12 +251,42 @@ fn add_promotion(board: &Board, actions: &mut Vec<Action>, action: Action, promo
                 info: promotion_piece_type,
             });
         }
-    } else {
-        actions.push(action);
+
+        // Before the last rank, promoting is optional.
+        if rank < config.get_last_rank(board) {
+            actions.push(action);
+        }
+    }
+
+    fn can_double_move(&self, board: &Board, pos: i16, team: i16) -> bool {
+        let config = self.get_config(team);
+        match &config.double_move_ranks {
+            Some(ranks) => ranks.contains(&config.get_rank(board, pos)),
+            None => {
+                board
+                    .pieces
+                    .iter()
+                    .find(|piece| piece.pos == pos)
+                    .unwrap()
+                    .first_move
+            }
+        }
+    }
+
+    // Whether the last move was a double move that skipped over `target`, so a pawn capturing onto it takes en passant.
+    fn can_en_passant(&self, board: &Board, piece_type: i16, target: i16) -> bool {
+        match board.history.last() {
+            Some(last_move) => {
+                let action = last_move.action;
+                action.piece_type == piece_type
+                    && action.info == DOUBLE_MOVE
+                    && (action.from + action.to) / 2 == target
+            }
+            None => false,
+        }
     }
 }
 
-pub struct PawnPiece;
 impl Piece for PawnPiece {
     fn add_actions(&self, actions: &mut Vec<Action>, board: &mut Board, piece_info: &PieceGenInfo) {
         let PieceGenInfo {
@@ -43,22 +297,15 @@ impl Piece for PawnPiece {
             ..
         } = *piece_info;
 
-        let target = match team {
-            0 => pos - row_gap,
-            1 => pos + row_gap,
-            _ => pos,
-        };
-
-        let promotion_row = match team {
-            0 => board.buffer_amount,
-            1 => board.rows + board.buffer_amount - 1,
-            _ => board.row_gap,
-        };
+        let config = self.get_config(team);
+        let forward = config.get_forward(row_gap);
+        let sideways = config.get_sideways(row_gap);
+        let target = pos + forward;
 
         let can_move_once = board.can_move(target);
         if can_move_once {
-            add_promotion(
-                &board,
+            self.add_promotion(
+                board,
                 actions,
                 Action {
                     from: pos,
@@ -68,37 +315,33 @@ impl Piece for PawnPiece {
                     info: NORMAL_MOVE,
                     team,
                 },
-                promotion_row,
             );
         }
 
-        let can_move_twice = board
-            .pieces
-            .iter()
-            .find(|piece| piece.pos == pos)
-            .unwrap()
-            .first_move;
+        let can_move_twice = self.can_double_move(board, pos, team);
 
         if can_move_once && can_move_twice {
-            let target = match team {
-                0 => pos - row_gap * 2,
-                1 => pos + row_gap * 2,
-                _ => pos,
-            };
+            let target = pos + forward * 2;
+
+            // Double moves from the back rank (like in Horde) can't be taken en passant, so they're stored as normal moves.
+            let from_back_rank = self.is_on_back_rank(board, pos, team);
 
             if board.can_move(target) {
-                add_promotion(
-                    &board,
+                self.add_promotion(
+                    board,
                     actions,
                     Action {
                         from: pos,
                         to: target,
                         capture: false,
                         piece_type,
-                        info: DOUBLE_MOVE,
+                        info: if from_back_rank {
+                            NORMAL_MOVE
+                        } else {
+                            DOUBLE_MOVE
+                        },
                         team,
                     },
-                    promotion_row,
                 );
             }
         }
@@ -113,16 +356,12 @@ impl Piece for PawnPiece {
         If both of those are true, we can take the pawn by moving to where it would've been 1 square from there
         */
 
-        let target_left = match team {
-            0 => pos - row_gap - 1,
-            1 => pos + row_gap - 1,
-            _ => pos,
-        };
+        let target_left = pos + forward - sideways;
         let capture_left = board.can_capture(target_left, team);
 
         if capture_left {
-            add_promotion(
-                &board,
+            self.add_promotion(
+                board,
                 actions,
                 Action {
                     from: pos,
@@ -132,20 +371,15 @@ impl Piece for PawnPiece {
                     info: NORMAL_MOVE,
                     team,
                 },
-                promotion_row,
             );
         }
 
-        let target_right = match team {
-            0 => pos - row_gap + 1,
-            1 => pos + row_gap + 1,
-            _ => pos,
-        };
+        let target_right = pos + forward + sideways;
         let capture_right = board.can_capture(target_right, team);
 
         if capture_right {
-            add_promotion(
-                &board,
+            self.add_promotion(
+                board,
                 actions,
                 Action {
                     from: pos,
@@ -155,16 +389,10 @@ impl Piece for PawnPiece {
                     info: NORMAL_MOVE,
                     team,
                 },
-                promotion_row,
             );
         }
 
-        let en_passant_left = if let Some(last_move) = board.history.last() {
-            let action = last_move.action;
-            action.piece_type == piece_info.piece_type && action.info == -2 && action.to == pos - 1
-        } else {
-            false
-        };
+        let en_passant_left = self.can_en_passant(board, piece_type, target_left);
 
         /*
             It should be noted that theoretically, there could be an en-passant promotion in some sort of variant.
@@ -172,8 +400,8 @@ impl Piece for PawnPiece {
         */
 
         if en_passant_left {
-            add_promotion(
-                &board,
+            self.add_promotion(
+                board,
                 actions,
                 Action {
                     from: pos,
@@ -183,20 +411,14 @@ impl Piece for PawnPiece {
                     info: EN_PASSANT,
                     team,
                 },
-                promotion_row,
             );
         }
 
-        let en_passant_right = if let Some(last_move) = board.history.last() {
-            let action = last_move.action;
-            action.piece_type == piece_info.piece_type && action.info == -2 && action.to == pos + 1
-        } else {
-            false
-        };
+        let en_passant_right = self.can_en_passant(board, piece_type, target_right);
 
         if en_passant_right {
-            add_promotion(
-                &board,
+            self.add_promotion(
+                board,
                 actions,
                 Action {
                     from: pos,
@@ -206,13 +428,33 @@ impl Piece for PawnPiece {
                     info: EN_PASSANT,
                     team,
                 },
-                promotion_row,
             );
         }
     }
 
+    /*
+        Pawns only control the squares diagonally in front of them, whether or not there's a piece there.
+        The default implementation goes through `get_actions`, which misses attacks on empty squares.
+    */
+    fn can_control(
+        &self,
+        _board: &mut Board,
+        piece_info: &PieceGenInfo,
+        targets: &Vec<i16>,
+    ) -> bool {
+        let PieceGenInfo {
+            pos, row_gap, team, ..
+        } = *piece_info;
+
+        let config = self.get_config(team);
+        let forward = pos + config.get_forward(row_gap);
+        let sideways = config.get_sideways(row_gap);
+
+        targets.contains(&(forward - sideways)) || targets.contains(&(forward + sideways))
+    }
+
     fn make_move(&self, board: &mut Board, action: Action) {
-        let states = vec![
+        let mut states = vec![
             ResetSquare {
                 pos: action.from,
                 state: board.state[action.from as usize],
@@ -226,34 +468,52 @@ impl Piece for PawnPiece {
         let mut pieces = vec![StoredMovePieceChange::PieceMove {
             from: action.from,
             to: action.to,
+            first_move: board
+                .pieces
+                .iter()
+                .find(|piece| piece.pos == action.from)
+                .unwrap()
+                .first_move,
         }];
 
         if action.info == EN_PASSANT {
             /*
                 The action in Lotisa's "to" represents where the capturer needs to go, not the piece that needs to be captured.
-                Since we're doing en passant, we'll always know which way to modify the row of the "to" to find the captured piece.
+                Since we're doing en passant, the captured piece is wherever the double move before this one ended.
                 Then, we move the captured piece to the "to" square, and simulate a normal capture.
                 We store the undo with the normal action to make sure squares get reset normally, though.
                 Perhaps this is a more efficient way to do this, but I thought this was easiest.
             */
 
-            let en_passant_target = if action.team == 0 {
-                action.to + board.row_gap
-            } else {
-                action.to - board.row_gap
-            };
+            let en_passant_target = board.history.last().unwrap().action.to;
 
             let en_passant_target_usize = en_passant_target as usize;
             let en_passant_target_state = board.state[en_passant_target_usize];
+            states.push(ResetSquare {
+                pos: en_passant_target,
+                state: en_passant_target_state,
+            });
+
             let to_usize = action.to as usize;
             board.state[to_usize] = en_passant_target_state;
             board.state[en_passant_target_usize] = 1;
 
-            let info = *board
+            let en_passant_index = board
                 .pieces
                 .iter()
-                .find(|piece| piece.pos == en_passant_target)
+                .position(|piece| piece.pos == en_passant_target)
                 .unwrap();
+            let info = board.pieces[en_passant_index];
+            board.pieces[en_passant_index].pos = action.to;
+
+            board.hash ^= hash_piece(
+                board,
+                en_passant_target,
+                en_passant_target_state,
+                info.first_move,
+            );
+            board.hash ^= hash_piece(board, action.to, en_passant_target_state, info.first_move);
+
             pieces.push(StoredMovePieceChange::PieceRemove { info })
         } else if action.capture {
             let info = *board
@@ -266,8 +526,38 @@ impl Piece for PawnPiece {
 
         base_make_move(board, action);
 
+        // Pawns stepping off the back rank can still double move from the rank in front of it.
+        let forward = self.get_config(action.team).get_forward(board.row_gap);
+        let is_double_step = action.to - action.from == forward * 2;
+        if action.info == NORMAL_MOVE
+            && !is_double_step
+            && self.is_on_back_rank(board, action.from, action.team)
+        {
+            let piece = board.state[action.to as usize];
+            let index = board
+                .pieces
+                .iter()
+                .position(|piece| piece.pos == action.to)
+                .unwrap();
+            board.pieces[index].first_move = true;
+
+            board.hash ^= hash_piece(board, action.to, piece, false);
+            board.hash ^= hash_piece(board, action.to, piece, true);
+        }
+
+        if action.info >= 0 {
+            // Promotions store the piece type being promoted to in "info".
+            let pawn = board.state[action.to as usize];
+            let promoted = board.get_piece_value(action.info, action.team);
+            board.state[action.to as usize] = promoted;
+
+            board.hash ^= hash_piece(board, action.to, pawn, false);
+            board.hash ^= hash_piece(board, action.to, promoted, false);
+        }
+
         let past_move = StoredMove {
             action,
+            halfmove_clock: board.halfmove_clock,
             move_type: StoredMoveType::Standard { states, pieces },
         };
 
@@ -282,7 +572,11 @@ impl Piece for PawnPiece {
         "♙"
     }
 
+    fn get_pawn_config(&self, team: i16) -> Option<PawnConfig> {
+        Some(self.get_config(team).clone())
+    }
+
     fn duplicate(&self) -> Box<dyn Piece> {
-        Box::new(PawnPiece)
+        Box::new(self.clone())
     }
 }
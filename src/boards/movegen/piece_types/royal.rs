@@ -0,0 +1,72 @@
+use crate::boards::{Action, Board, PieceGenInfo, StoredMove};
+
+use super::{PawnConfig, Piece, Royalty};
+
+/*
+    Wraps a piece to change its royalty, so variants can make any piece royal (or make the king an ordinary piece).
+    Everything else is left to the wrapped piece.
+*/
+pub struct RoyalPiece {
+    piece: Box<dyn Piece>,
+    royalty: Royalty,
+}
+
+impl RoyalPiece {
+    pub fn new(piece: Box<dyn Piece>, royalty: Royalty) -> Self {
+        RoyalPiece { piece, royalty }
+    }
+}
+
+impl Piece for RoyalPiece {
+    fn can_control(
+        &self,
+        board: &mut Board,
+        piece_info: &PieceGenInfo,
+        targets: &Vec<i16>,
+    ) -> bool {
+        self.piece.can_control(board, piece_info, targets)
+    }
+
+    fn add_actions(&self, actions: &mut Vec<Action>, board: &mut Board, piece_info: &PieceGenInfo) {
+        self.piece.add_actions(actions, board, piece_info);
+    }
+
+    fn get_material_value(&self) -> i32 {
+        self.piece.get_material_value()
+    }
+
+    fn get_icon(&self) -> &str {
+        self.piece.get_icon()
+    }
+
+    fn get_royalty(&self) -> Royalty {
+        self.royalty
+    }
+
+    fn is_castling(&self, action: &Action) -> bool {
+        self.piece.is_castling(action)
+    }
+
+    fn get_castling_piece(&self) -> Option<i16> {
+        self.piece.get_castling_piece()
+    }
+
+    fn get_pawn_config(&self, team: i16) -> Option<PawnConfig> {
+        self.piece.get_pawn_config(team)
+    }
+
+    fn make_move(&self, board: &mut Board, action: Action) {
+        self.piece.make_move(board, action);
+    }
+
+    fn undo_move(&self, board: &mut Board, undo: &StoredMove) {
+        self.piece.undo_move(board, undo);
+    }
+
+    fn duplicate(&self) -> Box<dyn Piece> {
+        Box::new(RoyalPiece {
+            piece: self.piece.duplicate(),
+            royalty: self.royalty,
+        })
+    }
+}
@@ -13,10 +13,11 @@ pub fn can_control_sliding(
     }
 
     for slider in sliders {
-        let mut can_skip = false;
+        // A slider can only reach targets that are a multiple of it away, in the same direction.
+        let mut can_skip = true;
         for dif in &difs {
-            if !((dif % slider) != 0 || dif.signum() != slider.signum()) {
-                can_skip = true;
+            if dif % slider == 0 && dif.signum() == slider.signum() {
+                can_skip = false;
                 break;
             }
         }
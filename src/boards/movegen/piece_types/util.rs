@@ -1,8 +1,10 @@
 use crate::boards::{
-    Action, ActionType, Board, PieceGenInfo, ResetSquare, StoredMove, StoredMovePieceChange,
-    StoredMoveType,
+    hash_piece, Action, ActionType, Board, PieceGenInfo, ResetSquare, StoredMove,
+    StoredMovePieceChange, StoredMoveType,
 };
 
+use super::PawnConfig;
+
 pub fn attempt_action(
     moves: &mut Vec<Action>,
     board: &Board,
@@ -46,6 +48,7 @@ pub fn base_make_move(board: &mut Board, action: Action) {
     let to_usize = action.to as usize;
 
     let from_state = board.state[from_usize];
+    let to_state = board.state[to_usize];
 
     board.state[to_usize] = from_state;
     board.state[from_usize] = 1;
@@ -61,15 +64,48 @@ pub fn base_make_move(board: &mut Board, action: Action) {
         .iter()
         .position(|piece| piece.pos == action.from)
         .unwrap();
+
+    board.hash ^= hash_piece(
+        board,
+        action.from,
+        from_state,
+        board.pieces[from_pos_all].first_move,
+    );
+    board.hash ^= hash_piece(board, action.to, from_state, false);
+
     board.pieces[from_pos_all].pos = action.to;
     board.pieces[from_pos_all].first_move = false;
 
     if let Some(to_pos_all) = to_pos_all {
+        let captured = board.pieces[to_pos_all];
+        board.hash ^= hash_piece(board, action.to, to_state, captured.first_move);
         board.pieces.swap_remove(to_pos_all);
     }
 }
 
-pub trait Piece {
+/*
+    What losing a piece means for its team.
+    - None: Nothing, it's an ordinary piece.
+    - Royal: It can't be captured, and its team is in check whenever it's attacked, like a king. With several of them, any one being attacked is check.
+    - Shared: Like Royal, but a team is only in check once every one of these it has is attacked (like the duple check in Spartan chess).
+    - Extinction: It can be captured, but a team loses once it has none of its type left (like every piece in Extinction chess).
+*/
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Royalty {
+    None,
+    Royal,
+    Shared,
+    Extinction,
+}
+
+impl Royalty {
+    // Whether the piece can be put in check, rather than captured.
+    pub fn is_royal(&self) -> bool {
+        matches!(self, Royalty::Royal | Royalty::Shared)
+    }
+}
+
+pub trait Piece: Send {
     /*
         The default `can_control` method is not very performant. Subtraits of Piece should reimplement this for the sake of performance.
     */
@@ -98,6 +134,25 @@ pub trait Piece {
     fn get_material_value(&self) -> i32;
     fn get_icon(&self) -> &str;
 
+    fn get_royalty(&self) -> Royalty {
+        Royalty::None
+    }
+
+    // Whether `action`, which was made by this piece, is castling, since that's written differently from other moves.
+    fn is_castling(&self, _action: &Action) -> bool {
+        false
+    }
+
+    // The piece type this piece castles with, for pieces that can castle.
+    fn get_castling_piece(&self) -> Option<i16> {
+        None
+    }
+
+    // How the pawns of `team` move, for pieces that are pawns.
+    fn get_pawn_config(&self, _team: i16) -> Option<PawnConfig> {
+        None
+    }
+
     fn make_move(&self, board: &mut Board, action: Action) {
         let states = vec![
             ResetSquare {
@@ -113,6 +168,12 @@ pub trait Piece {
         let mut pieces = vec![StoredMovePieceChange::PieceMove {
             from: action.from,
             to: action.to,
+            first_move: board
+                .pieces
+                .iter()
+                .find(|piece| piece.pos == action.from)
+                .unwrap()
+                .first_move,
         }];
 
         if action.capture {
@@ -128,6 +189,7 @@ pub trait Piece {
 
         let past_move = StoredMove {
             action,
+            halfmove_clock: board.halfmove_clock,
             move_type: StoredMoveType::Standard { states, pieces },
         };
 
@@ -135,7 +197,7 @@ pub trait Piece {
     }
 
     fn undo_move(&self, board: &mut Board, undo: &StoredMove) {
-        let StoredMove { action, move_type } = undo;
+        let StoredMove { move_type, .. } = undo;
 
         match move_type {
             StoredMoveType::Standard { states, pieces } => {
@@ -156,7 +218,21 @@ pub trait Piece {
                         StoredMovePieceChange::PieceRemove { info } => {
                             board.pieces.push(*info);
                         }
-                        StoredMovePieceChange::PieceMove { from, to } => {
+                        StoredMovePieceChange::PocketChange {
+                            team,
+                            piece_type,
+                            amount,
+                        } => {
+                            // The hash is restored by `Board::undo_move`, so the pocket is changed directly.
+                            if let Some(pockets) = &mut board.pockets {
+                                pockets[*team as usize][*piece_type as usize] -= amount;
+                            }
+                        }
+                        StoredMovePieceChange::PieceMove {
+                            from,
+                            to,
+                            first_move,
+                        } => {
                             let to = *to;
                             let moved_piece_index = board
                                 .pieces
@@ -164,6 +240,7 @@ pub trait Piece {
                                 .position(|piece| piece.pos == to)
                                 .unwrap();
                             board.pieces[moved_piece_index].pos = *from;
+                            board.pieces[moved_piece_index].first_move = *first_move;
                         }
                     }
                 }
@@ -1,8 +1,13 @@
+use std::cell::Cell;
+
 use crate::boards::{Action, Board};
 
-use super::in_check;
+use super::{
+    atomic_in_check, generate_moves, get_castling_squares, get_exploded_team, get_king_pos,
+    get_royal_pieces, get_royalty, in_check, is_draw_by_repetition,
+};
 
-pub trait Restrictor {
+pub trait Restrictor: Send {
     fn can_add(&self, board: &mut Board, action: &Action, required_team: i16) -> bool;
     fn duplicate(&self) -> Box<dyn Restrictor>;
 }
@@ -11,10 +16,12 @@ pub struct DefaultRestrictor;
 
 impl Restrictor for DefaultRestrictor {
     fn can_add(&self, board: &mut Board, action: &Action, required_team: i16) -> bool {
-        if action.capture {
-            let target_value = board.state[action.to as usize];
+        let target_value = board.state[action.to as usize];
+        // En passant lands on an empty square, so there's no piece there to look up.
+        if action.capture && target_value != 1 {
             let target_team = board.get_team(target_value);
-            if board.get_piece_type(target_value, target_team) == 5 {
+            let target_type = board.get_piece_type(target_value, target_team);
+            if get_royalty(board, target_type).is_royal() {
                 return false;
             }
         }
@@ -29,3 +36,269 @@ impl Restrictor for DefaultRestrictor {
         Box::new(DefaultRestrictor)
     }
 }
+
+// A position by its hash, the team to move and the last move.
+type PositionKey = (usize, i16, Option<Action>);
+
+/*
+    Captures are forced, like in Antichess: any other move is only allowed when the team has no legal capture.
+    Captures only count if the board's other restrictors allow them, so a pinned piece's capture doesn't force anything alongside `DefaultRestrictor`.
+    Castling is written as the king capturing its own rook, so it doesn't count as a capture either.
+*/
+#[derive(Default)]
+pub struct CaptureRestrictor {
+    /*
+        Whether the last position had a capture, since every quiet move in a position asks the same thing.
+        Positions are told apart by hash, team and the last move, which the hash leaves out even though it decides en passant.
+    */
+    has_capture: Cell<Option<(PositionKey, bool)>>,
+}
+
+impl CaptureRestrictor {
+    pub fn new() -> Self {
+        CaptureRestrictor::default()
+    }
+}
+
+fn is_capture(board: &Board, action: &Action) -> bool {
+    action.capture
+        && !board
+            .piece_lookup
+            .lookup(action.piece_type)
+            .is_castling(action)
+}
+
+impl Restrictor for CaptureRestrictor {
+    fn can_add(&self, board: &mut Board, action: &Action, required_team: i16) -> bool {
+        if is_capture(board, action) {
+            return true;
+        }
+
+        let key = (
+            board.hash,
+            required_team,
+            board.history.last().map(|last_move| last_move.action),
+        );
+        let has_capture = match self.has_capture.get() {
+            Some((cached_key, has_capture)) if cached_key == key => has_capture,
+            _ => {
+                let has_capture = generate_moves(board, required_team)
+                    .into_iter()
+                    .any(|action| {
+                        is_capture(board, &action) && board.is_legal(action, required_team)
+                    });
+                self.has_capture.set(Some((key, has_capture)));
+                has_capture
+            }
+        };
+        !has_capture
+    }
+
+    fn duplicate(&self) -> Box<dyn Restrictor> {
+        Box::new(CaptureRestrictor::new())
+    }
+}
+
+/*
+    Moves in Atomic can't blow up their own king, but blowing up the other king wins even out of check.
+    Kings can't capture at all, since they'd be caught in their own explosion.
+*/
+pub struct AtomicRestrictor;
+
+impl Restrictor for AtomicRestrictor {
+    fn can_add(&self, board: &mut Board, action: &Action, required_team: i16) -> bool {
+        // Castling is written as the king capturing its own rook, which isn't a real capture.
+        if get_royalty(board, action.piece_type).is_royal()
+            && board.can_capture(action.to, required_team)
+        {
+            return false;
+        }
+
+        board.make_move(*action);
+        let can_add = if get_king_pos(board, required_team).is_none() {
+            false
+        } else {
+            get_exploded_team(board).is_some() || !atomic_in_check(board, required_team)
+        };
+        board.undo_move();
+        can_add
+    }
+
+    fn duplicate(&self) -> Box<dyn Restrictor> {
+        Box::new(AtomicRestrictor)
+    }
+}
+
+/*
+    Moves can't put another team in check, like in the Kriegspiel-like mode where checks aren't allowed.
+    Teams without royal pieces can't be checked, so only boards with them pay for making the move.
+*/
+pub struct NoCheckRestrictor;
+
+impl Restrictor for NoCheckRestrictor {
+    fn can_add(&self, board: &mut Board, action: &Action, required_team: i16) -> bool {
+        if !board.royalties.iter().any(|royalty| royalty.is_royal()) {
+            return true;
+        }
+
+        board.make_move(*action);
+        let gives_check = (0..board.teams)
+            .filter(|team| *team != required_team)
+            .any(|team| in_check(board, team, board.row_gap));
+        board.undo_move();
+        !gives_check
+    }
+
+    fn duplicate(&self) -> Box<dyn Restrictor> {
+        Box::new(NoCheckRestrictor)
+    }
+}
+
+/*
+    Royal pieces of different teams can't come within `distance` squares of each other (counting diagonals as one square).
+    With a distance of 1 this is kings never touching, which boards without check (like with `KingCaptureRestrictor`) don't get for free.
+    Only moves of royal pieces can bring them closer, so every other move is let through without being made.
+*/
+pub struct KingProximityRestrictor {
+    pub distance: i16,
+}
+
+impl KingProximityRestrictor {
+    pub fn new(distance: i16) -> Self {
+        KingProximityRestrictor { distance }
+    }
+}
+
+impl Restrictor for KingProximityRestrictor {
+    fn can_add(&self, board: &mut Board, action: &Action, required_team: i16) -> bool {
+        if !get_royalty(board, action.piece_type).is_royal() {
+            return true;
+        }
+
+        board.make_move(*action);
+        let get_square = |board: &Board, pos: i16| {
+            let row = board.get_row(pos);
+            (row, board.get_col(pos, row))
+        };
+        let royals = get_royal_pieces(board, required_team);
+        let is_too_close = (0..board.teams)
+            .filter(|team| *team != required_team)
+            .flat_map(|team| get_royal_pieces(board, team))
+            .any(|(other, _)| {
+                let (other_row, other_col) = get_square(board, other);
+                royals.iter().any(|(pos, _)| {
+                    let (row, col) = get_square(board, *pos);
+                    (row - other_row).abs().max((col - other_col).abs()) <= self.distance
+                })
+            });
+        board.undo_move();
+        !is_too_close
+    }
+
+    fn duplicate(&self) -> Box<dyn Restrictor> {
+        Box::new(KingProximityRestrictor::new(self.distance))
+    }
+}
+
+/*
+    Pieces can't leave a zone of files and ranks, like the generals in the Xiangqi palace.
+    Files and ranks are counted the way the team's pawns move (see `PawnConfig`), so ranks count from 1 on each team's own side.
+    Only the square a move lands on matters, so nothing is generated or made.
+*/
+pub struct ZoneRestrictor {
+    // The piece types kept in the zone, with None being every royal piece.
+    pub piece_types: Option<Vec<i16>>,
+    pub files: (i16, i16),
+    pub ranks: (i16, i16),
+}
+
+impl ZoneRestrictor {
+    pub fn new(piece_types: Option<Vec<i16>>, files: (i16, i16), ranks: (i16, i16)) -> Self {
+        ZoneRestrictor {
+            piece_types,
+            files,
+            ranks,
+        }
+    }
+
+    // The middle three files of the first three ranks for royal pieces, leaning left on boards with an even number of files.
+    pub fn palace(cols: i16) -> Self {
+        let first_file = (cols - 3) / 2 + 1;
+        ZoneRestrictor::new(None, (first_file, first_file + 2), (1, 3))
+    }
+}
+
+impl Restrictor for ZoneRestrictor {
+    fn can_add(&self, board: &mut Board, action: &Action, required_team: i16) -> bool {
+        let is_kept = match &self.piece_types {
+            Some(piece_types) => piece_types.contains(&action.piece_type),
+            None => get_royalty(board, action.piece_type).is_royal(),
+        };
+        if !is_kept {
+            return true;
+        }
+
+        // Castling is written as the king moving onto its rook, so the king's square is checked instead.
+        let to = if board
+            .piece_lookup
+            .lookup(action.piece_type)
+            .is_castling(action)
+        {
+            get_castling_squares(board, action.from, action.to).0
+        } else {
+            action.to
+        };
+        let config = &board.pawn_configs[required_team as usize];
+        let file = config.get_file(board, to);
+        let rank = config.get_rank(board, to);
+
+        self.files.0 <= file && file <= self.files.1 && self.ranks.0 <= rank && rank <= self.ranks.1
+    }
+
+    fn duplicate(&self) -> Box<dyn Restrictor> {
+        Box::new(ZoneRestrictor::new(
+            self.piece_types.clone(),
+            self.files,
+            self.ranks,
+        ))
+    }
+}
+
+/*
+    Moves can't bring back a position that has already happened with the same team to move.
+    Captures and pawn moves can never repeat a position, so they're let through without being made.
+*/
+pub struct NoRepetitionRestrictor;
+
+impl Restrictor for NoRepetitionRestrictor {
+    fn can_add(&self, board: &mut Board, action: &Action, _required_team: i16) -> bool {
+        if action.capture || (action.piece_type == 0 && !action.is_drop()) {
+            return true;
+        }
+
+        board.make_move(*action);
+        let repeats = is_draw_by_repetition(board);
+        board.undo_move();
+        !repeats
+    }
+
+    fn duplicate(&self) -> Box<dyn Restrictor> {
+        Box::new(NoRepetitionRestrictor)
+    }
+}
+
+/*
+    Royal pieces can be left in check and captured, and losing them loses the game (see `KingCaptureWinConditions`).
+    This is used instead of `DefaultRestrictor`, so it lets every move through.
+*/
+pub struct KingCaptureRestrictor;
+
+impl Restrictor for KingCaptureRestrictor {
+    fn can_add(&self, _board: &mut Board, _action: &Action, _required_team: i16) -> bool {
+        true
+    }
+
+    fn duplicate(&self) -> Box<dyn Restrictor> {
+        Box::new(KingCaptureRestrictor)
+    }
+}
@@ -1,44 +1,86 @@
-use std::collections::HashSet;
+use crate::boards::{Action, Board, PieceInfo};
 
-use crate::boards::{hash_board, Action, Board};
+use super::{
+    generate_legal_moves, get_adjacent_squares, get_king_pos, get_royalty, in_check, Royalty,
+};
 
-use super::in_check;
+// Halfmove counts (plies without a pawn move or capture) for the fifty and seventy-five move rules.
+pub const FIFTY_MOVE_RULE: i16 = 100;
+pub const SEVENTY_FIVE_MOVE_RULE: i16 = 150;
 
-pub fn is_draw_by_repetition(board: &mut Board) -> bool {
-    let mut len = board.history.len();
-    if len < 20 {
-        len = 20;
-    }
-    let mut min_undos = len - 20;
-    if min_undos > 100_000_000 {
-        min_undos = 0;
-    }
-    let mut undos = board.history.as_slice()[min_undos..].to_vec();
-    let mut hashes: Vec<usize> = Vec::with_capacity(undos.len());
+/*
+    Positions can't repeat across a pawn move or a capture, so only the hashes since the last one (the halfmove clock) are scanned.
+    We step back a full round of teams at a time, so that only positions with the same team to move are compared.
+*/
+pub fn is_draw_by_repetition(board: &Board) -> bool {
+    let teams = board.teams as usize;
+    let len = board.hashes.len();
+    let reversible_moves = (board.halfmove_clock as usize).min(len);
 
-    let mut ind = 0;
-    for _ in &undos {
-        if ind % 2 == 0 {
-            hashes.push(hash_board(board, board.moving_team, &board.zobrist));
+    let mut ply = teams;
+    while ply <= reversible_moves {
+        if board.hashes[len - ply] == board.hash {
+            return true;
         }
-        board.undo_move();
-        ind += 1;
+        ply += teams;
     }
 
-    for undo in &undos {
-        board.make_move(undo.action);
+    false
+}
+
+/*
+    The fifty-move rule has to be claimed by a player, while the seventy-five move rule ends the game on its own.
+    Lotisa always claims, but `DefaultWinConditions` checks both, so its draws don't rely on the claim.
+*/
+pub fn is_draw_by_fifty_moves(board: &Board) -> bool {
+    board.halfmove_clock >= FIFTY_MOVE_RULE
+}
+
+pub fn is_draw_by_seventy_five_moves(board: &Board) -> bool {
+    board.halfmove_clock >= SEVENTY_FIVE_MOVE_RULE
+}
+
+/*
+    Dead positions for the default piece set: K v K, K + minor v K, and any number of bishops that all stand on one square color.
+    Knight endings with more than one knight aren't included, since a mate is still possible there.
+*/
+pub fn is_draw_by_insufficient_material(board: &Board) -> bool {
+    let mut minor_pieces = 0;
+    let mut knights = 0;
+    let mut bishop_colors: Vec<i16> = Vec::with_capacity(4);
+
+    // Anything in a pocket can still be dropped back in.
+    if let Some(pockets) = &board.pockets {
+        if pockets.iter().flatten().any(|count| *count > 0) {
+            return false;
+        }
     }
 
-    let mut hash_set: HashSet<usize> = HashSet::with_capacity(undos.len());
-    for hash in hashes {
-        if hash_set.contains(&hash) {
-            return true;
+    for piece in &board.pieces {
+        let PieceInfo { piece_type, .. } = board.get_piece_info(piece.pos);
+        match piece_type {
+            1 => {
+                minor_pieces += 1;
+                knights += 1;
+            }
+            2 => {
+                minor_pieces += 1;
+                let row = board.get_row(piece.pos);
+                let col = board.get_col(piece.pos, row);
+                bishop_colors.push((row + col) % 2);
+            }
+            _ if get_royalty(board, piece_type).is_royal() => {}
+            _ => {
+                return false;
+            }
         }
+    }
 
-        hash_set.insert(hash);
+    if minor_pieces <= 1 {
+        return true;
     }
 
-    return false;
+    knights == 0 && bishop_colors.iter().all(|color| *color == bishop_colors[0])
 }
 
 pub enum GameResult {
@@ -48,24 +90,306 @@ pub enum GameResult {
     Ongoing,
 }
 
-pub trait WinConditions {
+pub trait WinConditions: Send {
     fn compute(&self, board: &mut Board, actions: &Vec<Action>) -> GameResult;
+
+    /*
+        Whether the variant's own rules have already ended the game, like a king reaching the hill in King of the Hill.
+        `generate_legal_moves` doesn't give any moves once it's over, so perft stops there too.
+        Checkmates and draws aren't included, since they come from the moves themselves.
+    */
+    fn is_game_over(&self, _board: &mut Board) -> bool {
+        false
+    }
+
     fn duplicate(&self) -> Box<dyn WinConditions>;
 }
 
+fn is_in_check(board: &mut Board, team: i16) -> bool {
+    in_check(board, team, board.row_gap)
+}
+
+/*
+    Checkmate and stalemate, along with the fifty-move rule and repetitions, which most variants share.
+    Variants that see checks differently (like Atomic) pass their own `in_check`.
+*/
+pub fn compute_checkmate(
+    board: &mut Board,
+    actions: &[Action],
+    in_check: fn(&mut Board, i16) -> bool,
+) -> GameResult {
+    if actions.is_empty() {
+        if in_check(board, board.moving_team) {
+            return GameResult::Lose;
+        }
+
+        return GameResult::Draw;
+    }
+
+    if is_draw_by_fifty_moves(board) {
+        // A checkmate on the last move of the fifty still stands.
+        if in_check(board, board.moving_team)
+            && generate_legal_moves(board, board.moving_team).is_empty()
+        {
+            return GameResult::Lose;
+        }
+
+        return GameResult::Draw;
+    }
+
+    if is_draw_by_repetition(board) {
+        return GameResult::Draw;
+    }
+
+    GameResult::Ongoing
+}
+
+// The result for the moving team when `winner` has already won.
+fn get_result(board: &Board, winner: i16) -> GameResult {
+    if winner == board.moving_team {
+        GameResult::Win
+    } else {
+        GameResult::Lose
+    }
+}
+
+/*
+    The team that has lost every piece of a type with extinction royalty, if there is one.
+    Pieces in a pocket still count, since they can be dropped back in.
+*/
+pub fn get_extinct_team(board: &Board) -> Option<i16> {
+    let extinction_types = (0..board.piece_types)
+        .filter(|piece_type| get_royalty(board, *piece_type) == Royalty::Extinction)
+        .collect::<Vec<_>>();
+    if extinction_types.is_empty() {
+        return None;
+    }
+
+    (0..board.teams).find(|team| {
+        extinction_types.iter().any(|piece_type| {
+            let piece = board.get_piece_value(*piece_type, *team);
+            let in_pocket = board
+                .pockets
+                .as_ref()
+                .is_some_and(|pockets| pockets[*team as usize][*piece_type as usize] > 0);
+            !in_pocket
+                && !board
+                    .pieces
+                    .iter()
+                    .any(|info| board.state[info.pos as usize] == piece)
+        })
+    })
+}
+
+/*
+    Checkmate and draws, and losing a piece type with extinction royalty for the variants that have one.
+*/
 pub struct DefaultWinConditions;
 
 impl WinConditions for DefaultWinConditions {
     fn compute(&self, board: &mut Board, actions: &Vec<Action>) -> GameResult {
-        if actions.len() == 0 {
-            if in_check(board, board.moving_team, board.row_gap) {
-                return GameResult::Lose;
+        if let Some(team) = get_extinct_team(board) {
+            return if team == board.moving_team {
+                GameResult::Lose
+            } else {
+                GameResult::Win
+            };
+        }
+
+        match compute_checkmate(board, actions, is_in_check) {
+            GameResult::Ongoing
+                if is_draw_by_seventy_five_moves(board)
+                    || is_draw_by_insufficient_material(board) =>
+            {
+                GameResult::Draw
             }
+            result => result,
+        }
+    }
 
-            return GameResult::Draw;
+    fn is_game_over(&self, board: &mut Board) -> bool {
+        get_extinct_team(board).is_some()
+    }
+
+    fn duplicate(&self) -> Box<dyn WinConditions> {
+        Box::new(DefaultWinConditions)
+    }
+}
+
+/*
+    The hill is the middle of the board: d4, e4, d5 and e5 in chess, or the single center square on boards with an odd size.
+*/
+pub fn is_on_hill(board: &Board, pos: i16) -> bool {
+    let row = board.get_row(pos);
+    let col = board.get_col(pos, row) - (board.buffer_amount / 2);
+    let row = row - board.buffer_amount;
+
+    (board.rows - 1) / 2 <= row
+        && row <= board.rows / 2
+        && (board.cols - 1) / 2 <= col
+        && col <= board.cols / 2
+}
+
+fn get_hill_winner(board: &Board) -> Option<i16> {
+    (0..board.teams).find(|team| match get_king_pos(board, *team) {
+        Some(king) => is_on_hill(board, king),
+        None => false,
+    })
+}
+
+/*
+    King of the Hill: on top of checkmate, a team wins once its king reaches the hill.
+*/
+pub struct KingOfTheHillWinConditions;
+
+impl WinConditions for KingOfTheHillWinConditions {
+    fn compute(&self, board: &mut Board, actions: &Vec<Action>) -> GameResult {
+        match get_hill_winner(board) {
+            Some(winner) => get_result(board, winner),
+            None => compute_checkmate(board, actions, is_in_check),
         }
+    }
+
+    fn is_game_over(&self, board: &mut Board) -> bool {
+        get_hill_winner(board).is_some()
+    }
+
+    fn duplicate(&self) -> Box<dyn WinConditions> {
+        Box::new(KingOfTheHillWinConditions)
+    }
+}
 
-        if is_draw_by_repetition(board) {
+fn get_check_winner(board: &Board) -> Option<i16> {
+    board
+        .remaining_checks
+        .as_ref()?
+        .iter()
+        .position(|checks| *checks <= 0)
+        .map(|team| team as i16)
+}
+
+/*
+    Three-check: on top of checkmate, a team wins once it has given check three times.
+    The checks are counted down in the board's `remaining_checks`, so other limits work by starting from another number.
+*/
+pub struct ThreeCheckWinConditions;
+
+impl WinConditions for ThreeCheckWinConditions {
+    fn compute(&self, board: &mut Board, actions: &Vec<Action>) -> GameResult {
+        match get_check_winner(board) {
+            Some(winner) => get_result(board, winner),
+            None => compute_checkmate(board, actions, is_in_check),
+        }
+    }
+
+    fn is_game_over(&self, board: &mut Board) -> bool {
+        get_check_winner(board).is_some()
+    }
+
+    fn duplicate(&self) -> Box<dyn WinConditions> {
+        Box::new(ThreeCheckWinConditions)
+    }
+}
+
+/*
+    Kings next to each other are never in check in Atomic, since capturing either one would blow up both.
+*/
+pub fn atomic_in_check(board: &mut Board, team: i16) -> bool {
+    let king = match get_king_pos(board, team) {
+        Some(king) => king,
+        None => return false,
+    };
+
+    let adjacent = get_adjacent_squares(board, king);
+    for other_team in 0..board.teams {
+        if other_team == team {
+            continue;
+        }
+
+        if let Some(other_king) = get_king_pos(board, other_team) {
+            if adjacent.contains(&other_king) {
+                return false;
+            }
+        }
+    }
+
+    in_check(board, team, board.row_gap)
+}
+
+// The team whose king has been blown up, if there is one.
+pub fn get_exploded_team(board: &Board) -> Option<i16> {
+    (0..board.teams).find(|team| get_king_pos(board, *team).is_none())
+}
+
+/*
+    Atomic: a team loses once its king is caught in an explosion, or is checkmated.
+*/
+pub struct AtomicWinConditions;
+
+impl WinConditions for AtomicWinConditions {
+    fn compute(&self, board: &mut Board, actions: &Vec<Action>) -> GameResult {
+        match get_exploded_team(board) {
+            Some(team) if team == board.moving_team => GameResult::Lose,
+            Some(_) => GameResult::Win,
+            None => compute_checkmate(board, actions, atomic_in_check),
+        }
+    }
+
+    fn is_game_over(&self, board: &mut Board) -> bool {
+        get_exploded_team(board).is_some()
+    }
+
+    fn duplicate(&self) -> Box<dyn WinConditions> {
+        Box::new(AtomicWinConditions)
+    }
+}
+
+// The team that has lost all of its royal pieces, on boards that have them.
+pub fn get_dethroned_team(board: &Board) -> Option<i16> {
+    if !board.royalties.iter().any(|royalty| royalty.is_royal()) {
+        return None;
+    }
+
+    (0..board.teams).find(|team| get_king_pos(board, *team).is_none())
+}
+
+/*
+    King capture: checks don't have to be answered, and a team loses once its royal pieces are captured.
+    This goes with `KingCaptureRestrictor`, which lets royal pieces be left in check.
+*/
+pub struct KingCaptureWinConditions;
+
+impl WinConditions for KingCaptureWinConditions {
+    fn compute(&self, board: &mut Board, actions: &Vec<Action>) -> GameResult {
+        match get_dethroned_team(board) {
+            Some(team) if team == board.moving_team => GameResult::Lose,
+            Some(_) => GameResult::Win,
+            None => compute_checkmate(board, actions, is_in_check),
+        }
+    }
+
+    fn is_game_over(&self, board: &mut Board) -> bool {
+        get_dethroned_team(board).is_some()
+    }
+
+    fn duplicate(&self) -> Box<dyn WinConditions> {
+        Box::new(KingCaptureWinConditions)
+    }
+}
+
+/*
+    Antichess: a team wins when it can't move anymore, whether it has lost every piece or is stalemated.
+    There's no check, so the rest only comes down to the fifty-move rule and repetitions.
+*/
+pub struct AntichessWinConditions;
+
+impl WinConditions for AntichessWinConditions {
+    fn compute(&self, board: &mut Board, actions: &Vec<Action>) -> GameResult {
+        if actions.is_empty() {
+            return GameResult::Win;
+        }
+
+        if is_draw_by_fifty_moves(board) || is_draw_by_repetition(board) {
             return GameResult::Draw;
         }
 
@@ -73,6 +397,30 @@ impl WinConditions for DefaultWinConditions {
     }
 
     fn duplicate(&self) -> Box<dyn WinConditions> {
-        Box::new(DefaultWinConditions)
+        Box::new(AntichessWinConditions)
+    }
+}
+
+/*
+    Horde: white has no king and loses once all of its pieces are captured, while black is checkmated as usual.
+*/
+pub struct HordeWinConditions;
+
+impl WinConditions for HordeWinConditions {
+    fn compute(&self, board: &mut Board, actions: &Vec<Action>) -> GameResult {
+        let moving_team = board.moving_team;
+        let has_pieces = board
+            .pieces
+            .iter()
+            .any(|piece| board.get_team(board.state[piece.pos as usize]) == moving_team);
+        if !has_pieces {
+            return GameResult::Lose;
+        }
+
+        compute_checkmate(board, actions, is_in_check)
+    }
+
+    fn duplicate(&self) -> Box<dyn WinConditions> {
+        Box::new(HordeWinConditions)
     }
 }
@@ -1,4 +1,7 @@
-use crate::communication::{Communicator, UCICommunicator};
+use crate::{
+    communication::{Communicator, UCICommunicator},
+    error::LotisaError,
+};
 
 use super::{Action, Board};
 
@@ -35,3 +38,118 @@ pub fn perft(uci: &mut UCICommunicator, depth: i16, last_action: Option<Action>)
 
     nodes
 }
+
+/*
+    Perft, split up by the first move. Each move is paired with its node count, and encoded in UCI notation.
+    Meant for tracking down movegen bugs by comparing against another engine's "go perft" output.
+*/
+pub fn perft_divide(uci: &mut UCICommunicator, depth: i16) -> Vec<(String, u64)> {
+    let mut divide = vec![];
+    if depth == 0 {
+        return divide;
+    }
+
+    let actions = uci.board.generate_legal_moves();
+    for action in &actions {
+        uci.board.make_move(*action);
+        let nodes = perft(uci, depth - 1, Some(*action));
+        uci.board.undo_move();
+
+        divide.push((uci.encode(action), nodes));
+    }
+
+    divide
+}
+
+/*
+    A position from a perft EPD file, along with the node count expected at each depth.
+    Lines look like "<fen> ;D1 20 ;D2 400 ;D3 8902".
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct PerftSuiteEntry {
+    pub fen: String,
+    pub depths: Vec<(i16, u64)>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PerftMismatch {
+    pub fen: String,
+    pub depth: i16,
+    pub expected: u64,
+    pub actual: u64,
+}
+
+pub fn parse_perft_epd_line(line: &str) -> Result<PerftSuiteEntry, String> {
+    let mut parts = line.split(';');
+    let fen = parts.next().unwrap_or("").trim();
+    if fen.is_empty() {
+        return Err(format!("Missing FEN: {}", line));
+    }
+
+    let mut depths = vec![];
+    for part in parts {
+        let part = part.trim();
+        if part.is_empty() {
+            continue;
+        }
+
+        let mut fields = part.split_whitespace();
+        let depth = fields
+            .next()
+            .and_then(|label| label.strip_prefix('D'))
+            .and_then(|depth| depth.parse::<i16>().ok());
+        let nodes = fields.next().and_then(|nodes| nodes.parse::<u64>().ok());
+        match (depth, nodes) {
+            (Some(depth), Some(nodes)) => depths.push((depth, nodes)),
+            _ => return Err(format!("Invalid perft result \"{}\" in: {}", part, line)),
+        }
+    }
+
+    Ok(PerftSuiteEntry {
+        fen: fen.to_string(),
+        depths,
+    })
+}
+
+/*
+    Blank lines and lines starting with "#" are skipped.
+*/
+pub fn parse_perft_epd(contents: &str) -> Result<Vec<PerftSuiteEntry>, String> {
+    contents
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(parse_perft_epd_line)
+        .collect()
+}
+
+/*
+    Runs perft for every depth of the entry (skipping anything deeper than `max_depth`), returning the depths that didn't match.
+*/
+pub fn check_perft_entry(
+    entry: &PerftSuiteEntry,
+    max_depth: Option<i16>,
+) -> Result<Vec<PerftMismatch>, LotisaError> {
+    let mut uci = Board::try_load_fen(&entry.fen)?;
+    let mut mismatches = vec![];
+
+    for &(depth, expected) in &entry.depths {
+        if let Some(max_depth) = max_depth {
+            if depth > max_depth {
+                continue;
+            }
+        }
+
+        let actual = perft(&mut uci, depth, None);
+        if actual != expected {
+            mismatches.push(PerftMismatch {
+                fen: entry.fen.clone(),
+                depth,
+                expected,
+                actual,
+            });
+        }
+    }
+
+    Ok(mismatches)
+}
@@ -0,0 +1,690 @@
+use std::fs;
+
+use toml::{Table, Value};
+
+use crate::{
+    communication::{Communicator, UCICommunicator},
+    error::LotisaError,
+};
+
+use super::{
+    AntichessWinConditions, AtomicPiece, AtomicRestrictor, AtomicWinConditions, BetzaDefinition,
+    Board, BoardLayout, CaptureRestrictor, CompoundPiece, DefaultRestrictor, DefaultWinConditions,
+    HordeWinConditions, KingCaptureRestrictor, KingCaptureWinConditions,
+    KingOfTheHillWinConditions, KingPiece, KingProximityRestrictor, NoCheckRestrictor,
+    NoRepetitionRestrictor, PawnConfig, PawnPiece, Piece, PieceLookup, PieceMapLookup, Restrictor,
+    RoyalPiece, Royalty, ThreeCheckWinConditions, WinConditions, ZoneRestrictor, COMPOUND_PIECES,
+    FEN_PIECES,
+};
+
+// The default variant, which also shows the format of variant files.
+pub const CHESS_VARIANT: &str = include_str!("../../variants/chess.toml");
+pub const CRAZYHOUSE_VARIANT: &str = include_str!("../../variants/crazyhouse.toml");
+pub const ATOMIC_VARIANT: &str = include_str!("../../variants/atomic.toml");
+pub const ANTICHESS_VARIANT: &str = include_str!("../../variants/antichess.toml");
+pub const KING_OF_THE_HILL_VARIANT: &str = include_str!("../../variants/kingofthehill.toml");
+pub const THREE_CHECK_VARIANT: &str = include_str!("../../variants/3check.toml");
+pub const HORDE_VARIANT: &str = include_str!("../../variants/horde.toml");
+pub const CAPABLANCA_VARIANT: &str = include_str!("../../variants/capablanca.toml");
+pub const GRAND_VARIANT: &str = include_str!("../../variants/grand.toml");
+pub const EXTINCTION_VARIANT: &str = include_str!("../../variants/extinction.toml");
+
+const VARIANT_KEYS: [&str; 20] = [
+    "name",
+    "rows",
+    "cols",
+    "teams",
+    "startpos",
+    "promotions",
+    "promotion_ranks",
+    "promote_to_captured",
+    "double_move_ranks",
+    "pawns",
+    "castling",
+    "drops",
+    "explosions",
+    "checks",
+    "royal",
+    "extinction",
+    "duple_check",
+    "win_conditions",
+    "restrictors",
+    "pieces",
+];
+const PIECE_KEYS: [&str; 5] = ["letter", "betza", "piece", "value", "icon"];
+const PAWN_KEYS: [&str; 4] = [
+    "direction",
+    "double_move_ranks",
+    "promotion_ranks",
+    "promotions",
+];
+
+/*
+    Everything needed to play a variant: its board, its pieces and rules (through the `BoardLayout`) and its starting position.
+*/
+pub struct Variant {
+    pub name: String,
+    pub startpos: String,
+    pub layout: BoardLayout,
+}
+
+pub fn create_win_conditions(name: &str) -> Option<Box<dyn WinConditions>> {
+    match name {
+        "checkmate" => Some(Box::new(DefaultWinConditions)),
+        "kingofthehill" => Some(Box::new(KingOfTheHillWinConditions)),
+        "threecheck" => Some(Box::new(ThreeCheckWinConditions)),
+        "atomic" => Some(Box::new(AtomicWinConditions)),
+        "antichess" => Some(Box::new(AntichessWinConditions)),
+        "horde" => Some(Box::new(HordeWinConditions)),
+        "kingcapture" => Some(Box::new(KingCaptureWinConditions)),
+        _ => None,
+    }
+}
+
+// Restrictors that depend on the board (like the palace) are sized for a board `cols` files wide.
+pub fn create_restrictor(name: &str, cols: i16) -> Option<Box<dyn Restrictor>> {
+    match name {
+        "default" => Some(Box::new(DefaultRestrictor)),
+        "captures" => Some(Box::new(CaptureRestrictor::new())),
+        "atomic" => Some(Box::new(AtomicRestrictor)),
+        "nocheck" => Some(Box::new(NoCheckRestrictor)),
+        "kingsapart" => Some(Box::new(KingProximityRestrictor::new(1))),
+        "palace" => Some(Box::new(ZoneRestrictor::palace(cols))),
+        "norepetition" => Some(Box::new(NoRepetitionRestrictor)),
+        "kingcapture" => Some(Box::new(KingCaptureRestrictor)),
+        _ => None,
+    }
+}
+
+// A piece is either written as a Betza string, or named from the pieces Lotisa already has (see `COMPOUND_PIECES`).
+enum PieceDefinition {
+    Betza(BetzaDefinition),
+    Compound {
+        name: String,
+        value: Option<i32>,
+        icon: String,
+    },
+}
+
+impl PieceDefinition {
+    fn get_reach(&self) -> i16 {
+        match self {
+            PieceDefinition::Betza(definition) => definition.get_reach(),
+            // Every compound piece moves at most as far as a Knight outside of sliding.
+            PieceDefinition::Compound { .. } => 2,
+        }
+    }
+
+    fn create(&self, row_gap: i16) -> Box<dyn Piece> {
+        match self {
+            PieceDefinition::Betza(definition) => Box::new(definition.create(row_gap)),
+            PieceDefinition::Compound { name, value, icon } => {
+                let mut piece = CompoundPiece::from_name(name, row_gap)
+                    .expect("compound pieces are checked when parsing")
+                    .with_icon(icon);
+                if let Some(value) = value {
+                    piece = piece.with_value(*value);
+                }
+                Box::new(piece)
+            }
+        }
+    }
+}
+
+struct VariantPiece {
+    piece_type: i16,
+    letter: char,
+    definition: PieceDefinition,
+}
+
+fn invalid(message: String) -> LotisaError {
+    LotisaError::InvalidVariant(message)
+}
+
+fn check_keys(table: &Table, keys: &[&str], context: &str) -> Result<(), LotisaError> {
+    match table.keys().find(|key| !keys.contains(&key.as_str())) {
+        Some(key) => Err(invalid(format!("unknown key \"{}\" in {}", key, context))),
+        None => Ok(()),
+    }
+}
+
+fn get_string(table: &Table, key: &str) -> Result<Option<String>, LotisaError> {
+    match table.get(key) {
+        Some(Value::String(value)) => Ok(Some(value.clone())),
+        Some(_) => Err(invalid(format!("\"{}\" has to be a string", key))),
+        None => Ok(None),
+    }
+}
+
+fn get_number(table: &Table, key: &str, min: i64, max: i64) -> Result<Option<i64>, LotisaError> {
+    match table.get(key) {
+        Some(Value::Integer(value)) if *value >= min && *value <= max => Ok(Some(*value)),
+        Some(_) => Err(invalid(format!(
+            "\"{}\" has to be a number from {} to {}",
+            key, min, max
+        ))),
+        None => Ok(None),
+    }
+}
+
+fn get_bool(table: &Table, key: &str) -> Result<Option<bool>, LotisaError> {
+    match table.get(key) {
+        Some(Value::Boolean(value)) => Ok(Some(*value)),
+        Some(_) => Err(invalid(format!("\"{}\" has to be true or false", key))),
+        None => Ok(None),
+    }
+}
+
+fn get_strings(table: &Table, key: &str) -> Result<Option<Vec<String>>, LotisaError> {
+    let values = match table.get(key) {
+        Some(Value::Array(values)) => values,
+        Some(_) => return Err(invalid(format!("\"{}\" has to be a list", key))),
+        None => return Ok(None),
+    };
+
+    values
+        .iter()
+        .map(|value| match value {
+            Value::String(value) => Ok(value.clone()),
+            _ => Err(invalid(format!("\"{}\" has to be a list of strings", key))),
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map(Some)
+}
+
+fn get_numbers(
+    table: &Table,
+    key: &str,
+    min: i64,
+    max: i64,
+) -> Result<Option<Vec<i64>>, LotisaError> {
+    let values = match table.get(key) {
+        Some(Value::Array(values)) => values,
+        Some(_) => return Err(invalid(format!("\"{}\" has to be a list", key))),
+        None => return Ok(None),
+    };
+
+    values
+        .iter()
+        .map(|value| match value {
+            Value::Integer(value) if *value >= min && *value <= max => Ok(*value),
+            _ => Err(invalid(format!(
+                "\"{}\" has to be a list of numbers from {} to {}",
+                key, min, max
+            ))),
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map(Some)
+}
+
+fn parse_letter(letter: &str) -> Result<char, LotisaError> {
+    let mut chars = letter.chars();
+    match (chars.next(), chars.next()) {
+        (Some(letter), None) if letter.is_ascii_alphabetic() => Ok(letter.to_ascii_lowercase()),
+        _ => Err(invalid(format!("\"{}\" isn't a piece letter", letter))),
+    }
+}
+
+fn parse_pieces(table: &Table) -> Result<Vec<VariantPiece>, LotisaError> {
+    let pieces = match table.get("pieces") {
+        Some(Value::Array(pieces)) => pieces,
+        Some(_) => return Err(invalid("\"pieces\" has to be a list of tables".to_string())),
+        None => return Ok(vec![]),
+    };
+
+    let mut next_piece_type = FEN_PIECES.len() as i16;
+    let mut variant_pieces: Vec<VariantPiece> = Vec::with_capacity(pieces.len());
+    for piece in pieces {
+        let piece = match piece {
+            Value::Table(piece) => piece,
+            _ => return Err(invalid("\"pieces\" has to be a list of tables".to_string())),
+        };
+        check_keys(piece, &PIECE_KEYS, "a piece")?;
+
+        let letter = get_string(piece, "letter")?
+            .ok_or_else(|| invalid("a piece is missing its \"letter\"".to_string()))?;
+        let letter = parse_letter(&letter)?;
+        if letter == 'p' || letter == 'k' {
+            return Err(invalid("pawns and kings can't be redefined".to_string()));
+        }
+        if variant_pieces.iter().any(|piece| piece.letter == letter) {
+            return Err(invalid(format!("'{}' is defined twice", letter)));
+        }
+
+        let value = get_number(piece, "value", 0, i32::MAX as i64)?.map(|value| value as i32);
+        let icon = get_string(piece, "icon")?.unwrap_or(letter.to_ascii_uppercase().to_string());
+        let definition = match (get_string(piece, "betza")?, get_string(piece, "piece")?) {
+            (Some(betza), None) => {
+                let mut definition = BetzaDefinition::parse(&betza)?;
+                if let Some(value) = value {
+                    definition = definition.with_value(value);
+                }
+                PieceDefinition::Betza(definition.with_icon(&icon))
+            }
+            (None, Some(name)) => {
+                if !COMPOUND_PIECES.contains(&name.as_str()) {
+                    return Err(invalid(format!("unknown piece \"{}\"", name)));
+                }
+                PieceDefinition::Compound { name, value, icon }
+            }
+            (Some(_), Some(_)) => {
+                return Err(invalid(format!(
+                    "'{}' can't have both a \"betza\" and a \"piece\"",
+                    letter
+                )))
+            }
+            (None, None) => {
+                return Err(invalid(format!(
+                    "'{}' is missing its \"betza\" or \"piece\"",
+                    letter
+                )))
+            }
+        };
+
+        // Standard letters replace their piece, anything else is a new piece type.
+        let piece_type = match FEN_PIECES.iter().position(|piece| *piece == letter) {
+            Some(piece_type) => piece_type as i16,
+            None => {
+                next_piece_type += 1;
+                next_piece_type - 1
+            }
+        };
+
+        variant_pieces.push(VariantPiece {
+            piece_type,
+            letter,
+            definition,
+        });
+    }
+
+    Ok(variant_pieces)
+}
+
+fn parse_direction(direction: &str) -> Result<(i16, i16), LotisaError> {
+    match direction {
+        "up" => Ok((-1, 0)),
+        "down" => Ok((1, 0)),
+        "left" => Ok((0, -1)),
+        "right" => Ok((0, 1)),
+        _ => Err(invalid(format!("unknown pawn direction \"{}\"", direction))),
+    }
+}
+
+/*
+    Reads the "pawns" list, with one table for each team's pawns.
+    Anything a table leaves out comes from `default`, which has the variant's top-level pawn keys.
+*/
+fn parse_pawns(
+    table: &Table,
+    (rows, cols): (i16, i16),
+    default: &PawnConfig,
+    parse_promotions: &dyn Fn(Vec<String>) -> Result<Vec<i16>, LotisaError>,
+) -> Result<Option<Vec<PawnConfig>>, LotisaError> {
+    let pawns = match table.get("pawns") {
+        Some(Value::Array(pawns)) => pawns,
+        Some(_) => return Err(invalid("\"pawns\" has to be a list of tables".to_string())),
+        None => return Ok(None),
+    };
+
+    let mut configs: Vec<PawnConfig> = Vec::with_capacity(pawns.len());
+    for pawn in pawns {
+        let pawn = match pawn {
+            Value::Table(pawn) => pawn,
+            _ => return Err(invalid("\"pawns\" has to be a list of tables".to_string())),
+        };
+        check_keys(pawn, &PAWN_KEYS, "a pawn")?;
+
+        let direction = get_string(pawn, "direction")?
+            .ok_or_else(|| invalid("a pawn is missing its \"direction\"".to_string()))?;
+        let mut config = PawnConfig {
+            direction: parse_direction(&direction)?,
+            ..default.clone()
+        };
+        let ranks = if config.direction.0 != 0 { rows } else { cols } as i64;
+
+        if let Some(double_move_ranks) = get_numbers(pawn, "double_move_ranks", 1, ranks)? {
+            config = config.with_double_move_ranks(
+                double_move_ranks.iter().map(|rank| *rank as i16).collect(),
+            );
+        }
+        if let Some(promotion_ranks) = get_numbers(pawn, "promotion_ranks", 1, ranks)? {
+            config = config
+                .with_promotion_zone(promotion_ranks.iter().map(|rank| *rank as i16).collect());
+        }
+        if let Some(promotions) = get_strings(pawn, "promotions")? {
+            config = config.with_promotions(parse_promotions(promotions)?);
+        }
+        configs.push(config);
+    }
+
+    if configs.is_empty() {
+        return Err(invalid("\"pawns\" can't be empty".to_string()));
+    }
+    Ok(Some(configs))
+}
+
+impl Variant {
+    /*
+        Reads a variant from TOML, see `variants/chess.toml` for the format.
+        Only "name" and "startpos" are needed, everything else defaults to standard chess.
+    */
+    pub fn parse(contents: &str) -> Result<Variant, LotisaError> {
+        let table = contents
+            .parse::<Table>()
+            .map_err(|error| invalid(error.message().to_string()))?;
+        check_keys(&table, &VARIANT_KEYS, "the variant")?;
+
+        let name =
+            get_string(&table, "name")?.ok_or_else(|| invalid("missing \"name\"".to_string()))?;
+        let startpos = get_string(&table, "startpos")?
+            .ok_or_else(|| invalid("missing \"startpos\"".to_string()))?;
+
+        let rows = get_number(&table, "rows", 1, 128)?.unwrap_or(8) as i16;
+        let cols = get_number(&table, "cols", 1, 128)?.unwrap_or(8) as i16;
+        let teams = get_number(&table, "teams", 1, 16)?.unwrap_or(2) as i16;
+        let castling = get_bool(&table, "castling")?.unwrap_or(true);
+        let drops = get_bool(&table, "drops")?.unwrap_or(false);
+        let explosions = get_bool(&table, "explosions")?.unwrap_or(false);
+        let checks = get_number(&table, "checks", 1, i16::MAX as i64)?;
+
+        let pieces = parse_pieces(&table)?;
+        let piece_types = pieces
+            .iter()
+            .map(|piece| piece.piece_type + 1)
+            .max()
+            .unwrap_or(0)
+            .max(FEN_PIECES.len() as i16);
+        let buffer_amount = pieces
+            .iter()
+            .map(|piece| piece.definition.get_reach())
+            .max()
+            .unwrap_or(0)
+            .max(2);
+
+        let get_piece_type = |letter: char| {
+            pieces
+                .iter()
+                .find(|piece| piece.letter == letter)
+                .map(|piece| piece.piece_type)
+                .or_else(|| {
+                    FEN_PIECES
+                        .iter()
+                        .position(|piece| *piece == letter)
+                        .map(|piece_type| piece_type as i16)
+                })
+        };
+        let parse_piece_types = |letters: Vec<String>| {
+            letters
+                .iter()
+                .map(|letter| {
+                    get_piece_type(parse_letter(letter)?)
+                        .ok_or_else(|| invalid(format!("unknown piece \"{}\"", letter)))
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let promotions = match get_strings(&table, "promotions")? {
+            Some(promotions) => Some(parse_piece_types(promotions)?),
+            None => None,
+        };
+        let promotion_ranks =
+            get_number(&table, "promotion_ranks", 1, rows as i64)?.unwrap_or(1) as i16;
+        let double_move_ranks = get_numbers(&table, "double_move_ranks", 1, rows as i64)?
+            .map(|ranks| ranks.iter().map(|rank| *rank as i16).collect::<Vec<_>>());
+
+        let mut default_pawn =
+            PawnConfig::up().with_promotion_zone((0..promotion_ranks).map(|rank| -rank).collect());
+        default_pawn.promotions = promotions.clone();
+        default_pawn.double_move_ranks = double_move_ranks.clone();
+        let pawn_configs = parse_pawns(&table, (rows, cols), &default_pawn, &parse_piece_types)?;
+
+        /*
+            Kings are the only royal pieces unless "royal" says otherwise, and "duple_check" makes a team with several only be in check once all of them are attacked.
+            Pieces listed in "extinction" lose the game once a team has none of them left.
+        */
+        let royal = get_strings(&table, "royal")?.unwrap_or(vec!["k".to_string()]);
+        let royal = parse_piece_types(royal)?;
+        let extinction = parse_piece_types(get_strings(&table, "extinction")?.unwrap_or(vec![]))?;
+        if royal
+            .iter()
+            .any(|piece_type| extinction.contains(piece_type))
+        {
+            return Err(invalid(
+                "a piece can't be both royal and extinction".to_string(),
+            ));
+        }
+        let royal_royalty = if get_bool(&table, "duple_check")?.unwrap_or(false) {
+            Royalty::Shared
+        } else {
+            Royalty::Royal
+        };
+        let royalties = (0..piece_types)
+            .map(|piece_type| {
+                if royal.contains(&piece_type) {
+                    royal_royalty
+                } else if extinction.contains(&piece_type) {
+                    Royalty::Extinction
+                } else {
+                    Royalty::None
+                }
+            })
+            .collect::<Vec<_>>();
+
+        // Pawns promoting only to captured pieces can't have more of a piece than the first team starts with.
+        let promotion_limits = if get_bool(&table, "promote_to_captured")?.unwrap_or(false) {
+            let mut limits = vec![0; piece_types as usize];
+            let placement = startpos.split(['[', ' ']).next().unwrap_or("");
+            for letter in placement
+                .chars()
+                .filter(|letter| letter.is_ascii_uppercase())
+            {
+                if let Some(piece_type) = get_piece_type(letter.to_ascii_lowercase()) {
+                    limits[piece_type as usize] += 1;
+                }
+            }
+            Some(limits)
+        } else {
+            None
+        };
+
+        let win_conditions =
+            get_string(&table, "win_conditions")?.unwrap_or("checkmate".to_string());
+        let win_conditions = create_win_conditions(&win_conditions)
+            .ok_or_else(|| invalid(format!("unknown win conditions \"{}\"", win_conditions)))?;
+        let restrictors = get_strings(&table, "restrictors")?
+            .unwrap_or(vec!["default".to_string()])
+            .iter()
+            .map(|restrictor| {
+                create_restrictor(restrictor, cols)
+                    .ok_or_else(|| invalid(format!("unknown restrictor \"{}\"", restrictor)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let piece_definitions = pieces
+            .into_iter()
+            .map(|piece| (piece.piece_type, piece.letter, piece.definition))
+            .collect::<Vec<_>>();
+        let create_piece_lookup = move |row_gap: i16| {
+            let mut map = PieceMapLookup::default_map(row_gap);
+            let mut pawn = match &pawn_configs {
+                Some(configs) => PawnPiece::with_configs(configs.clone()),
+                None => {
+                    let mut pawn = match &promotions {
+                        Some(promotions) => PawnPiece::with_promotions(promotions.clone()),
+                        None => PawnPiece::default(),
+                    }
+                    .with_promotion_ranks(promotion_ranks);
+                    if let Some(ranks) = &double_move_ranks {
+                        pawn = pawn.with_double_move_ranks(ranks.clone());
+                    }
+                    pawn
+                }
+            };
+            if let Some(limits) = &promotion_limits {
+                pawn = pawn.with_promotion_limits(limits.clone());
+            }
+            map.insert(0, Box::new(pawn));
+            if !castling {
+                map.insert(5, Box::new(KingPiece::without_castling(row_gap)));
+            }
+
+            let mut lookup = PieceMapLookup::new(map);
+            for (piece_type, letter, definition) in &piece_definitions {
+                lookup = lookup.with_piece(*piece_type, *letter, definition.create(row_gap));
+            }
+            for (piece_type, royalty) in royalties.iter().enumerate() {
+                let piece_type = piece_type as i16;
+                let piece = match lookup.map.remove(&piece_type) {
+                    Some(piece) if piece.get_royalty() != *royalty => {
+                        Box::new(RoyalPiece::new(piece, *royalty)) as Box<dyn Piece>
+                    }
+                    Some(piece) => piece,
+                    None => continue,
+                };
+                lookup.map.insert(piece_type, piece);
+            }
+            if explosions {
+                lookup.map = lookup
+                    .map
+                    .into_iter()
+                    .map(|(piece_type, piece)| {
+                        (
+                            piece_type,
+                            Box::new(AtomicPiece::new(piece)) as Box<dyn Piece>,
+                        )
+                    })
+                    .collect();
+            }
+            Box::new(lookup) as Box<dyn PieceLookup>
+        };
+
+        let mut layout = BoardLayout::new(
+            piece_types,
+            teams,
+            (rows, cols),
+            Box::new(create_piece_lookup),
+        )
+        .with_buffer_amount(buffer_amount)
+        .with_win_conditions(win_conditions)
+        .with_restrictors(restrictors);
+        if drops {
+            layout = layout.with_drops();
+        }
+        if let Some(checks) = checks {
+            layout = layout.with_checks(checks as i16);
+        }
+
+        let variant = Variant {
+            name,
+            startpos,
+            layout,
+        };
+        variant.try_load_startpos()?;
+
+        Ok(variant)
+    }
+
+    pub fn load(path: &str) -> Result<Variant, LotisaError> {
+        let contents = fs::read_to_string(path)
+            .map_err(|error| invalid(format!("couldn't read {}: {}", path, error)))?;
+        Variant::parse(&contents)
+    }
+
+    pub fn chess() -> Variant {
+        Variant::parse(CHESS_VARIANT).unwrap()
+    }
+
+    pub fn crazyhouse() -> Variant {
+        Variant::parse(CRAZYHOUSE_VARIANT).unwrap()
+    }
+
+    pub fn atomic() -> Variant {
+        Variant::parse(ATOMIC_VARIANT).unwrap()
+    }
+
+    pub fn antichess() -> Variant {
+        Variant::parse(ANTICHESS_VARIANT).unwrap()
+    }
+
+    pub fn king_of_the_hill() -> Variant {
+        Variant::parse(KING_OF_THE_HILL_VARIANT).unwrap()
+    }
+
+    pub fn three_check() -> Variant {
+        Variant::parse(THREE_CHECK_VARIANT).unwrap()
+    }
+
+    pub fn horde() -> Variant {
+        Variant::parse(HORDE_VARIANT).unwrap()
+    }
+
+    pub fn capablanca() -> Variant {
+        Variant::parse(CAPABLANCA_VARIANT).unwrap()
+    }
+
+    pub fn grand() -> Variant {
+        Variant::parse(GRAND_VARIANT).unwrap()
+    }
+
+    pub fn extinction() -> Variant {
+        Variant::parse(EXTINCTION_VARIANT).unwrap()
+    }
+
+    // The variants that are always available, without a variant file.
+    pub fn built_in() -> Vec<Variant> {
+        vec![
+            Variant::chess(),
+            Variant::crazyhouse(),
+            Variant::atomic(),
+            Variant::antichess(),
+            Variant::king_of_the_hill(),
+            Variant::three_check(),
+            Variant::horde(),
+            Variant::capablanca(),
+            Variant::grand(),
+            Variant::extinction(),
+        ]
+    }
+
+    pub fn find_built_in(name: &str) -> Option<Variant> {
+        Variant::built_in()
+            .into_iter()
+            .find(|variant| variant.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn try_load_fen(&self, fen: &str) -> Result<UCICommunicator, LotisaError> {
+        Board::try_load_variant_fen(fen, &self.layout)
+    }
+
+    pub fn try_load_startpos(&self) -> Result<UCICommunicator, LotisaError> {
+        self.try_load_fen(&self.startpos)
+    }
+
+    /*
+        Plays moves in UCI notation from the starting position, skipping move numbers like `Board::try_load_uci_pgn`.
+    */
+    pub fn try_load_uci_pgn(&self, uci_pgn: &str) -> Result<UCICommunicator, LotisaError> {
+        let mut uci = self.try_load_startpos()?;
+
+        for action in uci_pgn.split_whitespace() {
+            if action.starts_with(|char: char| char.is_ascii_digit()) {
+                continue;
+            }
+
+            let action = uci.try_decode(action)?;
+            uci.board.make_move(action);
+        }
+
+        Ok(uci)
+    }
+
+    pub fn load_startpos(&self) -> UCICommunicator {
+        self.try_load_startpos()
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+}
+
+impl Default for Variant {
+    fn default() -> Self {
+        Variant::chess()
+    }
+}
@@ -2,39 +2,123 @@ use rand::prelude::*;
 
 use crate::boards::Board;
 
-pub fn generate_zobrist(pieces: i16, teams: i16, positions: i16) -> Vec<usize> {
-    let mut rng = rand_hc::Hc128Rng::from_entropy();
-    let len = (((pieces * teams) + 2) * 2) * positions;
+/*
+    Keys are generated from a fixed seed by default, so hashes (and anything depending on them, like the transposition table) are the same on every run.
+*/
+pub const DEFAULT_ZOBRIST_SEED: u64 = 0x4c6f74697361;
 
-    let mut zobrist: Vec<usize> = Vec::with_capacity(len as usize);
-    for i in 0..len {
-        zobrist.insert(i as usize, rng.next_u64() as usize);
+pub enum ZobristKeys {
+    Seed(u64),
+    // An existing table, laid out like the ones `generate_zobrist` creates.
+    Table(Vec<usize>),
+}
+
+// Pocket counts from here on share a key, which only costs a few collisions in positions that don't come up in real games.
+pub const MAX_POCKET_KEYS: usize = 32;
+// Remaining checks (for Three-check) past this many share a key.
+pub const MAX_CHECK_KEYS: usize = 16;
+// The most keys a board can have, so a huge board size in a FEN is turned down instead of running out of memory.
+pub const MAX_ZOBRIST_LEN: usize = 1 << 24;
+
+// The keys for pieces on squares, which come before the keys for pockets.
+fn square_zobrist_len(pieces: i16, teams: i16, positions: i16) -> usize {
+    ((pieces as usize * teams as usize) + 2) * 2 * positions as usize
+}
+
+fn pocket_zobrist_len(pieces: i16, teams: i16) -> usize {
+    pieces as usize * teams as usize * MAX_POCKET_KEYS
+}
+
+pub fn zobrist_len(pieces: i16, teams: i16, positions: i16) -> usize {
+    square_zobrist_len(pieces, teams, positions)
+        + pocket_zobrist_len(pieces, teams)
+        + (teams as usize * MAX_CHECK_KEYS)
+}
+
+pub fn generate_zobrist(pieces: i16, teams: i16, positions: i16, seed: u64) -> Vec<usize> {
+    let mut rng = rand_hc::Hc128Rng::seed_from_u64(seed);
+    let len = zobrist_len(pieces, teams, positions);
+
+    let mut zobrist: Vec<usize> = Vec::with_capacity(len);
+    for _ in 0..len {
+        zobrist.push(rng.next_u64() as usize);
     }
 
     zobrist
 }
 
+/*
+    Keys are laid out as `pos + positions * first_move + (positions * 2) * piece`.
+    Piece values 0 and 1 (out of bounds and empty squares) are never hashed, so the first few keys are free to use for the moving team.
+*/
+pub fn zobrist_index(board: &Board, pos: i16, piece: i16, first_move: bool) -> usize {
+    let positions = board.row_gap as usize * board.col_gap as usize;
+    pos as usize + positions * if first_move { 1 } else { 0 } + (positions * 2) * piece as usize
+}
+
+pub fn hash_piece(board: &Board, pos: i16, piece: i16, first_move: bool) -> usize {
+    board.zobrist[zobrist_index(board, pos, piece, first_move)]
+}
+
+/*
+    Each team has a key for every count of every piece type in its pocket, after the keys for squares.
+    An empty pocket isn't hashed, so boards without pockets hash the same as before pockets existed.
+*/
+pub fn hash_pocket(board: &Board, team: i16, piece_type: i16, count: i16) -> usize {
+    if count <= 0 {
+        return 0;
+    }
+
+    let positions = board.row_gap * board.col_gap;
+    let pocket = (team as usize * board.piece_types as usize) + piece_type as usize;
+    let count = (count as usize).min(MAX_POCKET_KEYS);
+    board.zobrist[square_zobrist_len(board.piece_types, board.teams, positions)
+        + (pocket * MAX_POCKET_KEYS)
+        + count
+        - 1]
+}
+
+/*
+    Each team has a key for every number of checks it still needs to give, after the keys for pockets.
+    Only boards that count checks hash them.
+*/
+pub fn hash_checks(board: &Board, team: i16, remaining: i16) -> usize {
+    let positions = board.row_gap * board.col_gap;
+    let remaining = (remaining.max(0) as usize).min(MAX_CHECK_KEYS - 1);
+    board.zobrist[square_zobrist_len(board.piece_types, board.teams, positions)
+        + pocket_zobrist_len(board.piece_types, board.teams)
+        + (team as usize * MAX_CHECK_KEYS)
+        + remaining]
+}
+
+pub fn hash_team(board: &Board, team: i16) -> usize {
+    board.zobrist[team as usize]
+}
+
+/*
+    Hashes the whole board from scratch.
+    `Board::hash` is kept up to date incrementally while moves are made, so this is only needed after editing `state` or `pieces` by hand.
+*/
 pub fn hash_board(board: &Board, moving_team: i16, zobrist: &Vec<usize>) -> usize {
     let mut hash: usize = 0;
-    let mut ind: i16 = 0;
-    let positions = board.row_gap * board.col_gap;
     hash ^= zobrist[moving_team as usize];
-    for piece in &board.state {
-        let piece = *piece;
-        if piece == 0 {
-            ind += 1;
-            continue;
-        }
+    for piece in &board.pieces {
+        let piece_value = board.state[piece.pos as usize];
+        hash ^= zobrist[zobrist_index(board, piece.pos, piece_value, piece.first_move)];
+    }
 
-        let first_move = board
-            .pieces
-            .iter()
-            .find(|piece| piece.pos == ind)
-            .map_or(false, |piece| piece.first_move);
+    if let Some(pockets) = &board.pockets {
+        for (team, pocket) in pockets.iter().enumerate() {
+            for (piece_type, count) in pocket.iter().enumerate() {
+                hash ^= hash_pocket(board, team as i16, piece_type as i16, *count);
+            }
+        }
+    }
 
-        hash ^= zobrist
-            [(ind + positions * if first_move { 1 } else { 0 } + (positions * 2) * piece) as usize];
-        ind += 1;
+    if let Some(remaining_checks) = &board.remaining_checks {
+        for (team, remaining) in remaining_checks.iter().enumerate() {
+            hash ^= hash_checks(board, team as i16, *remaining);
+        }
     }
 
     return hash;
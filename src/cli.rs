@@ -1,16 +1,31 @@
-use rand::{seq::SliceRandom, SeedableRng};
-use regex::Regex;
-use std::io::{BufRead, Stdin};
+use std::{
+    io::{BufRead, Stdin},
+    sync::{atomic::Ordering, Arc, Mutex},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
 
 use crate::{
-    boards::Board,
-    communication::Communicator,
+    boards::{is_polyglot_board, polyglot_hash, Variant},
+    commands::run_self_test,
+    communication::{Communicator, UCICommunicator},
     engine::{
-        root_search, search, SearchInfo, MAX_DEPTH, MAX_KILLER_MOVES, MAX_VALUE, MIN_VALUE, PV,
+        root_search, MultiplayerSearch, SearchInfo, SearchLimits, SearchOptions, SearchSignals,
+        TimeManager, TranspositionTable, MAX_DEPTH, MAX_KILLER_MOVES, PV,
     },
+    error::LotisaError,
 };
 
-pub fn create_info() -> SearchInfo {
+// Used for a bare "go", with no limits at all.
+const DEFAULT_MOVE_TIME: u128 = 1_000;
+
+pub const MAX_HASH_MB: usize = 65536;
+// Search is single-threaded for now.
+pub const MAX_THREADS: usize = 1;
+pub const MAX_MULTI_PV: usize = 64;
+pub const MAX_MOVE_OVERHEAD: u128 = 5000;
+
+pub fn create_info(options: SearchOptions, signals: Arc<SearchSignals>) -> SearchInfo {
     SearchInfo {
         root_depth: 0,
         root_nodes: 0,
@@ -20,79 +35,421 @@ pub fn create_info() -> SearchInfo {
             table: [[None; MAX_DEPTH]; MAX_DEPTH],
             length: [0; MAX_DEPTH],
         },
-        transposition_table: vec![None; 9_000_000],
-        max_tt_size: 9_000_000,
+        transposition_table: TranspositionTable::new(options.hash_mb),
         killer_moves: [[None; MAX_DEPTH]; MAX_KILLER_MOVES],
         history_moves: vec![vec![vec![0; 120]; 120]; 2],
         counter_moves: vec![vec![None; 120]; 120],
         sel_depth: 0,
+        nodes: 0,
+        limits: SearchLimits::default(),
+        time_manager: TimeManager::default(),
+        signals,
+        stopped: false,
+        debug: false,
+        quiet: false,
+        options,
+        excluded_moves: Vec::with_capacity(MAX_MULTI_PV),
+    }
+}
+
+/*
+    Everything the search thread needs. It's locked for the whole search,
+    so commands that change the position have to wait for the search to end first.
+*/
+pub struct UCIState {
+    pub uci: UCICommunicator,
+    pub info: SearchInfo,
+    // Variants that can be picked with "UCI_Variant", and the index of the one being played.
+    pub variants: Vec<Variant>,
+    pub variant: usize,
+    // "UCI_Chess960", which changes how castling moves are written.
+    pub chess960: bool,
+}
+
+impl UCIState {
+    pub fn new(variants: Vec<Variant>, info: SearchInfo) -> UCIState {
+        UCIState {
+            uci: variants[0].load_startpos(),
+            info,
+            variants,
+            variant: 0,
+            chess960: false,
+        }
+    }
+
+    pub fn load_startpos(&self) -> Result<UCICommunicator, LotisaError> {
+        let mut uci = self.get_variant().try_load_startpos()?;
+        uci.board.chess960 = self.chess960;
+        Ok(uci)
+    }
+
+    pub fn get_variant(&self) -> &Variant {
+        &self.variants[self.variant]
+    }
+
+    /*
+        Switches to a variant by name, or loads one from a variant file if the name ends with ".toml".
+    */
+    pub fn set_variant(&mut self, name: &str) -> Result<(), LotisaError> {
+        let index = match self
+            .variants
+            .iter()
+            .position(|variant| variant.name.eq_ignore_ascii_case(name))
+        {
+            Some(index) => index,
+            None if name.ends_with(".toml") => {
+                self.variants.push(Variant::load(name)?);
+                self.variants.len() - 1
+            }
+            None => {
+                return Err(LotisaError::InvalidVariant(format!(
+                    "unknown variant \"{}\"",
+                    name
+                )))
+            }
+        };
+
+        self.variant = index;
+        self.uci = self.load_startpos()?;
+        Ok(())
+    }
+}
+
+pub fn print_uci_info(state: &UCIState) {
+    let defaults = SearchOptions::default();
+
+    println!("id name Lotisa 0.0.1");
+    println!("id author Corman");
+    println!(
+        "option name Hash type spin default {} min 1 max {}",
+        defaults.hash_mb, MAX_HASH_MB
+    );
+    println!("option name Clear Hash type button");
+    println!(
+        "option name Threads type spin default {} min 1 max {}",
+        defaults.threads, MAX_THREADS
+    );
+    println!(
+        "option name MultiPV type spin default {} min 1 max {}",
+        defaults.multi_pv, MAX_MULTI_PV
+    );
+    println!("option name Ponder type check default {}", defaults.ponder);
+    println!(
+        "option name Move Overhead type spin default {} min 0 max {}",
+        defaults.move_overhead, MAX_MOVE_OVERHEAD
+    );
+    println!(
+        "option name Multiplayer Search type combo default {} var paranoid var maxn",
+        defaults.multiplayer_search.name()
+    );
+    println!("option name UCI_Chess960 type check default false");
+    let variants = state
+        .variants
+        .iter()
+        .map(|variant| variant.name.as_str())
+        .collect::<Vec<_>>();
+    println!(
+        "option name UCI_Variant type combo default {} var {}",
+        state.get_variant().name,
+        variants.join(" var ")
+    );
+    println!("uciok");
+}
+
+/*
+    Applies a "setoption" command, returning false if the option or its value isn't valid.
+    Spin values outside of their range are clamped, like most GUIs expect.
+*/
+pub fn set_option(state: &mut UCIState, name: &str, value: &str) -> bool {
+    let options = &mut state.info.options;
+    match name.to_lowercase().as_str() {
+        "hash" => match value.parse::<usize>() {
+            Ok(hash_mb) => {
+                options.hash_mb = hash_mb.clamp(1, MAX_HASH_MB);
+                state.info.transposition_table.resize(options.hash_mb);
+            }
+            Err(_) => return false,
+        },
+        "clear hash" => state.info.transposition_table.clear(),
+        "threads" => match value.parse::<usize>() {
+            Ok(threads) => options.threads = threads.clamp(1, MAX_THREADS),
+            Err(_) => return false,
+        },
+        "multipv" => match value.parse::<usize>() {
+            Ok(multi_pv) => options.multi_pv = multi_pv.clamp(1, MAX_MULTI_PV),
+            Err(_) => return false,
+        },
+        "ponder" => match value.parse::<bool>() {
+            Ok(ponder) => options.ponder = ponder,
+            Err(_) => return false,
+        },
+        "move overhead" => match value.parse::<u128>() {
+            Ok(move_overhead) => options.move_overhead = move_overhead.min(MAX_MOVE_OVERHEAD),
+            Err(_) => return false,
+        },
+        "multiplayer search" => match MultiplayerSearch::parse(value) {
+            Some(multiplayer_search) => options.multiplayer_search = multiplayer_search,
+            None => return false,
+        },
+        "uci_chess960" => match value.parse::<bool>() {
+            Ok(chess960) => {
+                state.chess960 = chess960;
+                state.uci.board.chess960 = chess960;
+            }
+            Err(_) => return false,
+        },
+        "uci_variant" => {
+            if state.set_variant(value).is_err() {
+                return false;
+            }
+        }
+        _ => return false,
     }
+
+    true
 }
 
-pub fn run_uci(stdin: Stdin) {
-    let mut uci = Board::load_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w kqKQ -");
-    let mut info = create_info();
+/*
+    Parses "position [startpos | fen <fen>] [moves <moves>...]".
+    With `chess960`, castling moves are read as the king taking its own rook.
+*/
+pub fn parse_position(
+    tokens: &[&str],
+    variant: &Variant,
+    chess960: bool,
+) -> Result<UCICommunicator, LotisaError> {
+    let moves_index = tokens.iter().position(|token| *token == "moves");
+
+    let mut uci = match tokens.get(1) {
+        Some(&"startpos") => variant.try_load_startpos()?,
+        Some(&"fen") => {
+            let fen_end = moves_index.unwrap_or(tokens.len());
+            variant.try_load_fen(&tokens[2..fen_end].join(" "))?
+        }
+        _ => {
+            return Err(LotisaError::InvalidCommand(
+                "position needs \"startpos\" or \"fen\"".to_string(),
+            ))
+        }
+    };
+    uci.board.chess960 = chess960;
+
+    if let Some(moves_index) = moves_index {
+        for action in &tokens[(moves_index + 1)..] {
+            let action = uci.try_decode(action)?;
+            uci.board.make_move(action);
+        }
+    }
+
+    Ok(uci)
+}
+
+/*
+    Parses "go", returning the limits of the search and whether it's a ponder search.
+    Unknown parameters (like "searchmoves") are skipped.
+*/
+pub fn parse_go(tokens: &[&str], moving_team: i16) -> (SearchLimits, bool) {
+    let mut limits = SearchLimits::default();
+    let mut ponder = false;
+
+    let (time_token, inc_token) = match moving_team {
+        1 => ("btime", "binc"),
+        _ => ("wtime", "winc"),
+    };
+
+    let mut tokens = tokens.iter().skip(1);
+    while let Some(token) = tokens.next() {
+        let mut next_number = || tokens.next().and_then(|value| value.parse::<i64>().ok());
+        match *token {
+            "infinite" => limits.infinite = true,
+            "ponder" => ponder = true,
+            "depth" => limits.depth = next_number().map(|depth| depth as i16),
+            "nodes" => limits.nodes = next_number().map(|nodes| nodes.max(0) as u128),
+            "mate" => limits.mate = next_number().map(|mate| mate as i16),
+            "movetime" => limits.move_time = next_number().map(|time| time.max(0) as u128),
+            "movestogo" => limits.moves_to_go = next_number().map(|moves| moves.max(1) as u128),
+            token if token == time_token => {
+                limits.time = next_number().map(|time| time.max(0) as u128)
+            }
+            token if token == inc_token => limits.inc = next_number().map(|inc| inc.max(0) as u128),
+            _ => {}
+        }
+    }
+
+    let has_limit = limits.infinite
+        || limits.move_time.is_some()
+        || limits.time.is_some()
+        || limits.depth.is_some()
+        || limits.nodes.is_some()
+        || limits.mate.is_some();
+    if !has_limit && !ponder {
+        limits.move_time = Some(DEFAULT_MOVE_TIME);
+    }
+
+    (limits, ponder)
+}
+
+/*
+    Parses "setoption name <id> [value <x>]". Both the name and the value can contain spaces.
+*/
+pub fn parse_setoption(tokens: &[&str]) -> Option<(String, String)> {
+    let name_index = tokens.iter().position(|token| *token == "name")?;
+    let value_index = tokens.iter().position(|token| *token == "value");
+
+    let name_end = value_index.unwrap_or(tokens.len());
+    let name = tokens.get((name_index + 1)..name_end)?.join(" ");
+    let value = match value_index {
+        Some(value_index) => tokens[(value_index + 1)..].join(" "),
+        None => String::new(),
+    };
+
+    Some((name, value))
+}
+
+fn print_best_move(uci: &mut UCICommunicator, info: &SearchInfo) {
+    let best_move = match info.pv_table.table[0][0] {
+        Some(best_move) => best_move,
+        None => {
+            println!("bestmove 0000");
+            return;
+        }
+    };
+
+    match info.pv_table.table[0][1] {
+        Some(ponder_move) if info.options.ponder => println!(
+            "bestmove {} ponder {}",
+            uci.encode(&best_move),
+            uci.encode(&ponder_move)
+        ),
+        _ => println!("bestmove {}", uci.encode(&best_move)),
+    }
+}
+
+/*
+    Tells any running search to stop and waits for it to print its move.
+*/
+fn stop_search(signals: &SearchSignals, search_thread: &mut Option<JoinHandle<()>>) {
+    signals.stop.store(true, Ordering::Relaxed);
+    signals.pondering.store(false, Ordering::Relaxed);
+    if let Some(search_thread) = search_thread.take() {
+        search_thread.join().unwrap();
+    }
+}
+
+/*
+    `variants` are the variants "UCI_Variant" can pick from, starting with the one that's played by default.
+*/
+pub fn run_uci(stdin: Stdin, variants: Vec<Variant>) {
+    let signals = Arc::new(SearchSignals::default());
+    let mut debug = false;
+    let state = Arc::new(Mutex::new(UCIState::new(
+        variants,
+        create_info(SearchOptions::default(), signals.clone()),
+    )));
+    let mut search_thread: Option<JoinHandle<()>> = None;
 
     for line in stdin.lock().lines() {
         let line = line.unwrap();
-        if line == "ucinewgame" {
-            uci = Board::load_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w kqKQ -");
-            info = create_info();
-        } else if line.starts_with("position startpos moves ") {
-            let moves = &line[24..].split(" ").collect::<Vec<_>>();
-            uci = Board::load_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w kqKQ -");
-            for action in moves {
-                let action = uci.decode(action.to_string());
-                uci.board.make_move(action);
+        let tokens = line.split_whitespace().collect::<Vec<_>>();
+        let command = match tokens.first() {
+            Some(command) => *command,
+            None => continue,
+        };
+
+        match command {
+            "uci" => print_uci_info(&state.lock().unwrap()),
+            "isready" => println!("readyok"),
+            "debug" => debug = tokens.get(1) == Some(&"on"),
+            // Lotisa doesn't need registering, so there's nothing to do.
+            "register" => {}
+            "stop" => stop_search(&signals, &mut search_thread),
+            "ponderhit" => signals.pondering.store(false, Ordering::Relaxed),
+            "quit" => break,
+            "ucinewgame" => {
+                stop_search(&signals, &mut search_thread);
+                let mut state = state.lock().unwrap();
+                match state.load_startpos() {
+                    Ok(uci) => state.uci = uci,
+                    Err(error) => println!("info string error {}", error),
+                }
+                state.info = create_info(state.info.options.clone(), signals.clone());
             }
-            info = create_info();
-        } else if line.starts_with("position startpos fen ") {
-            let fen = &line[22..];
-            uci = Board::load_fen(fen);
-            info = create_info();
-        } else if line.starts_with("print-board") {
-            // Not UCI but why not
-
-            uci.board.print_board();
-        } else if line.starts_with("go") {
-            let mut max_time = 0;
-
-            let wtime_re = Regex::new(r"wtime (\d+)").unwrap();
-            let btime_re = Regex::new(r"btime (\d+)").unwrap();
-            let wtime_inc = Regex::new(r"winc (\d+)").unwrap();
-            let btime_inc = Regex::new(r"binc (\d+)").unwrap();
-
-            let (time_re, inc_re) = match uci.board.moving_team {
-                0 => (wtime_re, wtime_inc),
-                1 => (btime_re, btime_inc),
-                _ => (wtime_re, wtime_inc),
-            };
-
-            let mut found_capture = false;
-
-            if let Some(cap) = time_re.captures(&line) {
-                max_time = cap[1].parse::<u128>().unwrap() / 300;
-                found_capture = true;
+            "position" => {
+                stop_search(&signals, &mut search_thread);
+                let mut state = state.lock().unwrap();
+                // A bad position leaves the previous one in place, rather than taking the engine down.
+                match parse_position(&tokens, state.get_variant(), state.chess960) {
+                    Ok(uci) => {
+                        if debug {
+                            println!("info string position {}", uci.board.to_fen());
+                            // The key opening books look the position up by.
+                            if is_polyglot_board(&uci.board) {
+                                println!(
+                                    "info string polyglot key {:016x}",
+                                    polyglot_hash(&uci.board)
+                                );
+                            }
+                        }
+                        state.uci = uci;
+                    }
+                    Err(error) => println!("info string error {}", error),
+                }
             }
+            "setoption" => {
+                stop_search(&signals, &mut search_thread);
+                let (name, value) = match parse_setoption(&tokens) {
+                    Some(option) => option,
+                    None => continue,
+                };
 
-            if let Some(cap) = inc_re.captures(&line) {
-                max_time += cap[1].parse::<u128>().unwrap() / 10;
-                found_capture = true;
+                let mut state = state.lock().unwrap();
+                if !set_option(&mut state, &name, &value) {
+                    println!("info string invalid option {} with value {}", name, value);
+                }
             }
+            "go" => {
+                stop_search(&signals, &mut search_thread);
+
+                let moving_team = state.lock().unwrap().uci.board.moving_team;
+                let (limits, ponder) = parse_go(&tokens, moving_team);
+                if debug {
+                    println!("info string limits {:?} ponder {}", limits, ponder);
+                }
+
+                signals.stop.store(false, Ordering::Relaxed);
+                signals.pondering.store(ponder, Ordering::Relaxed);
+
+                let state = state.clone();
+                let signals = signals.clone();
+                search_thread = Some(thread::spawn(move || {
+                    let mut state = state.lock().unwrap();
+                    let UCIState { uci, info, .. } = &mut *state;
+                    info.limits = limits;
+                    info.debug = debug;
 
-            if !found_capture {
-                max_time = 1_000;
+                    let moving_team = uci.board.moving_team;
+                    root_search(info, uci, moving_team);
+
+                    // "go infinite" and "go ponder" can't answer until they're told to stop.
+                    while (limits.infinite || signals.is_pondering()) && !signals.is_stopped() {
+                        thread::sleep(Duration::from_millis(1));
+                    }
+
+                    print_best_move(uci, info);
+                }));
             }
+            "print-board" => {
+                // Not UCI but why not
 
-            let moving_team = uci.board.moving_team;
-            let score = root_search(&mut info, &mut uci, moving_team, max_time);
-            let best_move = info.pv_table.table[0][0];
-            if let Some(best_move) = best_move {
-                println!("bestmove {}", uci.encode(&best_move));
-            } else {
+                state.lock().unwrap().uci.board.print_board();
             }
-        } else if line == "isready" {
-            println!("readyok");
+            "test" => {
+                stop_search(&signals, &mut search_thread);
+                run_self_test();
+            }
+            _ => {}
         }
     }
+
+    stop_search(&signals, &mut search_thread);
 }
@@ -0,0 +1,362 @@
+/*
+    Subcommands of the `lotisa` binary, other than UCI (which lives in `cli`).
+    Each one takes the arguments after its name, and returns an error message for bad arguments.
+*/
+
+use std::sync::Arc;
+
+use crate::{
+    boards::{
+        check_perft_entry, get_chess960_fen, parse_perft_epd, perft, perft_divide, Board,
+        GameResult, PerftMismatch, Variant, CHESS960_POSITIONS,
+    },
+    cli::create_info,
+    communication::{parse_pgn, Communicator, UCICommunicator},
+    engine::{get_epoch_ms, root_search, SearchInfo, SearchLimits, SearchOptions, SearchSignals},
+};
+
+pub const DEFAULT_BENCH_DEPTH: i16 = 5;
+pub const DEFAULT_ANALYZE_DEPTH: i16 = 12;
+pub const DEFAULT_SELFPLAY_MOVE_TIME: u128 = 100;
+
+// Games are adjudicated as a draw past this many plies.
+pub const MAX_SELFPLAY_PLIES: usize = 400;
+
+// Commands with chess baked in (their positions, or the PGN parser), which can't play another variant.
+pub const CHESS_ONLY_COMMANDS: [&str; 4] = ["bench", "perft-suite", "annotate", "test"];
+
+pub const USAGE: &str = "Usage:
+    lotisa [--variant <file.toml|name>] [command]
+                                        Play a variant from a file (see variants/chess.toml) or a built-in one
+                                        (crazyhouse, atomic, antichess, kingofthehill, 3check, horde, capablanca,
+                                        grand or extinction), instead of chess (bench, perft-suite, annotate and
+                                        test are chess only)
+    lotisa                              Run the UCI protocol (default)
+    lotisa bench [depth]                Search the bench positions and report nodes and nps
+    lotisa perft <fen> <depth> [--divide]
+    lotisa perft-suite <file.epd> [--depth <depth>]
+    lotisa analyze <fen|moves> [--depth <depth>] [--movetime <ms>]
+                                        Search one position for the best move
+    lotisa annotate <file.pgn> [--depth <depth>] [--movetime <ms>]
+                                        Add evals to every move of the games in a PGN file
+    lotisa selfplay [--fen <fen> | --chess960 <0-959>] [--movetime <ms>]
+    lotisa test                         Check move generation against known perft results";
+
+const BENCH_FENS: [&str; 6] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+];
+
+// The positions from https://www.chessprogramming.org/Perft_Results, in the EPD format `lotisa perft-suite` reads.
+const PERFT_SUITE: &str = include_str!("../perft/standard.epd");
+
+// Deeper results in the suite are skipped by `lotisa test`, so it stays quick.
+pub const SELF_TEST_MAX_DEPTH: i16 = 4;
+
+fn parse_number<T: std::str::FromStr>(value: Option<&&str>, name: &str) -> Result<T, String> {
+    match value {
+        Some(value) => value
+            .parse::<T>()
+            .map_err(|_| format!("Invalid {}: {}", name, value)),
+        None => Err(format!("Missing {}", name)),
+    }
+}
+
+/*
+    Takes "--variant <file.toml>" out of the arguments, loading the variant it points to.
+    Built-in variants like "crazyhouse" can be given by name instead.
+*/
+pub fn take_variant(args: &mut Vec<&str>) -> Result<Option<Variant>, String> {
+    let index = match args.iter().position(|arg| *arg == "--variant") {
+        Some(index) => index,
+        None => return Ok(None),
+    };
+    let path = *args.get(index + 1).ok_or("Missing variant file")?;
+    args.drain(index..index + 2);
+
+    if let Some(variant) = Variant::find_built_in(path) {
+        return Ok(Some(variant));
+    }
+
+    Variant::load(path)
+        .map(Some)
+        .map_err(|error| error.to_string())
+}
+
+// Finds the value after a flag like "--depth", if the flag is there.
+fn get_flag<'a>(args: &[&'a str], flag: &str) -> Option<&'a str> {
+    let index = args.iter().position(|arg| *arg == flag)?;
+    args.get(index + 1).copied()
+}
+
+fn create_quiet_info(limits: SearchLimits) -> SearchInfo {
+    let mut info = create_info(SearchOptions::default(), Arc::new(SearchSignals::default()));
+    info.limits = limits;
+    info.quiet = true;
+    info
+}
+
+fn search_position(uci: &mut UCICommunicator, info: &mut SearchInfo) -> i32 {
+    let moving_team = uci.board.moving_team;
+    root_search(info, uci, moving_team)
+}
+
+pub fn run_bench(args: &[&str]) -> Result<(), String> {
+    let depth = match args.first() {
+        Some(_) => parse_number::<i16>(args.first(), "depth")?,
+        None => DEFAULT_BENCH_DEPTH,
+    };
+
+    let mut nodes: u128 = 0;
+    let start = get_epoch_ms();
+    for fen in BENCH_FENS {
+        let mut uci = Board::load_fen(fen);
+        let mut info = create_quiet_info(SearchLimits {
+            depth: Some(depth),
+            ..Default::default()
+        });
+
+        search_position(&mut uci, &mut info);
+        nodes += info.nodes;
+    }
+    let time = get_epoch_ms() - start;
+
+    println!("{} nodes {} nps", nodes, (nodes * 1000) / (time + 1));
+    Ok(())
+}
+
+pub fn run_perft(args: &[&str], variant: &Variant) -> Result<(), String> {
+    let fen = args.first().ok_or("Missing FEN")?;
+    let depth = parse_number::<i16>(args.get(1), "depth")?;
+    let divide = args.contains(&"--divide");
+
+    let mut uci = variant
+        .try_load_fen(fen)
+        .map_err(|error| error.to_string())?;
+    let start = get_epoch_ms();
+
+    let nodes = if divide {
+        let divide = perft_divide(&mut uci, depth);
+        for (action, nodes) in &divide {
+            println!("{}: {}", action, nodes);
+        }
+        println!();
+        divide.iter().map(|(_, nodes)| nodes).sum()
+    } else {
+        perft(&mut uci, depth, None)
+    };
+
+    println!("Nodes searched: {}", nodes);
+    println!("Time: {}ms", get_epoch_ms() - start);
+    Ok(())
+}
+
+// "--movetime" takes priority over "--depth", and without either we search to `DEFAULT_ANALYZE_DEPTH`.
+fn parse_analysis_limits(args: &[&str]) -> Result<SearchLimits, String> {
+    let mut limits = SearchLimits::default();
+    match get_flag(args, "--movetime") {
+        Some(move_time) => limits.move_time = Some(parse_number(Some(&move_time), "movetime")?),
+        None => {
+            limits.depth = match get_flag(args, "--depth") {
+                Some(depth) => Some(parse_number(Some(&depth), "depth")?),
+                None => Some(DEFAULT_ANALYZE_DEPTH),
+            }
+        }
+    }
+
+    Ok(limits)
+}
+
+/*
+    Takes either a FEN, or moves in UCI notation from the starting position (move numbers are skipped).
+    Whole PGN games go through `run_annotate` instead, which searches every position of the game.
+*/
+pub fn run_analyze(args: &[&str], variant: &Variant) -> Result<(), String> {
+    let position = args.first().ok_or("Missing position to analyze")?;
+    let mut uci = if position.contains('/') {
+        variant.try_load_fen(position)
+    } else {
+        variant.try_load_uci_pgn(position)
+    }
+    .map_err(|error| error.to_string())?;
+
+    let limits = parse_analysis_limits(args)?;
+
+    uci.board.print_board();
+    let mut info = create_info(SearchOptions::default(), Arc::new(SearchSignals::default()));
+    info.limits = limits;
+    search_position(&mut uci, &mut info);
+
+    match info.pv_table.table[0][0] {
+        Some(best_move) => println!("bestmove {}", uci.encode(&best_move)),
+        None => println!("bestmove 0000"),
+    }
+    Ok(())
+}
+
+/*
+    Searches the position after every mainline move of every game in a PGN file, and prints the games back out with the evals as comments.
+*/
+pub fn run_annotate(args: &[&str]) -> Result<(), String> {
+    let path = args.first().ok_or("Missing PGN file")?;
+    let contents = std::fs::read_to_string(path)
+        .map_err(|error| format!("Couldn't read {}: {}", path, error))?;
+    let limits = parse_analysis_limits(args)?;
+
+    for mut game in parse_pgn(&contents).map_err(|error| error.to_string())? {
+        let mut uci = game
+            .starting_position()
+            .map_err(|error| error.to_string())?;
+        for pgn_move in &mut game.moves {
+            uci.board.make_move(pgn_move.action);
+
+            let mut info = create_quiet_info(limits);
+            let score = search_position(&mut uci, &mut info);
+            // PGN evals are always from team 0's perspective.
+            pgn_move.eval = Some(if uci.board.moving_team == 0 {
+                score
+            } else {
+                -score
+            });
+        }
+
+        println!("{}", game.to_pgn());
+    }
+
+    Ok(())
+}
+
+/*
+    Plays the engine against itself, printing the moves and the result.
+    "--chess960" starts from that Chess960 position, with castling written as the king taking its rook.
+*/
+pub fn run_selfplay(args: &[&str], variant: &Variant) -> Result<(), String> {
+    let chess960 = match get_flag(args, "--chess960") {
+        Some(index) => {
+            let index = parse_number::<u16>(Some(&index), "Chess960 position")?;
+            Some(get_chess960_fen(index).ok_or(format!(
+                "Chess960 positions go from 0 to {}",
+                CHESS960_POSITIONS - 1
+            ))?)
+        }
+        None => None,
+    };
+    let fen = match &chess960 {
+        Some(fen) => fen,
+        None => get_flag(args, "--fen").unwrap_or(&variant.startpos),
+    };
+    let move_time = match get_flag(args, "--movetime") {
+        Some(move_time) => parse_number(Some(&move_time), "movetime")?,
+        None => DEFAULT_SELFPLAY_MOVE_TIME,
+    };
+
+    let mut uci = variant
+        .try_load_fen(fen)
+        .map_err(|error| error.to_string())?;
+    uci.board.chess960 = chess960.is_some();
+    let mut info = create_quiet_info(SearchLimits {
+        move_time: Some(move_time),
+        ..Default::default()
+    });
+
+    let mut moves: Vec<String> = Vec::with_capacity(MAX_SELFPLAY_PLIES);
+    let result = loop {
+        let actions = uci.board.generate_legal_moves();
+        let win_conditions = uci.board.win_conditions.duplicate();
+        match win_conditions.compute(&mut uci.board, &actions) {
+            // The result is from the perspective of the moving team.
+            GameResult::Win => break format!("team {} wins", uci.board.moving_team),
+            GameResult::Lose => break format!("team {} wins", uci.board.previous_team()),
+            GameResult::Draw => break "draw".to_string(),
+            GameResult::Ongoing => {}
+        }
+
+        if moves.len() >= MAX_SELFPLAY_PLIES {
+            break "draw (adjudicated)".to_string();
+        }
+
+        search_position(&mut uci, &mut info);
+        let action = match info.pv_table.table[0][0] {
+            Some(action) => action,
+            None => break "draw".to_string(),
+        };
+
+        let encoded = uci.encode(&action);
+        println!("{} {}", uci.board.fullmove_number, encoded);
+        moves.push(encoded);
+        uci.board.make_move(action);
+    };
+
+    println!("{}", moves.join(" "));
+    println!("Result: {}", result);
+    Ok(())
+}
+
+fn print_perft_mismatch(mismatch: &PerftMismatch) {
+    println!(
+        "FAILED {} (depth {}: expected {}, got {})",
+        mismatch.fen, mismatch.depth, mismatch.expected, mismatch.actual
+    );
+}
+
+/*
+    Runs perft on every position of an EPD file, returning the mismatches.
+    Depths past `max_depth` are skipped.
+*/
+fn check_perft_suite(contents: &str, max_depth: Option<i16>) -> Result<Vec<PerftMismatch>, String> {
+    let entries = parse_perft_epd(contents)?;
+
+    let mut mismatches = vec![];
+    for entry in &entries {
+        let entry_mismatches =
+            check_perft_entry(entry, max_depth).map_err(|error| error.to_string())?;
+        if entry_mismatches.is_empty() {
+            println!("ok     {}", entry.fen);
+        }
+        for mismatch in &entry_mismatches {
+            print_perft_mismatch(mismatch);
+        }
+        mismatches.extend(entry_mismatches);
+    }
+
+    println!(
+        "{} positions, {} mismatches",
+        entries.len(),
+        mismatches.len()
+    );
+    Ok(mismatches)
+}
+
+pub fn run_perft_suite(args: &[&str]) -> Result<(), String> {
+    let path = args.first().ok_or("Missing EPD file")?;
+    let contents = std::fs::read_to_string(path)
+        .map_err(|error| format!("Couldn't read {}: {}", path, error))?;
+    let max_depth = match get_flag(args, "--depth") {
+        Some(depth) => Some(parse_number(Some(&depth), "depth")?),
+        None => None,
+    };
+
+    let mismatches = check_perft_suite(&contents, max_depth)?;
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("{} perft results didn't match", mismatches.len()))
+    }
+}
+
+/*
+    Checks move generation against known perft results, returning whether everything matched.
+*/
+pub fn run_self_test() -> bool {
+    match check_perft_suite(PERFT_SUITE, Some(SELF_TEST_MAX_DEPTH)) {
+        Ok(mismatches) => mismatches.is_empty(),
+        Err(error) => {
+            println!("{}", error);
+            false
+        }
+    }
+}
@@ -1,40 +1,160 @@
-use crate::boards::{Action, Board};
+use crate::{
+    boards::{get_castling_squares, Action, Board},
+    error::LotisaError,
+};
+
+mod pgn;
+mod san;
+
+pub use pgn::*;
 
 pub trait Communicator {
     fn encode(&mut self, action: &Action) -> String;
-    fn decode(&mut self, action: String) -> Action;
+    fn try_decode(&mut self, action: &str) -> Result<Action, LotisaError>;
     fn encode_pos(&mut self, pos: i16) -> String;
-    fn decode_pos(&mut self, pos: String) -> i16;
+    fn try_decode_pos(&mut self, pos: &str) -> Result<i16, LotisaError>;
+
+    // Panics on bad input, so it's only for moves we know are legal.
+    fn decode(&mut self, action: String) -> Action {
+        self.try_decode(&action)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    fn decode_pos(&mut self, pos: String) -> i16 {
+        self.try_decode_pos(&pos)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
 }
 
 pub struct UCICommunicator {
     pub board: Board,
 }
 
-fn encode_uci_pos(board: &Board, pos: i16, buffer_amount: i16) -> String {
+/*
+    Files are lettered like spreadsheet columns, so boards wider than 26 files go on with "aa", "ab", ..., "az", "ba" and so on.
+*/
+pub fn encode_file(col: i16) -> String {
+    let mut file = vec![];
+    let mut col = col + 1;
+    while col > 0 {
+        col -= 1;
+        file.push((b'a' + (col % 26) as u8) as char);
+        col /= 26;
+    }
+    file.into_iter().rev().collect()
+}
+
+pub fn decode_file(file: &str) -> Option<i16> {
+    if file.is_empty() || !file.chars().all(|char| char.is_ascii_lowercase()) {
+        return None;
+    }
+
+    let mut col: i32 = 0;
+    for char in file.chars() {
+        col = (col * 26) + (char as i32 - 'a' as i32 + 1);
+        if col > i16::MAX as i32 {
+            return None;
+        }
+    }
+    Some((col - 1) as i16)
+}
+
+pub fn encode_uci_pos(board: &Board, pos: i16, buffer_amount: i16) -> String {
     let true_row = board.get_row(pos);
     let true_col = board.get_col(pos, true_row);
     let row = board.rows - (true_row - buffer_amount) - 1;
     let col = true_col - (buffer_amount / 2);
-    let mut abcs = "abcdefghijklmnopqrstuvwxyz".chars();
-    return format!("{}{}", abcs.nth(col as usize).unwrap(), row + 1);
+    format!("{}{}", encode_file(col), row + 1)
+}
+
+/*
+    Squares are file letters followed by a rank number, like "e4", "j10" or "ab3".
+*/
+pub fn try_decode_uci_pos(
+    board: &Board,
+    pos: &str,
+    buffer_amount: i16,
+) -> Result<i16, LotisaError> {
+    let invalid = || LotisaError::InvalidSquare(pos.to_string());
+
+    let rank_start = pos
+        .find(|char: char| !char.is_ascii_lowercase())
+        .ok_or_else(invalid)?;
+    let (file, rank) = pos.split_at(rank_start);
+    let col = decode_file(file).ok_or_else(invalid)?;
+    if !rank.chars().all(|char| char.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    let rank = rank.parse::<i16>().map_err(|_| invalid())?;
+    if col >= board.cols || rank < 1 || rank > board.rows {
+        return Err(invalid());
+    }
+
+    let row = board.rows - rank + buffer_amount;
+    Ok((row * board.row_gap) + col + (buffer_amount / 2))
 }
 
-fn decode_uci_pos(board: &Board, pos: &str, buffer_amount: i16) -> i16 {
-    let mut abcs = "abcdefghijklmnopqrstuvwxyz".chars();
-    let col_char = pos.chars().nth(0).unwrap();
-    let mut col = abcs.position(|char| char == col_char).unwrap() as i16;
-    let mut row = board.rows - (pos.chars().nth(1).unwrap().to_digit(10).unwrap() as i16);
-    col += buffer_amount / 2;
-    row += buffer_amount;
-    return (row * board.row_gap) + col;
+/*
+    Splits a UCI move like "e7e8q" or "a10ab10" into its two squares and the promotion letters after them.
+    Squares don't have a fixed length on bigger boards, so each one is read as letters followed by digits.
+*/
+pub fn split_uci_move(action: &str) -> Option<(&str, &str, &str)> {
+    let square_end = |from: usize| {
+        let rank_start = from + action[from..].find(|char: char| !char.is_ascii_lowercase())?;
+        let rank_end = action[rank_start..]
+            .find(|char: char| !char.is_ascii_digit())
+            .map_or(action.len(), |end| rank_start + end);
+        if rank_start == from || rank_end == rank_start {
+            None
+        } else {
+            Some(rank_end)
+        }
+    };
+
+    if !action.is_ascii() {
+        return None;
+    }
+
+    let from_end = square_end(0)?;
+    let to_end = square_end(from_end)?;
+    let promotion = &action[to_end..];
+    if !promotion.chars().all(|char| char.is_ascii_lowercase()) {
+        return None;
+    }
+
+    Some((&action[..from_end], &action[from_end..to_end], promotion))
+}
+
+/*
+    Drops are written as the piece's uppercase letter and the square it lands on, like "P@e4".
+*/
+pub fn encode_drop(board: &Board, action: &Action) -> String {
+    let letter = board
+        .piece_lookup
+        .get_letter(action.piece_type)
+        .unwrap_or('?');
+    format!(
+        "{}@{}",
+        letter.to_ascii_uppercase(),
+        encode_uci_pos(board, action.to, board.buffer_amount)
+    )
 }
 
 impl Communicator for UCICommunicator {
     fn encode(&mut self, action: &Action) -> String {
+        if action.is_drop() {
+            return encode_drop(&self.board, action);
+        }
+
         let buffer_amount = self.board.buffer_amount;
-        let to = if action.piece_type == 5 && action.info == 1 {
-            action.from + ((action.to - action.from).signum() * 2)
+        // Castling is the king moving to its new square, or onto its rook in Chess960 (which is how it's stored).
+        let is_castling = self
+            .board
+            .piece_lookup
+            .lookup(action.piece_type)
+            .is_castling(action);
+        let to = if is_castling && !self.board.chess960 {
+            get_castling_squares(&self.board, action.from, action.to).0
         } else {
             action.to
         };
@@ -44,86 +164,49 @@ impl Communicator for UCICommunicator {
             encode_uci_pos(&self.board, action.from, buffer_amount),
             encode_uci_pos(&self.board, to, buffer_amount),
             if action.piece_type == 0 && action.info >= 0 {
-                match action.info {
-                    1 => "n",
-                    2 => "b",
-                    3 => "r",
-                    4 => "q",
-                    _ => "",
-                }
+                self.board
+                    .piece_lookup
+                    .get_letter(action.info)
+                    .map_or(String::new(), |letter| letter.to_string())
             } else {
-                ""
+                String::new()
             }
         );
     }
 
-    fn decode(&mut self, action: String) -> Action {
-        let buffer_amount = self.board.buffer_amount;
-        let from = decode_uci_pos(&self.board, &action[0..2], buffer_amount);
-        let mut to = decode_uci_pos(&self.board, &action[2..4], buffer_amount);
-        let piece_info = self.board.get_piece_info(from);
-
-        let en_passant = piece_info.piece_type == 0
-            && ((from - to).abs() % self.board.row_gap) != 0
-            && self.board.state[to as usize] == 1;
-
-        let castling = if piece_info.piece_type == 5 && (from - to).abs() == 2 {
-            to = self
-                .board
-                .generate_moves()
-                .iter()
-                .find(|action| {
-                    action.from == from
-                        && action.info == 1
-                        && (from - to).signum() == (action.from - action.to).signum()
-                })
-                .unwrap()
-                .to;
-
-            true
-        } else {
-            false
+    /*
+        Only legal moves are decoded, so input from a GUI can't put the board in a broken state.
+    */
+    fn try_decode(&mut self, action: &str) -> Result<Action, LotisaError> {
+        let is_uci_move = match (action.split_once('@'), split_uci_move(action)) {
+            (Some((letter, to)), _) => {
+                letter.len() == 1
+                    && letter.chars().all(|char| char.is_ascii_uppercase())
+                    && self.try_decode_pos(to).is_ok()
+            }
+            (None, Some((from, to, _))) => {
+                self.try_decode_pos(from).is_ok() && self.try_decode_pos(to).is_ok()
+            }
+            (None, None) => false,
         };
+        if !is_uci_move {
+            return Err(LotisaError::InvalidMove(action.to_string()));
+        }
 
-        Action {
-            from,
-            to,
-            piece_type: piece_info.piece_type,
-            team: piece_info.team,
-            capture: self.board.state[to as usize] > 1 && !en_passant,
-            info: if piece_info.piece_type == 0 {
-                if action.len() == 5 {
-                    match action.chars().nth(4).unwrap() {
-                        'n' => 1,
-                        'b' => 2,
-                        'r' => 3,
-                        'q' => 4,
-                        _ => 0,
-                    }
-                } else if en_passant {
-                    -3
-                } else if (from - to).abs() == 2 * self.board.row_gap {
-                    -2
-                } else {
-                    -1
-                }
-            } else if piece_info.piece_type == 5 {
-                if castling {
-                    1
-                } else {
-                    0
-                }
-            } else {
-                0
-            },
+        for legal_action in self.board.generate_legal_moves() {
+            if self.encode(&legal_action) == action {
+                return Ok(legal_action);
+            }
         }
+
+        Err(LotisaError::IllegalMove(action.to_string()))
     }
 
     fn encode_pos(&mut self, pos: i16) -> String {
         return encode_uci_pos(&self.board, pos, self.board.buffer_amount);
     }
 
-    fn decode_pos(&mut self, pos: String) -> i16 {
-        return decode_uci_pos(&self.board, &pos, self.board.buffer_amount);
+    fn try_decode_pos(&mut self, pos: &str) -> Result<i16, LotisaError> {
+        try_decode_uci_pos(&self.board, pos, self.board.buffer_amount)
     }
 }
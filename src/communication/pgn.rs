@@ -0,0 +1,560 @@
+use crate::{
+    boards::{Action, Board, STARTPOS_FEN},
+    engine::{MATE_THRESHOLD, MAX_VALUE, MIN_VALUE},
+    error::LotisaError,
+};
+
+use super::UCICommunicator;
+
+// Export format keeps lines under 80 characters.
+pub const PGN_LINE_LENGTH: usize = 79;
+
+// The suffix annotations ("!", "?", ...) are stored as their NAGs, so they're written back out as "$1", "$2", ...
+const SUFFIX_ANNOTATIONS: [(&str, u8); 6] = [
+    ("!", 1),
+    ("?", 2),
+    ("!!", 3),
+    ("??", 4),
+    ("!?", 5),
+    ("?!", 6),
+];
+
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+/*
+    A move in a game tree.
+    Variations are alternatives to this move, so each one starts from the position before it.
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct PgnMove {
+    pub action: Action,
+    pub san: String,
+    pub nags: Vec<u8>,
+    // Only the first move of a line can have a comment before it.
+    pub comment_before: Option<String>,
+    pub comment: Option<String>,
+    // Engine evaluation of the position after this move, from team 0's perspective. Stored as "[%eval]" in the comment.
+    pub eval: Option<i32>,
+    pub variations: Vec<Vec<PgnMove>>,
+}
+
+impl PgnMove {
+    pub fn new(action: Action, san: String) -> PgnMove {
+        PgnMove {
+            action,
+            san,
+            nags: vec![],
+            comment_before: None,
+            comment: None,
+            eval: None,
+            variations: vec![],
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PgnGame {
+    // Kept in the order they were read, so writing a game back out doesn't shuffle them.
+    pub tags: Vec<(String, String)>,
+    pub moves: Vec<PgnMove>,
+    pub result: String,
+}
+
+impl Default for PgnGame {
+    fn default() -> Self {
+        PgnGame {
+            tags: vec![],
+            moves: vec![],
+            result: "*".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum PgnToken {
+    Tag(String, String),
+    Comment(String),
+    Nag(u8),
+    VariationStart,
+    VariationEnd,
+    Result(String),
+    Move(String),
+}
+
+fn invalid_pgn(message: impl Into<String>) -> LotisaError {
+    LotisaError::InvalidPgn(message.into())
+}
+
+fn get_suffix_nag(suffix: &str) -> Option<u8> {
+    SUFFIX_ANNOTATIONS
+        .iter()
+        .find(|(annotation, _)| *annotation == suffix)
+        .map(|(_, nag)| *nag)
+}
+
+fn read_tag(chars: &[char]) -> Result<PgnToken, LotisaError> {
+    let text = chars.iter().collect::<String>();
+    let (name, value) = text
+        .trim()
+        .split_once(char::is_whitespace)
+        .ok_or_else(|| invalid_pgn(format!("invalid tag [{}]", text)))?;
+
+    let value = value.trim();
+    let value = value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .ok_or_else(|| invalid_pgn(format!("tag value isn't quoted in [{}]", text)))?;
+
+    Ok(PgnToken::Tag(
+        name.to_string(),
+        value.replace("\\\"", "\"").replace("\\\\", "\\"),
+    ))
+}
+
+/*
+    Turns a symbol like "12.", "12...Nf3!?" or "1-0" into tokens. Move numbers aren't kept, since they're implied by the moves.
+*/
+fn read_symbol(symbol: &str, tokens: &mut Vec<PgnToken>) {
+    if RESULTS.contains(&symbol) {
+        tokens.push(PgnToken::Result(symbol.to_string()));
+        return;
+    }
+
+    let mut symbol = symbol;
+    let digits = symbol
+        .find(|char: char| !char.is_ascii_digit())
+        .unwrap_or(symbol.len());
+    if digits > 0 && symbol[digits..].starts_with('.') {
+        symbol = symbol[digits..].trim_start_matches('.');
+    }
+
+    let annotation_start = symbol.find(['!', '?']).unwrap_or(symbol.len());
+    let (san, annotation) = symbol.split_at(annotation_start);
+
+    if !san.is_empty() {
+        tokens.push(PgnToken::Move(san.to_string()));
+    }
+    if let Some(nag) = get_suffix_nag(annotation) {
+        tokens.push(PgnToken::Nag(nag));
+    }
+}
+
+fn tokenize_pgn(pgn: &str) -> Result<Vec<PgnToken>, LotisaError> {
+    let chars = pgn.chars().collect::<Vec<_>>();
+    let mut tokens = vec![];
+    let mut index = 0;
+
+    // Finds the closing character, returning everything in between.
+    let read_until = |index: &mut usize, end: char| -> Option<String> {
+        let start = *index + 1;
+        let length = chars[start..].iter().position(|char| *char == end)?;
+        *index = start + length;
+        Some(chars[start..start + length].iter().collect())
+    };
+
+    while index < chars.len() {
+        let char = chars[index];
+        match char {
+            _ if char.is_whitespace() => {}
+            // Escaped lines are for other programs' use.
+            '%' if index == 0 || chars[index - 1] == '\n' => {
+                if read_until(&mut index, '\n').is_none() {
+                    index = chars.len();
+                }
+            }
+            '[' => {
+                let start = index + 1;
+                let mut in_quotes = false;
+                let mut end = start;
+                while end < chars.len() && (in_quotes || chars[end] != ']') {
+                    match chars[end] {
+                        '\\' => end += 1,
+                        '"' => in_quotes = !in_quotes,
+                        _ => {}
+                    }
+                    end += 1;
+                }
+                if end >= chars.len() {
+                    return Err(invalid_pgn("unterminated tag"));
+                }
+
+                tokens.push(read_tag(&chars[start..end])?);
+                index = end;
+            }
+            '{' => {
+                let comment = read_until(&mut index, '}')
+                    .ok_or_else(|| invalid_pgn("unterminated comment"))?;
+                tokens.push(PgnToken::Comment(comment));
+            }
+            ';' => {
+                let comment = match read_until(&mut index, '\n') {
+                    Some(comment) => comment,
+                    None => {
+                        let comment = chars[index + 1..].iter().collect();
+                        index = chars.len();
+                        comment
+                    }
+                };
+                tokens.push(PgnToken::Comment(comment));
+            }
+            '(' => tokens.push(PgnToken::VariationStart),
+            ')' => tokens.push(PgnToken::VariationEnd),
+            _ => {
+                let start = index;
+                while index + 1 < chars.len()
+                    && !chars[index + 1].is_whitespace()
+                    && !"{}()[];".contains(chars[index + 1])
+                {
+                    index += 1;
+                }
+                let symbol = chars[start..=index].iter().collect::<String>();
+
+                if let Some(nag) = symbol.strip_prefix('$') {
+                    let nag = nag
+                        .parse::<u8>()
+                        .map_err(|_| invalid_pgn(format!("invalid NAG {}", symbol)))?;
+                    tokens.push(PgnToken::Nag(nag));
+                } else {
+                    read_symbol(&symbol, &mut tokens);
+                }
+            }
+        }
+
+        index += 1;
+    }
+
+    Ok(tokens)
+}
+
+fn join_comments(first: Option<String>, second: &str) -> Option<String> {
+    let second = second.trim();
+    match first {
+        _ if second.is_empty() => first,
+        Some(first) => Some(format!("{} {}", first, second)),
+        None => Some(second.to_string()),
+    }
+}
+
+fn parse_eval(eval: &str) -> Option<i32> {
+    if let Some(mate) = eval.strip_prefix('#') {
+        let mate = mate.parse::<i32>().ok()?;
+        return Some(if mate > 0 {
+            MAX_VALUE - (mate * 2 - 1)
+        } else {
+            MIN_VALUE + (-mate * 2)
+        });
+    }
+
+    let pawns = eval.parse::<f64>().ok()?;
+    Some((pawns * 1000.0).round() as i32)
+}
+
+/*
+    Evals are written like Lichess does: in pawns, or "#3"/"#-3" for mates, always from team 0's perspective.
+*/
+pub fn format_eval(eval: i32) -> String {
+    if eval >= MATE_THRESHOLD {
+        format!("#{}", (MAX_VALUE - eval + 1) / 2)
+    } else if eval <= -MATE_THRESHOLD {
+        format!("#-{}", (eval - MIN_VALUE) / 2)
+    } else {
+        format!("{:.2}", eval as f64 / 1000.0)
+    }
+}
+
+// Pulls an "[%eval]" command out of a comment, leaving the rest of it.
+fn add_comment(pgn_move: &mut PgnMove, comment: &str) {
+    let mut comment = comment.to_string();
+    if let Some(start) = comment.find("[%eval") {
+        if let Some(length) = comment[start..].find(']') {
+            let eval = comment[start + "[%eval".len()..start + length].trim();
+            if let Some(eval) = parse_eval(eval) {
+                pgn_move.eval = Some(eval);
+                comment.replace_range(start..=start + length, "");
+            }
+        }
+    }
+
+    pgn_move.comment = join_comments(pgn_move.comment.take(), &comment);
+}
+
+/*
+    Reads moves until the end of the line (a result, a closing parenthesis or the next game's tags).
+    Variations are read recursively, and the board is left after the last move of the line.
+*/
+fn parse_line(
+    tokens: &[PgnToken],
+    index: &mut usize,
+    uci: &mut UCICommunicator,
+    depth: usize,
+) -> Result<Vec<PgnMove>, LotisaError> {
+    let mut moves: Vec<PgnMove> = vec![];
+    let mut comment_before: Option<String> = None;
+
+    while let Some(token) = tokens.get(*index) {
+        match token {
+            PgnToken::Tag(..) | PgnToken::Result(_) if depth > 0 => {
+                return Err(invalid_pgn("unterminated variation"));
+            }
+            PgnToken::Tag(..) | PgnToken::Result(_) => break,
+            PgnToken::VariationEnd if depth == 0 => {
+                return Err(invalid_pgn("unexpected end of variation"));
+            }
+            PgnToken::VariationEnd => break,
+            PgnToken::Move(san) => {
+                let action = uci
+                    .decode_san(san)
+                    .ok_or_else(|| LotisaError::IllegalMove(san.clone()))?;
+                let mut pgn_move = PgnMove::new(action, uci.encode_san(&action));
+                pgn_move.comment_before = comment_before.take();
+
+                uci.board.make_move(action);
+                moves.push(pgn_move);
+            }
+            PgnToken::Nag(nag) => {
+                if let Some(last_move) = moves.last_mut() {
+                    last_move.nags.push(*nag);
+                }
+            }
+            PgnToken::Comment(comment) => match moves.last_mut() {
+                Some(last_move) => add_comment(last_move, comment),
+                None => comment_before = join_comments(comment_before, comment),
+            },
+            PgnToken::VariationStart => {
+                let last_move = moves
+                    .last_mut()
+                    .ok_or_else(|| invalid_pgn("variation without a move to replace"))?;
+
+                uci.board.undo_move();
+                *index += 1;
+                let variation = parse_line(tokens, index, uci, depth + 1)?;
+                if tokens.get(*index) != Some(&PgnToken::VariationEnd) {
+                    return Err(invalid_pgn("unterminated variation"));
+                }
+
+                for _ in &variation {
+                    uci.board.undo_move();
+                }
+                uci.board.make_move(last_move.action);
+                last_move.variations.push(variation);
+            }
+        }
+
+        *index += 1;
+    }
+
+    Ok(moves)
+}
+
+/*
+    Reads every game in a PGN file.
+    Moves are checked against the position they're played in, so an illegal move is an error rather than a broken game.
+*/
+pub fn parse_pgn(pgn: &str) -> Result<Vec<PgnGame>, LotisaError> {
+    let tokens = tokenize_pgn(pgn)?;
+    let mut games = vec![];
+    let mut index = 0;
+
+    while index < tokens.len() {
+        let mut game = PgnGame::default();
+        while let Some(PgnToken::Tag(name, value)) = tokens.get(index) {
+            game.set_tag(name, value);
+            index += 1;
+        }
+
+        let mut uci = game.starting_position()?;
+        game.moves = parse_line(&tokens, &mut index, &mut uci, 0)?;
+
+        if let Some(PgnToken::Result(result)) = tokens.get(index) {
+            game.result = result.clone();
+            index += 1;
+        }
+
+        games.push(game);
+    }
+
+    Ok(games)
+}
+
+/*
+    Wraps tokens into lines, without spaces just inside parentheses.
+*/
+fn wrap_tokens(tokens: &[String]) -> String {
+    let mut lines = vec![];
+    let mut line = String::new();
+    let mut previous = "";
+
+    for token in tokens {
+        let separator = if line.is_empty() || previous == "(" || token == ")" {
+            ""
+        } else {
+            " "
+        };
+
+        if !line.is_empty() && line.len() + separator.len() + token.len() > PGN_LINE_LENGTH {
+            lines.push(line);
+            line = token.clone();
+        } else {
+            line.push_str(separator);
+            line.push_str(token);
+        }
+        previous = token;
+    }
+
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines.join("\n")
+}
+
+/*
+    Comments are split into words so they can wrap across lines.
+    Anything in `commands` (like "[%eval 0.25]") is kept together, ahead of the text.
+*/
+fn write_comment(tokens: &mut Vec<String>, commands: Vec<String>, comment: &str) {
+    let mut words = commands;
+    words.extend(comment.split_whitespace().map(|word| word.to_string()));
+    if words.is_empty() {
+        return;
+    }
+
+    let last = words.len() - 1;
+    for (index, mut word) in words.into_iter().enumerate() {
+        if index == 0 {
+            word.insert(0, '{');
+        }
+        if index == last {
+            word.push('}');
+        }
+        tokens.push(word);
+    }
+}
+
+fn write_line(
+    tokens: &mut Vec<String>,
+    moves: &[PgnMove],
+    mut fullmove_number: i16,
+    mut team: i16,
+    teams: i16,
+) {
+    // Moves by any team but the first need a number like "12..." when they don't follow the first team's move.
+    let mut needs_number = true;
+
+    for pgn_move in moves {
+        if let Some(comment) = &pgn_move.comment_before {
+            write_comment(tokens, vec![], comment);
+            needs_number = true;
+        }
+
+        // The move number stays on the same line as its move.
+        if team == 0 {
+            tokens.push(format!("{}. {}", fullmove_number, pgn_move.san));
+        } else if needs_number {
+            tokens.push(format!("{}... {}", fullmove_number, pgn_move.san));
+        } else {
+            tokens.push(pgn_move.san.clone());
+        }
+        needs_number = false;
+
+        for nag in &pgn_move.nags {
+            tokens.push(format!("${}", nag));
+        }
+
+        let commands = pgn_move
+            .eval
+            .map(|eval| format!("[%eval {}]", format_eval(eval)))
+            .into_iter()
+            .collect::<Vec<_>>();
+        let comment = pgn_move.comment.as_deref().unwrap_or("");
+        if !commands.is_empty() || !comment.is_empty() {
+            write_comment(tokens, commands, comment);
+            needs_number = true;
+        }
+
+        for variation in &pgn_move.variations {
+            tokens.push("(".to_string());
+            write_line(tokens, variation, fullmove_number, team, teams);
+            tokens.push(")".to_string());
+            needs_number = true;
+        }
+
+        team = (team + 1) % teams;
+        if team == 0 {
+            fullmove_number += 1;
+        }
+    }
+}
+
+impl PgnGame {
+    pub fn parse(pgn: &str) -> Result<PgnGame, LotisaError> {
+        parse_pgn(pgn)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| invalid_pgn("no game found"))
+    }
+
+    pub fn get_tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some(tag) => tag.1 = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    // Games start from the "FEN" tag if there is one.
+    pub fn starting_position(&self) -> Result<UCICommunicator, LotisaError> {
+        Board::try_load_fen(self.get_tag("FEN").unwrap_or(STARTPOS_FEN))
+    }
+
+    /*
+        The position after the mainline, ignoring every variation.
+    */
+    pub fn final_position(&self) -> Result<UCICommunicator, LotisaError> {
+        let mut uci = self.starting_position()?;
+        for pgn_move in &self.moves {
+            uci.board.make_move(pgn_move.action);
+        }
+        Ok(uci)
+    }
+
+    pub fn to_pgn(&self) -> String {
+        let mut pgn = String::new();
+        for (name, value) in &self.tags {
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+            pgn.push_str(&format!("[{} \"{}\"]\n", name, value));
+        }
+        if !self.tags.is_empty() {
+            pgn.push('\n');
+        }
+
+        // Without a valid starting position, we number the moves as if the game started normally.
+        let (fullmove_number, moving_team, teams) = match self.starting_position() {
+            Ok(uci) => (
+                uci.board.fullmove_number,
+                uci.board.moving_team,
+                uci.board.teams,
+            ),
+            Err(_) => (1, 0, 2),
+        };
+
+        let mut tokens = vec![];
+        write_line(
+            &mut tokens,
+            &self.moves,
+            fullmove_number,
+            moving_team,
+            teams,
+        );
+        tokens.push(self.result.clone());
+
+        pgn.push_str(&wrap_tokens(&tokens));
+        pgn.push('\n');
+        pgn
+    }
+}
@@ -0,0 +1,255 @@
+use crate::boards::{in_check, Action, PieceLookup};
+
+use super::{encode_drop, Communicator, UCICommunicator};
+
+const PAWN: i16 = 0;
+
+/*
+    The SAN letter for a piece type, pawns don't have one.
+*/
+fn get_san_letter(piece_lookup: &dyn PieceLookup, piece_type: i16) -> Option<char> {
+    if piece_type == PAWN {
+        return None;
+    }
+
+    piece_lookup
+        .get_letter(piece_type)
+        .map(|letter| letter.to_ascii_uppercase())
+}
+
+fn get_san_piece_type(piece_lookup: &dyn PieceLookup, letter: char) -> Option<i16> {
+    if !letter.is_ascii_uppercase() {
+        return None;
+    }
+
+    piece_lookup.get_piece_type(letter.to_ascii_lowercase())
+}
+
+fn is_castling(piece_lookup: &dyn PieceLookup, action: &Action) -> bool {
+    piece_lookup.lookup(action.piece_type).is_castling(action)
+}
+
+// Castling is stored as the king moving onto its rook, so the side is whichever way the rook is.
+fn encode_castling(action: &Action) -> String {
+    if action.to > action.from {
+        "O-O".to_string()
+    } else {
+        "O-O-O".to_string()
+    }
+}
+
+/*
+    Splits a square like "e4" into its file ("e") and rank ("4").
+*/
+fn split_square(square: &str) -> (&str, &str) {
+    let rank_start = square
+        .find(|char: char| char.is_ascii_digit())
+        .unwrap_or(square.len());
+    square.split_at(rank_start)
+}
+
+impl UCICommunicator {
+    /*
+        Encodes a legal move in Standard Algebraic Notation (like "Nbd7", "exd6", "e8=Q+" or "O-O-O#").
+        The move has to be made from the current position, since disambiguation and checks depend on it.
+    */
+    pub fn encode_san(&mut self, action: &Action) -> String {
+        let mut san = if is_castling(self.board.piece_lookup.as_ref(), action) {
+            encode_castling(action)
+        } else if action.is_drop() {
+            encode_drop(&self.board, action)
+        } else {
+            self.encode_san_move(action)
+        };
+
+        self.board.make_move(*action);
+        let moving_team = self.board.moving_team;
+        let row_gap = self.board.row_gap;
+        if in_check(&mut self.board, moving_team, row_gap) {
+            if self.board.generate_legal_moves().is_empty() {
+                san.push('#');
+            } else {
+                san.push('+');
+            }
+        }
+        self.board.undo_move();
+
+        san
+    }
+
+    fn encode_san_move(&mut self, action: &Action) -> String {
+        let from = self.encode_pos(action.from);
+        let to = self.encode_pos(action.to);
+        let (from_file, from_rank) = split_square(&from);
+
+        let mut san = String::new();
+        match get_san_letter(self.board.piece_lookup.as_ref(), action.piece_type) {
+            Some(letter) => {
+                san.push(letter);
+
+                // Other pieces of the same type that could move to the same square.
+                let others = self
+                    .board
+                    .generate_legal_moves()
+                    .into_iter()
+                    .filter(|other| {
+                        other.piece_type == action.piece_type
+                            && other.to == action.to
+                            && other.from != action.from
+                            && !is_castling(self.board.piece_lookup.as_ref(), other)
+                            && !other.is_drop()
+                    })
+                    .collect::<Vec<_>>()
+                    .into_iter()
+                    .map(|other| self.encode_pos(other.from))
+                    .collect::<Vec<_>>();
+
+                if !others.is_empty() {
+                    if others
+                        .iter()
+                        .all(|other| split_square(other).0 != from_file)
+                    {
+                        san.push_str(from_file);
+                    } else if others
+                        .iter()
+                        .all(|other| split_square(other).1 != from_rank)
+                    {
+                        san.push_str(from_rank);
+                    } else {
+                        san.push_str(&from);
+                    }
+                }
+            }
+            None => {
+                if action.capture {
+                    san.push_str(from_file);
+                }
+            }
+        }
+
+        if action.capture {
+            san.push('x');
+        }
+        san.push_str(&to);
+
+        if action.piece_type == PAWN && action.info >= 0 {
+            if let Some(letter) = get_san_letter(self.board.piece_lookup.as_ref(), action.info) {
+                san.push('=');
+                san.push(letter);
+            }
+        }
+
+        san
+    }
+
+    /*
+        Finds the legal move a SAN string refers to.
+        Check and annotation suffixes are ignored, and a missing "x" or over-disambiguation ("Ng1f3") is accepted.
+        Returns None if the move is illegal or ambiguous.
+    */
+    pub fn decode_san(&mut self, san: &str) -> Option<Action> {
+        let san = san.trim_end_matches(['+', '#', '!', '?']);
+        let actions = self.board.generate_legal_moves();
+
+        if matches!(san, "O-O" | "0-0" | "O-O-O" | "0-0-0") {
+            let matches = actions
+                .into_iter()
+                .filter(|action| {
+                    is_castling(self.board.piece_lookup.as_ref(), action)
+                        && encode_castling(action) == san.replace('0', "O")
+                })
+                .collect::<Vec<_>>();
+            return match matches[..] {
+                [action] => Some(action),
+                _ => None,
+            };
+        }
+
+        // Drops ("N@f3") are the same in SAN and UCI, pawn drops may leave out the "P".
+        if let Some((letter, to)) = san.split_once('@') {
+            let piece_type = match letter.chars().next() {
+                Some(letter) => get_san_piece_type(self.board.piece_lookup.as_ref(), letter)?,
+                None => PAWN,
+            };
+            let matches = actions
+                .into_iter()
+                .filter(|action| {
+                    action.is_drop()
+                        && action.piece_type == piece_type
+                        && self.encode_pos(action.to) == to
+                })
+                .collect::<Vec<_>>();
+            return match matches[..] {
+                [action] => Some(action),
+                _ => None,
+            };
+        }
+
+        let piece_lookup = self.board.piece_lookup.as_ref();
+        let san = san.replace(['x', '-', ':'], "");
+        let mut chars = san.chars();
+        let (piece_type, rest) = match chars.next() {
+            Some(letter) if letter.is_ascii_uppercase() => (
+                get_san_piece_type(piece_lookup, letter)?,
+                chars.as_str().to_string(),
+            ),
+            _ => (PAWN, san.clone()),
+        };
+
+        // Promotions are usually written "e8=Q", but "e8Q" shows up too.
+        let (rest, promotion) = match rest.split_once('=') {
+            Some((rest, promotion)) => {
+                let promotion = promotion
+                    .chars()
+                    .next()
+                    .and_then(|letter| get_san_piece_type(piece_lookup, letter))?;
+                (rest.to_string(), Some(promotion))
+            }
+            None => match rest.chars().last() {
+                Some(letter) if piece_type == PAWN && letter.is_ascii_uppercase() => (
+                    rest[..rest.len() - 1].to_string(),
+                    Some(get_san_piece_type(piece_lookup, letter)?),
+                ),
+                _ => (rest, None),
+            },
+        };
+
+        let mut matches = vec![];
+        for action in actions {
+            if action.piece_type != piece_type
+                || is_castling(self.board.piece_lookup.as_ref(), &action)
+                || action.is_drop()
+            {
+                continue;
+            }
+
+            let is_promotion = piece_type == PAWN && action.info >= 0;
+            match promotion {
+                Some(promotion) if !is_promotion || action.info != promotion => continue,
+                None if is_promotion => continue,
+                _ => {}
+            }
+
+            let to = self.encode_pos(action.to);
+            let disambiguation = match rest.strip_suffix(to.as_str()) {
+                Some(disambiguation) => disambiguation,
+                None => continue,
+            };
+
+            let from = self.encode_pos(action.from);
+            let (from_file, from_rank) = split_square(&from);
+            if disambiguation.is_empty()
+                || disambiguation == from_file
+                || disambiguation == from_rank
+                || disambiguation == from
+            {
+                matches.push(action);
+            }
+        }
+
+        match matches[..] {
+            [action] => Some(action),
+            _ => None,
+        }
+    }
+}
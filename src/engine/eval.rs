@@ -3,49 +3,10 @@ use std::collections::HashSet;
 use fnv::FnvHashMap;
 use rand::Rng;
 
-use crate::boards::{generate_legal_moves, generate_moves, Action, Board, PieceGenInfo, PieceInfo};
+use crate::boards::{generate_moves, Action, Board, PieceGenInfo, PieceInfo};
 
 const INNER_CENTER_SQUARES: [i16; 4] = [54, 55, 64, 65];
 
-const CENTER_SQUARES: [i16; 16] = [
-    43, 44, 45, 46, 53, 54, 55, 56, 63, 64, 65, 66, 73, 74, 75, 76,
-];
-
-const CENTER_BOX: [i16; 8] = [
-    53, 54, 55, 56, 63, 64, 65, 66,
-];
-
-pub fn weigh_mobility_move(board: &mut Board, action: &Action) -> i32 {
-    let material_value = board
-        .piece_lookup
-        .lookup(action.piece_type)
-        .get_material_value();
-    let mut score = 5;
-
-    if CENTER_SQUARES.contains(&action.to) {
-        score += 2;
-    }
-
-    score *= 1 + ((9000 - material_value) / 8000);
-
-    if action.capture {
-        let attacker_material = board
-            .piece_lookup
-            .lookup(action.piece_type)
-            .get_material_value();
-        let victim_piece_type = board.get_piece_info(action.to).piece_type;
-        let victim_material = board
-            .piece_lookup
-            .lookup(victim_piece_type)
-            .get_material_value();
-        if victim_material > attacker_material {
-            score += 30;
-        }
-    }
-
-    score
-}
-
 pub struct MobilityInfo {
     piece_material: i32,
     count: i16
@@ -58,6 +19,11 @@ fn weigh_mobility_moves(board: &mut Board, actions: &Vec<Action>, opposing_actio
     let opposing_targets = opposing_actions.iter().map(|action| action.to).collect::<HashSet<_>>();
 
     for action in actions {
+        // Drops aren't moves of a piece on the board, so they don't count as mobility.
+        if action.is_drop() {
+            continue;
+        }
+
         let mut bonus = 2;
         let contested = opposing_targets.contains(&action.to);
 
@@ -93,21 +59,24 @@ fn weigh_mobility_moves(board: &mut Board, actions: &Vec<Action>, opposing_actio
     score
 }
 
-pub fn evaluate(board: &mut Board, pov_team: i16) -> i32 {
-    let mut score: i32 = 0;
+/*
+    How well each team is doing on its own, indexed by team.
+    Material and king safety only count a team's own pieces, while mobility is weighed against the moves of every other team.
+*/
+pub fn evaluate_teams(board: &mut Board) -> Vec<i32> {
+    let mut scores: Vec<i32> = vec![0; board.teams as usize];
     let row_gap = board.row_gap;
 
     for piece in board.pieces.clone() {
         let PieceInfo {
             piece_type, team, ..
         } = board.get_piece_info(piece.pos);
-        let team_multiplier = if team == pov_team { 1 } else { -1 };
 
         let piece_trait = board.piece_lookup.lookup(piece_type);
         let material_value = piece_trait.get_material_value();
-        score += team_multiplier * material_value;
+        scores[team as usize] += material_value;
 
-        if piece_type == 5 {
+        if piece_trait.get_royalty().is_royal() {
             let deltas = [
                 1,
                 -1,
@@ -160,17 +129,57 @@ pub fn evaluate(board: &mut Board, pov_team: i16) -> i32 {
 
             if empty_squares > 0 {
                 let blocked_squares: i32 = empty_squares - open_squares;
-                score -= 2_000
-                    * ((blocked_squares * blocked_squares) / (empty_squares * empty_squares))
-                    * team_multiplier;
+                scores[team as usize] -=
+                    2_000 * ((blocked_squares * blocked_squares) / (empty_squares * empty_squares));
             }
         }
     }
 
-    let moves = generate_moves(board, pov_team);
-    let opposing_moves = generate_moves(board, board.get_next_team(pov_team));
-    score += weigh_mobility_moves(board, &moves, &opposing_moves);
-    score -= weigh_mobility_moves(board, &opposing_moves, &moves);
+    // Pieces in hand are worth about as much as they would be on the board.
+    if let Some(pockets) = board.pockets.clone() {
+        for (team, pocket) in pockets.iter().enumerate() {
+            for (piece_type, count) in pocket.iter().enumerate() {
+                let material_value = board
+                    .piece_lookup
+                    .lookup(piece_type as i16)
+                    .get_material_value();
+                scores[team] += material_value * *count as i32;
+            }
+        }
+    }
 
-    score
+    let moves = (0..board.teams)
+        .map(|team| generate_moves(board, team))
+        .collect::<Vec<_>>();
+    for team in 0..moves.len() {
+        let opposing_moves = moves
+            .iter()
+            .enumerate()
+            .filter(|(other_team, _)| *other_team != team)
+            .flat_map(|(_, actions)| actions.iter().copied())
+            .collect::<Vec<_>>();
+        scores[team] += weigh_mobility_moves(board, &moves[team], &opposing_moves);
+    }
+
+    scores
+}
+
+/*
+    The score of `pov_team` against the strongest of the other teams.
+    With two teams, this is just the difference between them.
+*/
+pub fn evaluate(board: &mut Board, pov_team: i16) -> i32 {
+    get_relative_score(&evaluate_teams(board), pov_team)
+}
+
+pub fn get_relative_score(scores: &[i32], pov_team: i16) -> i32 {
+    let best_opponent = scores
+        .iter()
+        .enumerate()
+        .filter(|(team, _)| *team as i16 != pov_team)
+        .map(|(_, score)| *score)
+        .max()
+        .unwrap_or(0);
+
+    scores[pov_team as usize] - best_opponent
 }
@@ -1,11 +1,17 @@
 mod eval;
 mod move_ordering;
+mod multiplayer;
 mod pv_table;
 mod search;
+mod time_manager;
+mod transposition_table;
 mod util;
 
 pub use eval::*;
 pub use move_ordering::*;
+pub use multiplayer::*;
 pub use pv_table::*;
 pub use search::*;
+pub use time_manager::*;
+pub use transposition_table::*;
 pub use util::*;
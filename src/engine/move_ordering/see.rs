@@ -1,6 +1,11 @@
 use crate::boards::{Action, Board, PieceGenInfo, PieceInfo};
 
 pub fn see(board: &mut Board, square: i16, moving_team: i16, current_attacker: Option<i16>) -> i32 {
+    // Nothing is left to capture, like after an explosion in Atomic.
+    if board.state[square as usize] <= 1 {
+        return 0;
+    }
+
     let row_gap = board.row_gap;
     let targets = vec![square];
     let pieces = board.pieces.clone();
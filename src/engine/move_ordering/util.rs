@@ -1,5 +1,5 @@
 use crate::{
-    boards::{Action, Board},
+    boards::{Action, Board, EN_PASSANT},
     engine::SearchInfo,
 };
 
@@ -14,7 +14,7 @@ pub fn weigh_qs_move(search_info: &mut SearchInfo, board: &mut Board, action: &A
         return 200_000;
     }
 
-    if action.capture && !(action.piece_type == 0 && action.info == -3) {
+    if action.capture && !(action.piece_type == 0 && action.info == EN_PASSANT) {
         // MVV-LVA
 
         let victim_value = board
@@ -65,9 +65,15 @@ pub fn weigh_move(
     if action.capture {
         // MVV-LVA
 
+        // En passant lands on an empty square, so the victim is always a pawn.
+        let victim_piece_type = if action.piece_type == 0 && action.info == EN_PASSANT {
+            0
+        } else {
+            board.get_piece_info(action.to).piece_type
+        };
         let victim_value = board
             .piece_lookup
-            .lookup(board.get_piece_info(action.to).piece_type)
+            .lookup(victim_piece_type)
             .get_material_value();
         let attacker_value = board
             .piece_lookup
@@ -0,0 +1,235 @@
+use std::cmp::Reverse;
+
+use super::{
+    evaluate, evaluate_teams, get_relative_score, move_ordering::weigh_move, ScoredAction,
+    SearchInfo, MAX_VALUE, MIN_VALUE,
+};
+use crate::boards::{Action, Board, GameResult};
+
+/*
+    How positions with more than two teams are searched, since negamax only works when one team's gain is the other's loss.
+    - Paranoid: Every other team is assumed to be playing against us, which keeps alpha-beta pruning but can be too careful.
+    - MaxN: Every team plays for its own score, which is more realistic but can't be pruned.
+*/
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MultiplayerSearch {
+    #[default]
+    Paranoid,
+    MaxN,
+}
+
+impl MultiplayerSearch {
+    pub fn parse(name: &str) -> Option<MultiplayerSearch> {
+        match name.to_lowercase().as_str() {
+            "paranoid" => Some(MultiplayerSearch::Paranoid),
+            "maxn" => Some(MultiplayerSearch::MaxN),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            MultiplayerSearch::Paranoid => "paranoid",
+            MultiplayerSearch::MaxN => "maxn",
+        }
+    }
+}
+
+// The score of a finished game for `team`, where `result` is for the team to move. Everyone but the loser wins.
+fn get_result_score(board: &Board, result: &GameResult, team: i16, ply: i16) -> i32 {
+    let is_moving_team = team == board.moving_team;
+    match (result, is_moving_team) {
+        (GameResult::Win, true) | (GameResult::Lose, false) => MAX_VALUE - (ply as i32),
+        (GameResult::Win, false) | (GameResult::Lose, true) => MIN_VALUE + (ply as i32),
+        _ => 0,
+    }
+}
+
+fn get_result_scores(board: &Board, result: &GameResult, ply: i16) -> Vec<i32> {
+    (0..board.teams)
+        .map(|team| get_result_score(board, result, team, ply))
+        .collect()
+}
+
+fn sort_moves(
+    search_info: &mut SearchInfo,
+    board: &mut Board,
+    actions: Vec<Action>,
+    ply: i16,
+) -> Vec<Action> {
+    let mut sorted_actions: Vec<ScoredAction> = Vec::with_capacity(actions.len());
+    for action in actions {
+        sorted_actions.push(ScoredAction {
+            action,
+            score: weigh_move(search_info, board, &action, &None, &None, ply),
+        });
+    }
+
+    sorted_actions.sort_by_key(|scored_action| Reverse(scored_action.score));
+    sorted_actions
+        .into_iter()
+        .map(|scored_action| scored_action.action)
+        .collect()
+}
+
+/*
+    Paranoid search, from the point of view of `starting_team`.
+    Its moves maximize the score and everyone else's minimize it, so it's plain alpha-beta without negating scores.
+*/
+pub fn paranoid_search(
+    search_info: &mut SearchInfo,
+    board: &mut Board,
+    mut alpha: i32,
+    mut beta: i32,
+    depth: i16,
+    ply: i16,
+    starting_team: i16,
+) -> i32 {
+    search_info.pv_table.init_pv(ply);
+
+    if search_info.should_stop() {
+        return 0;
+    }
+
+    let actions = board.generate_moves(); // Psuedolegal Move Generation
+    let result = board.win_conditions.duplicate().compute(board, &actions);
+    if !matches!(result, GameResult::Ongoing) {
+        return get_result_score(board, &result, starting_team, ply);
+    }
+
+    if depth == 0 {
+        return evaluate(board, starting_team);
+    }
+
+    let maximizing = board.moving_team == starting_team;
+    let mut best_score = if maximizing { MIN_VALUE } else { MAX_VALUE };
+    let mut best_move: Option<Action> = None;
+    let mut legal_moves = 0;
+    for action in sort_moves(search_info, board, actions, ply) {
+        search_info.root_nodes += 1;
+        search_info.nodes += 1;
+        if !board.is_legal(action, board.moving_team) {
+            continue;
+        }
+        legal_moves += 1;
+
+        if ply == 0 && search_info.excluded_moves.contains(&action) {
+            continue;
+        }
+
+        board.make_move(action);
+        let score = paranoid_search(
+            search_info,
+            board,
+            alpha,
+            beta,
+            depth - 1,
+            ply + 1,
+            starting_team,
+        );
+        board.undo_move();
+
+        if search_info.stopped {
+            return 0;
+        }
+
+        let is_better = if maximizing {
+            score > best_score
+        } else {
+            score < best_score
+        };
+        if is_better || best_move.is_none() {
+            best_score = score;
+            best_move = Some(action);
+            search_info.pv_table.update_pv(ply, best_move);
+        }
+
+        if maximizing {
+            alpha = alpha.max(score);
+        } else {
+            beta = beta.min(score);
+        }
+        if alpha >= beta {
+            break;
+        }
+    }
+
+    // The moves are psuedolegal, so checkmate and stalemate only show up once none of them turn out to be legal.
+    if legal_moves == 0 {
+        let result = board.win_conditions.duplicate().compute(board, &vec![]);
+        return get_result_score(board, &result, starting_team, ply);
+    }
+
+    best_score
+}
+
+/*
+    Max^n search, which scores positions for every team at once (indexed by team).
+    Each team picks the move that's best for its own score, without caring about the others.
+*/
+pub fn max_n_search(
+    search_info: &mut SearchInfo,
+    board: &mut Board,
+    depth: i16,
+    ply: i16,
+) -> Vec<i32> {
+    search_info.pv_table.init_pv(ply);
+
+    if search_info.should_stop() {
+        return vec![0; board.teams as usize];
+    }
+
+    let actions = board.generate_moves(); // Psuedolegal Move Generation
+    let result = board.win_conditions.duplicate().compute(board, &actions);
+    if !matches!(result, GameResult::Ongoing) {
+        return get_result_scores(board, &result, ply);
+    }
+
+    if depth == 0 {
+        let scores = evaluate_teams(board);
+        return (0..board.teams)
+            .map(|team| get_relative_score(&scores, team))
+            .collect();
+    }
+
+    let moving_team = board.moving_team as usize;
+    let mut best_scores: Option<Vec<i32>> = None;
+    let mut legal_moves = 0;
+    for action in sort_moves(search_info, board, actions, ply) {
+        search_info.root_nodes += 1;
+        search_info.nodes += 1;
+        if !board.is_legal(action, board.moving_team) {
+            continue;
+        }
+        legal_moves += 1;
+
+        if ply == 0 && search_info.excluded_moves.contains(&action) {
+            continue;
+        }
+
+        board.make_move(action);
+        let scores = max_n_search(search_info, board, depth - 1, ply + 1);
+        board.undo_move();
+
+        if search_info.stopped {
+            return vec![0; board.teams as usize];
+        }
+
+        let is_better = match &best_scores {
+            Some(best_scores) => scores[moving_team] > best_scores[moving_team],
+            None => true,
+        };
+        if is_better {
+            best_scores = Some(scores);
+            search_info.pv_table.update_pv(ply, Some(action));
+        }
+    }
+
+    if legal_moves == 0 {
+        let result = board.win_conditions.duplicate().compute(board, &vec![]);
+        return get_result_scores(board, &result, ply);
+    }
+
+    // Only excluded moves were left at the root.
+    best_scores.unwrap_or_else(|| vec![MIN_VALUE; board.teams as usize])
+}
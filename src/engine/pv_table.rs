@@ -22,6 +22,12 @@ impl PV {
                 self.table[(ply + 1) as usize][next_ply as usize];
         }
         self.length[ply as usize] = self.length[(ply + 1) as usize];
+
+        // Ends the line here, so moves left over from a longer line aren't displayed.
+        let length = self.length[ply as usize] as usize;
+        if length < MAX_DEPTH {
+            self.table[ply as usize][length] = None;
+        }
     }
 
     pub fn display_pv(&mut self, uci: &mut UCICommunicator) -> String {
@@ -39,7 +45,11 @@ impl PV {
                     .iter()
                     .position(|piece| piece.pos == action.from);
 
-                if from_pos_all.is_none() {
+                // Drops don't have a piece on the board yet, they come from the pocket instead.
+                let is_droppable = action.is_drop()
+                    && uci.board.get_pocket(action.team, action.piece_type) > 0
+                    && uci.board.state[action.to as usize] == 1;
+                if from_pos_all.is_none() && !is_droppable {
                     break;
                 }
 
@@ -1,47 +1,120 @@
+use std::cmp::Reverse;
+
 use super::{
-    evaluate, get_epoch_ms, move_ordering::weigh_move, store_counter_move, store_history_move,
-    weigh_qs_move, ScoredAction, SearchInfo, TranspositionEntry, MAX_VALUE, MIN_VALUE, see,
+    evaluate, get_epoch_ms, max_n_search, move_ordering::weigh_move, paranoid_search,
+    score_from_tt, store_counter_move, store_history_move, weigh_qs_move, MultiplayerSearch,
+    ScoredAction, SearchInfo, TimeManager, TranspositionBound, DEFAULT_MAX_SEARCH_DEPTH,
+    MATE_THRESHOLD, MAX_DEPTH, MAX_VALUE, MIN_VALUE,
 };
 use crate::{
-    boards::{hash_board, in_check, Action, Board, GameResult},
+    boards::{in_check, Action, Board, GameResult},
     communication::UCICommunicator,
     engine::store_killer_move,
 };
 
+/*
+    UCI wants mate scores in moves rather than centipawns, positive if we're the one mating.
+*/
+pub fn format_score(score: i32) -> String {
+    if score >= MATE_THRESHOLD {
+        format!("mate {}", (MAX_VALUE - score + 1) / 2)
+    } else if score <= -MATE_THRESHOLD {
+        format!("mate -{}", (score - MIN_VALUE) / 2)
+    } else {
+        format!("cp {}", score / 10)
+    }
+}
+
 pub fn root_search(
     search_info: &mut SearchInfo,
     uci: &mut UCICommunicator,
     starting_team: i16,
-    max_time: u128,
 ) -> i32 {
+    let limits = search_info.limits;
+    let max_depth = limits
+        .depth
+        .unwrap_or(DEFAULT_MAX_SEARCH_DEPTH)
+        .clamp(1, (MAX_DEPTH / 2) as i16);
+
     let mut total_time = 0;
     let mut depth = 1;
     let mut score: i32 = MIN_VALUE;
+
+    // Only completed iterations are trusted, an interrupted one falls back to these.
+    let mut best_score: i32 = MIN_VALUE;
+    let mut best_pv = search_info.pv_table.table[0];
+
+    search_info.time_manager = TimeManager::new(&limits, search_info.options.move_overhead);
+    search_info.fit_board(&uci.board);
+    search_info.nodes = 0;
+    search_info.stopped = false;
+    search_info.excluded_moves.clear();
+    search_info.transposition_table.new_search();
     loop {
         let start = get_epoch_ms();
         search_info.root_depth = depth;
         search_info.root_nodes = 0;
         search_info.quiescence_nodes = 0;
 
-        if score > MIN_VALUE {
-            // Aspiration Windows
+        /*
+            With MultiPV, every line after the first is searched again with the earlier best moves excluded.
+            Each line is kept as (score, pv) so they can be printed once the iteration is done.
+        */
+        let mut lines: Vec<(i32, [Option<Action>; MAX_DEPTH])> =
+            Vec::with_capacity(search_info.options.multi_pv);
+        for pv_index in 0..search_info.options.multi_pv.max(1) {
+            search_info.pv_table.table[0][0] = None;
+
+            // Negamax only works with two teams, so games with more go through a multiplayer search instead.
+            let line_score = if uci.board.teams > 2 {
+                match search_info.options.multiplayer_search {
+                    MultiplayerSearch::Paranoid => paranoid_search(
+                        search_info,
+                        &mut uci.board,
+                        MIN_VALUE,
+                        MAX_VALUE,
+                        depth,
+                        0,
+                        starting_team,
+                    ),
+                    MultiplayerSearch::MaxN => {
+                        max_n_search(search_info, &mut uci.board, depth, 0)[starting_team as usize]
+                    }
+                }
+            } else if pv_index == 0 && score > MIN_VALUE {
+                // Aspiration Windows
 
-            let alpha = score - 250;
-            let beta = score + 250;
-            score = search(
-                search_info,
-                &mut uci.board,
-                alpha,
-                beta,
-                depth,
-                0,
-                starting_team,
-                None,
-                true,
-            );
-            if score <= alpha || score >= beta {
-                // Research
-                score = search(
+                let alpha = score - 250;
+                let beta = score + 250;
+                let line_score = search(
+                    search_info,
+                    &mut uci.board,
+                    alpha,
+                    beta,
+                    depth,
+                    0,
+                    starting_team,
+                    None,
+                    true,
+                );
+                if !search_info.stopped && (line_score <= alpha || line_score >= beta) {
+                    // Research
+                    search(
+                        search_info,
+                        &mut uci.board,
+                        MIN_VALUE,
+                        MAX_VALUE,
+                        depth,
+                        0,
+                        starting_team,
+                        None,
+                        true,
+                    )
+                } else {
+                    line_score
+                }
+            } else {
+                search(
                     search_info,
                     &mut uci.board,
                     MIN_VALUE,
@@ -51,20 +124,31 @@ pub fn root_search(
                     starting_team,
                     None,
                     true,
-                );
+                )
+            };
+
+            if search_info.stopped {
+                break;
             }
-        } else {
-            score = search(
-                search_info,
-                &mut uci.board,
-                MIN_VALUE,
-                MAX_VALUE,
-                depth,
-                0,
-                starting_team,
-                None,
-                true,
-            );
+
+            // There are fewer legal moves than lines.
+            let best_move = match search_info.pv_table.table[0][0] {
+                Some(best_move) => best_move,
+                None => break,
+            };
+
+            lines.push((line_score, search_info.pv_table.table[0]));
+            search_info.excluded_moves.push(best_move);
+        }
+        search_info.excluded_moves.clear();
+
+        if search_info.stopped {
+            if search_info.debug {
+                println!("info string search stopped during depth {}", depth);
+            }
+
+            search_info.pv_table.table[0] = best_pv;
+            return best_score;
         }
 
         let end = get_epoch_ms();
@@ -74,18 +158,56 @@ pub fn root_search(
         search_info.time = total_time;
 
         let nodes = search_info.quiescence_nodes + search_info.root_nodes;
-        println!(
-            "info depth {} time {} score cp {} nodes {} nps {} seldepth {} pv {} ",
-            search_info.root_depth,
-            search_info.time,
-            score / 10,
-            nodes,
-            (nodes / (search_info.time + 1)) * 1000,
-            search_info.sel_depth,
-            search_info.pv_table.display_pv(uci)
-        );
+        for (pv_index, (line_score, pv)) in lines.iter().enumerate() {
+            if search_info.quiet {
+                break;
+            }
+
+            search_info.pv_table.table[0] = *pv;
+            println!(
+                "info depth {} multipv {} time {} score {} nodes {} nps {} seldepth {} hashfull {} pv {} ",
+                search_info.root_depth,
+                pv_index + 1,
+                search_info.time,
+                format_score(*line_score),
+                nodes,
+                (nodes / (search_info.time + 1)) * 1000,
+                search_info.sel_depth,
+                search_info.transposition_table.hashfull(),
+                search_info.pv_table.display_pv(uci)
+            );
+        }
+
+        if let Some((line_score, pv)) = lines.first() {
+            score = *line_score;
+            search_info.pv_table.table[0] = *pv;
+        }
+
+        if depth > 1 {
+            let best_move_changed = best_pv[0] != search_info.pv_table.table[0][0];
+            let score_drop = best_score.saturating_sub(score);
+            search_info
+                .time_manager
+                .update(best_move_changed, score_drop);
+        }
+
+        best_score = score;
+        best_pv = search_info.pv_table.table[0];
+
+        let out_of_time = if search_info.signals.is_pondering() {
+            search_info.time_manager.restart();
+            false
+        } else {
+            search_info.time_manager.soft_limit_reached()
+        };
+
+        // Mate in N moves is found N * 2 - 1 plies from the root.
+        let found_mate = match limits.mate {
+            Some(mate) => score >= MAX_VALUE - (mate as i32 * 2 - 1),
+            None => false,
+        };
 
-        if total_time >= max_time || depth >= 30 {
+        if out_of_time || found_mate || depth >= max_depth {
             return score;
         }
 
@@ -101,6 +223,10 @@ pub fn quiescence(
     starting_team: i16,
     ply: i16,
 ) -> i32 {
+    if search_info.should_stop() {
+        return 0;
+    }
+
     let standing_pat = evaluate(board, board.moving_team);
     if standing_pat >= beta {
         return standing_pat;
@@ -139,10 +265,11 @@ pub fn quiescence(
         });
     }
 
-    sorted_actions.sort_by(|a, b| b.score.cmp(&a.score));
+    sorted_actions.sort_by_key(|scored_action| Reverse(scored_action.score));
 
     for ScoredAction { action, .. } in sorted_actions {
         search_info.quiescence_nodes += 1;
+        search_info.nodes += 1;
         if !board.is_legal(action, board.moving_team) {
             continue;
         }
@@ -151,6 +278,10 @@ pub fn quiescence(
         let score = -quiescence(search_info, board, -beta, -alpha, starting_team, ply + 1);
         board.undo_move();
 
+        if search_info.stopped {
+            return 0;
+        }
+
         if score > alpha {
             alpha = score;
 
@@ -176,6 +307,10 @@ pub fn search(
 ) -> i32 {
     search_info.pv_table.init_pv(ply);
 
+    if search_info.should_stop() {
+        return 0;
+    }
+
     if depth == 0 {
         return quiescence(search_info, board, alpha, beta, starting_team, ply);
     }
@@ -183,13 +318,20 @@ pub fn search(
     assert!(ply >= 0, "ply >= 0");
     assert!(beta >= alpha, "beta >= alpha");
 
-    let hash = hash_board(board, board.moving_team, &board.zobrist) % search_info.max_tt_size;
+    let original_alpha = alpha;
     let mut pv_move: Option<Action> = None;
-    let transposition_entry = search_info.transposition_table[hash].clone();
-    if let Some(entry) = &transposition_entry {
+    if let Some(entry) = search_info.transposition_table.probe(board.hash) {
         pv_move = entry.action;
-        if entry.depth >= depth {
-            return entry.eval;
+
+        // The root never cuts off here, otherwise there would be no best move in the PV table.
+        if ply > 0 && entry.depth >= depth {
+            let eval = score_from_tt(entry.eval, ply);
+            match entry.bound {
+                TranspositionBound::Exact => return eval,
+                TranspositionBound::Lower if eval >= beta => return eval,
+                TranspositionBound::Upper if eval <= alpha => return eval,
+                _ => {}
+            }
         }
     }
 
@@ -203,7 +345,7 @@ pub fn search(
             ply,
             starting_team,
             previous_move,
-            true
+            true,
         );
         pv_move = search_info.pv_table.table[ply as usize][0];
     }
@@ -231,7 +373,7 @@ pub fn search(
         });
     }
 
-    sorted_actions.sort_by(|a, b| b.score.cmp(&a.score));
+    sorted_actions.sort_by_key(|scored_action| Reverse(scored_action.score));
 
     let in_check_before = in_check(board, board.moving_team, board.row_gap);
     if false && !is_pv_node && !in_check_before {
@@ -273,11 +415,18 @@ pub fn search(
     let mut best_move: Option<Action> = None;
     let mut found_pv_node: bool = false;
     let mut moves_tried = 0;
+    let mut legal_moves = 0;
     for ScoredAction { action, .. } in sorted_actions {
         search_info.root_nodes += 1;
+        search_info.nodes += 1;
         if !board.is_legal(action, board.moving_team) {
             continue;
         }
+        legal_moves += 1;
+
+        if ply == 0 && search_info.excluded_moves.contains(&action) {
+            continue;
+        }
 
         board.make_move(action);
         let score = if found_pv_node {
@@ -345,6 +494,11 @@ pub fn search(
         };
         board.undo_move();
 
+        // The score is meaningless if the search was cut off, so it can't be stored anywhere.
+        if search_info.stopped {
+            return 0;
+        }
+
         if score > alpha {
             alpha = score;
             best_move = Some(action);
@@ -368,11 +522,25 @@ pub fn search(
         moves_tried += 1;
     }
 
-    search_info.transposition_table[hash] = Some(TranspositionEntry {
-        eval: alpha,
-        depth,
-        action: best_move,
-    });
+    // The moves are psuedolegal, so checkmate and stalemate only show up once none of them turn out to be legal.
+    if legal_moves == 0 {
+        return match board.win_conditions.duplicate().compute(board, &vec![]) {
+            GameResult::Win => MAX_VALUE - (ply as i32),
+            GameResult::Lose => MIN_VALUE + (ply as i32),
+            _ => 0,
+        };
+    }
+
+    let bound = if alpha >= beta {
+        TranspositionBound::Lower
+    } else if alpha > original_alpha {
+        TranspositionBound::Exact
+    } else {
+        TranspositionBound::Upper
+    };
+    search_info
+        .transposition_table
+        .store(board.hash, alpha, depth, bound, best_move, ply);
 
     return alpha;
 }
@@ -0,0 +1,113 @@
+use super::{get_epoch_ms, SearchLimits};
+
+// How often `search` checks the clock and the stop flag.
+pub const NODES_BETWEEN_TIME_CHECKS: u128 = 1024;
+
+// Without "movestogo", we assume the game lasts about this many more moves.
+pub const DEFAULT_MOVES_TO_GO: u128 = 30;
+
+// Scores are in tenths of a centipawn, so this is a 30cp drop.
+pub const SCORE_DROP_MARGIN: i32 = 300;
+
+/*
+    The soft limit is checked between iterations, since starting an iteration we can't finish is a waste.
+    The hard limit is checked while searching, and the search is cut off as soon as it's reached.
+    The soft limit gets stretched (never past the hard limit) when the search looks unstable.
+*/
+#[derive(Clone, Copy, Debug)]
+pub struct TimeManager {
+    pub start: u128,
+    pub soft_limit: Option<u128>,
+    pub hard_limit: Option<u128>,
+    // The soft limit is scaled by this percentage.
+    pub soft_scale: u128,
+}
+
+impl Default for TimeManager {
+    fn default() -> Self {
+        TimeManager {
+            start: get_epoch_ms(),
+            soft_limit: None,
+            hard_limit: None,
+            soft_scale: 100,
+        }
+    }
+}
+
+impl TimeManager {
+    pub fn new(limits: &SearchLimits, move_overhead: u128) -> TimeManager {
+        let mut time_manager = TimeManager::default();
+        if limits.infinite {
+            return time_manager;
+        }
+
+        if let Some(move_time) = limits.move_time {
+            let move_time = move_time.saturating_sub(move_overhead).max(1);
+            time_manager.soft_limit = Some(move_time);
+            time_manager.hard_limit = Some(move_time);
+            return time_manager;
+        }
+
+        if let Some(time) = limits.time {
+            let available = time.saturating_sub(move_overhead).max(1);
+            let inc = limits.inc.unwrap_or(0);
+            let moves_to_go = limits.moves_to_go.unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+
+            // The last move before the time control can use up everything but the overhead.
+            let hard_limit = if moves_to_go == 1 {
+                (available * 3) / 4
+            } else {
+                available / 3
+            };
+            let soft_limit = (available / (moves_to_go + 1)) + ((inc * 3) / 4);
+
+            time_manager.hard_limit = Some(hard_limit.max(1));
+            time_manager.soft_limit = Some(soft_limit.min(hard_limit).max(1));
+        }
+
+        time_manager
+    }
+
+    pub fn elapsed(&self) -> u128 {
+        get_epoch_ms().saturating_sub(self.start)
+    }
+
+    pub fn restart(&mut self) {
+        self.start = get_epoch_ms();
+    }
+
+    /*
+        Called after every iteration.
+        If the best move changed or the score dropped, the position is probably harder than it looked, so we spend more time.
+    */
+    pub fn update(&mut self, best_move_changed: bool, score_drop: i32) {
+        self.soft_scale = 100;
+        if best_move_changed {
+            self.soft_scale += 40;
+        }
+        if score_drop >= SCORE_DROP_MARGIN {
+            self.soft_scale += 60;
+        }
+    }
+
+    pub fn soft_limit_reached(&self) -> bool {
+        let soft_limit = match self.soft_limit {
+            Some(soft_limit) => (soft_limit * self.soft_scale) / 100,
+            None => return false,
+        };
+
+        let soft_limit = match self.hard_limit {
+            Some(hard_limit) => soft_limit.min(hard_limit),
+            None => soft_limit,
+        };
+
+        self.elapsed() >= soft_limit
+    }
+
+    pub fn hard_limit_reached(&self) -> bool {
+        match self.hard_limit {
+            Some(hard_limit) => self.elapsed() >= hard_limit,
+            None => false,
+        }
+    }
+}
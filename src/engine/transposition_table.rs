@@ -0,0 +1,167 @@
+use std::mem::size_of;
+
+use crate::boards::Action;
+
+use super::{MAX_DEPTH, MAX_VALUE};
+
+pub const DEFAULT_HASH_MB: usize = 16;
+pub const BUCKET_SIZE: usize = 4;
+
+// Any score past this is a mate, stored relative to the position instead of the root.
+pub const MATE_THRESHOLD: i32 = MAX_VALUE - (MAX_DEPTH as i32);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TranspositionBound {
+    // The eval is the exact score of the position.
+    Exact,
+    // The search failed high, so the real score is at least the eval.
+    Lower,
+    // No move raised alpha, so the real score is at most the eval.
+    Upper,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TranspositionEntry {
+    // The full hash, since many positions share the same bucket.
+    pub key: usize,
+    pub eval: i32,
+    pub depth: i16,
+    pub bound: TranspositionBound,
+    pub age: u8,
+    pub action: Option<Action>,
+}
+
+pub type TranspositionBucket = [Option<TranspositionEntry>; BUCKET_SIZE];
+
+pub struct TranspositionTable {
+    pub buckets: Vec<TranspositionBucket>,
+    // Bumped once per search, so entries from older searches are replaced first.
+    pub age: u8,
+}
+
+/*
+    Mate scores are relative to the root (`MAX_VALUE - ply`), but the same position can be reached at a different ply.
+    They're stored as the distance to mate from the position itself, and converted back when probing.
+*/
+pub fn score_to_tt(score: i32, ply: i16) -> i32 {
+    if score >= MATE_THRESHOLD {
+        score + (ply as i32)
+    } else if score <= -MATE_THRESHOLD {
+        score - (ply as i32)
+    } else {
+        score
+    }
+}
+
+pub fn score_from_tt(score: i32, ply: i16) -> i32 {
+    if score >= MATE_THRESHOLD {
+        score - (ply as i32)
+    } else if score <= -MATE_THRESHOLD {
+        score + (ply as i32)
+    } else {
+        score
+    }
+}
+
+impl TranspositionTable {
+    pub fn new(megabytes: usize) -> TranspositionTable {
+        let bucket_count = (megabytes * 1024 * 1024) / size_of::<TranspositionBucket>();
+
+        TranspositionTable {
+            buckets: vec![[None; BUCKET_SIZE]; bucket_count.max(1)],
+            age: 0,
+        }
+    }
+
+    pub fn resize(&mut self, megabytes: usize) {
+        *self = TranspositionTable::new(megabytes);
+    }
+
+    pub fn clear(&mut self) {
+        for bucket in &mut self.buckets {
+            *bucket = [None; BUCKET_SIZE];
+        }
+        self.age = 0;
+    }
+
+    pub fn new_search(&mut self) {
+        self.age = self.age.wrapping_add(1);
+    }
+
+    fn get_bucket_index(&self, key: usize) -> usize {
+        key % self.buckets.len()
+    }
+
+    pub fn probe(&self, key: usize) -> Option<TranspositionEntry> {
+        let bucket = &self.buckets[self.get_bucket_index(key)];
+        bucket
+            .iter()
+            .flatten()
+            .find(|entry| entry.key == key)
+            .copied()
+    }
+
+    /*
+        An entry for the same position is always replaced, then empty slots are filled.
+        Otherwise, we replace the entry that is the least useful, being shallow or from an older search.
+    */
+    pub fn store(
+        &mut self,
+        key: usize,
+        eval: i32,
+        depth: i16,
+        bound: TranspositionBound,
+        action: Option<Action>,
+        ply: i16,
+    ) {
+        let age = self.age;
+        let bucket_index = self.get_bucket_index(key);
+        let bucket = &mut self.buckets[bucket_index];
+
+        let mut replace_index = 0;
+        let mut replace_score = i32::MAX;
+        for (index, entry) in bucket.iter().enumerate() {
+            let score = match entry {
+                Some(entry) if entry.key == key => {
+                    replace_index = index;
+                    break;
+                }
+                Some(entry) => (entry.depth as i32) - 8 * (age.wrapping_sub(entry.age) as i32),
+                None => i32::MIN,
+            };
+
+            if score < replace_score {
+                replace_index = index;
+                replace_score = score;
+            }
+        }
+
+        // Keep the old move around if this search didn't find one, it's still better than nothing for move ordering.
+        let action = match (action, bucket[replace_index]) {
+            (None, Some(entry)) if entry.key == key => entry.action,
+            _ => action,
+        };
+
+        bucket[replace_index] = Some(TranspositionEntry {
+            key,
+            eval: score_to_tt(eval, ply),
+            depth,
+            bound,
+            age,
+            action,
+        });
+    }
+
+    // How full the table is, in permille, sampled from the first thousand buckets like UCI's "hashfull".
+    pub fn hashfull(&self) -> usize {
+        let sample = self.buckets.len().min(1000);
+        let used = self.buckets[0..sample]
+            .iter()
+            .flatten()
+            .flatten()
+            .filter(|entry| entry.age == self.age)
+            .count();
+
+        (used * 1000) / (sample * BUCKET_SIZE)
+    }
+}
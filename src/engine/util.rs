@@ -1,14 +1,16 @@
-use crate::boards::Action;
-use std::time::{SystemTime, UNIX_EPOCH};
+use crate::boards::{Action, Board};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
 
-use super::{CounterMoves, HistoryMoves, KillerMoves, PV};
-
-#[derive(Clone, Debug)]
-pub struct TranspositionEntry {
-    pub eval: i32,
-    pub depth: i16,
-    pub action: Option<Action>,
-}
+use super::{
+    CounterMoves, HistoryMoves, KillerMoves, MultiplayerSearch, TimeManager, TranspositionTable,
+    DEFAULT_HASH_MB, NODES_BETWEEN_TIME_CHECKS, PV,
+};
 
 #[derive(Clone, Debug)]
 pub struct ScoredAction {
@@ -21,6 +23,74 @@ pub const MIN_VALUE: i32 = -2_000_000_000;
 pub const MAX_VALUE: i32 = 2_000_000_000;
 
 pub const MAX_DEPTH: usize = 100;
+pub const DEFAULT_MAX_SEARCH_DEPTH: i16 = 30;
+
+/*
+    Limits from a UCI "go" command. Anything left as None doesn't limit the search.
+    An infinite search only ends on "stop", even after it runs out of depth.
+    The clock ("time", "inc" and "moves_to_go") is for the moving team, and is turned into a budget by the `TimeManager`.
+*/
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SearchLimits {
+    pub depth: Option<i16>,
+    pub nodes: Option<u128>,
+    pub move_time: Option<u128>,
+    pub time: Option<u128>,
+    pub inc: Option<u128>,
+    pub moves_to_go: Option<u128>,
+    pub mate: Option<i16>,
+    pub infinite: bool,
+}
+
+/*
+    Settings that are changed through UCI's "setoption", and outlive a single game.
+*/
+#[derive(Clone, Debug)]
+pub struct SearchOptions {
+    pub hash_mb: usize,
+    // Lotisa only searches on one thread for now, this is kept so GUIs can set it.
+    pub threads: usize,
+    pub multi_pv: usize,
+    pub ponder: bool,
+    // Subtracted from every time budget, to make up for lag between the engine and the GUI.
+    pub move_overhead: u128,
+    // How games with more than two teams are searched.
+    pub multiplayer_search: MultiplayerSearch,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        SearchOptions {
+            hash_mb: DEFAULT_HASH_MB,
+            threads: 1,
+            multi_pv: 1,
+            ponder: false,
+            move_overhead: 10,
+            multiplayer_search: MultiplayerSearch::default(),
+        }
+    }
+}
+
+/*
+    Signals shared between the UCI thread and the search thread.
+    - stop: Set by "stop" and "quit", the search finishes as soon as it notices.
+    - pondering: Set by "go ponder" and cleared by "ponderhit", time limits don't apply while it's set.
+*/
+#[derive(Debug, Default)]
+pub struct SearchSignals {
+    pub stop: AtomicBool,
+    pub pondering: AtomicBool,
+}
+
+impl SearchSignals {
+    pub fn is_stopped(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+    }
+
+    pub fn is_pondering(&self) -> bool {
+        self.pondering.load(Ordering::Relaxed)
+    }
+}
 
 pub struct SearchInfo {
     pub root_nodes: u128,
@@ -28,12 +98,76 @@ pub struct SearchInfo {
     pub root_depth: i16,
     pub time: u128,
     pub pv_table: PV,
-    pub transposition_table: Vec<Option<TranspositionEntry>>,
-    pub max_tt_size: usize,
+    pub transposition_table: TranspositionTable,
     pub killer_moves: KillerMoves,
     pub history_moves: HistoryMoves,
     pub counter_moves: CounterMoves,
     pub sel_depth: i16,
+    // Nodes searched across every iteration, unlike `root_nodes` and `quiescence_nodes`.
+    pub nodes: u128,
+    pub limits: SearchLimits,
+    pub time_manager: TimeManager,
+    pub signals: Arc<SearchSignals>,
+    // Set once the search is cut off, so the unfinished iteration isn't trusted.
+    pub stopped: bool,
+    pub debug: bool,
+    // Skips printing "info" lines, for searches that aren't run through UCI.
+    pub quiet: bool,
+    pub options: SearchOptions,
+    // Root moves skipped by the search, so MultiPV can find the next best line.
+    pub excluded_moves: Vec<Action>,
+}
+
+impl SearchInfo {
+    /*
+        The history and counter move tables are indexed by square (and history by team too), and they start out sized for two teams on an 8x8 board.
+        Bigger boards and more teams get bigger tables before they're searched.
+    */
+    pub fn fit_board(&mut self, board: &Board) {
+        let squares = board.state.len();
+        let teams = board.teams as usize;
+        if self.counter_moves.len() < squares || self.history_moves.len() < teams {
+            let squares = squares.max(self.counter_moves.len());
+            self.history_moves = vec![vec![vec![0; squares]; squares]; teams.max(2)];
+            self.counter_moves = vec![vec![None; squares]; squares];
+        }
+    }
+
+    /*
+        Checked at every node. The node limit is cheap to check, but the clock and the stop flag are only polled every so often.
+    */
+    pub fn should_stop(&mut self) -> bool {
+        if self.stopped {
+            return true;
+        }
+
+        // The first iteration always finishes, so there's a move to play.
+        if self.root_depth <= 1 {
+            return false;
+        }
+
+        if let Some(nodes) = self.limits.nodes {
+            if self.nodes >= nodes {
+                self.stopped = true;
+                return true;
+            }
+        }
+
+        if self.nodes.is_multiple_of(NODES_BETWEEN_TIME_CHECKS) {
+            // The clock isn't running while we ponder, so the budget only starts after "ponderhit".
+            if self.signals.is_pondering() {
+                self.time_manager.restart();
+            } else if self.time_manager.hard_limit_reached() {
+                self.stopped = true;
+            }
+
+            if self.signals.is_stopped() {
+                self.stopped = true;
+            }
+        }
+
+        self.stopped
+    }
 }
 
 pub fn get_epoch_ms() -> u128 {
@@ -0,0 +1,37 @@
+use std::fmt;
+
+/*
+    Errors from input that Lotisa doesn't control: FENs, moves and PGNs from users or GUIs.
+    Each one carries a message describing what was wrong with the input.
+*/
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LotisaError {
+    InvalidFen(String),
+    InvalidSquare(String),
+    // The move couldn't be read at all.
+    InvalidMove(String),
+    // The move was read, but it can't be played in the current position.
+    IllegalMove(String),
+    InvalidPgn(String),
+    InvalidCommand(String),
+    // A piece definition (like a Betza string) that can't be read.
+    InvalidPiece(String),
+    InvalidVariant(String),
+}
+
+impl fmt::Display for LotisaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LotisaError::InvalidFen(message) => write!(f, "invalid FEN: {}", message),
+            LotisaError::InvalidSquare(square) => write!(f, "invalid square: {}", square),
+            LotisaError::InvalidMove(action) => write!(f, "invalid move: {}", action),
+            LotisaError::IllegalMove(action) => write!(f, "illegal move: {}", action),
+            LotisaError::InvalidPgn(message) => write!(f, "invalid PGN: {}", message),
+            LotisaError::InvalidCommand(message) => write!(f, "invalid command: {}", message),
+            LotisaError::InvalidPiece(message) => write!(f, "invalid piece: {}", message),
+            LotisaError::InvalidVariant(message) => write!(f, "invalid variant: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for LotisaError {}
@@ -1,43 +1,80 @@
-use communication::UCICommunicator;
-use tests::boards_bench;
-use core::time;
-use rand::seq::{IteratorRandom, SliceRandom};
-use std::{
-    env,
-    io::{self, BufRead, Stdin},
-    thread,
-    time::{Duration, SystemTime, UNIX_EPOCH},
-};
-
-use boards::Board;
+use std::{env, io, process};
 
-use crate::cli::run_uci;
+use crate::{
+    boards::Variant,
+    cli::run_uci,
+    commands::{
+        run_analyze, run_annotate, run_bench, run_perft, run_perft_suite, run_self_test,
+        run_selfplay, take_variant, CHESS_ONLY_COMMANDS, USAGE,
+    },
+};
 
 mod boards;
 mod cli;
+mod commands;
 mod communication;
 mod engine;
+mod error;
+#[cfg(test)]
 mod tests;
 mod util;
 
 fn main() {
-    boards_bench();
-    return;
-
     env::set_var("RUST_BACKTRACE", "1");
 
-    let mut args = env::args().collect::<Vec<_>>();
-    let stdin = io::stdin();
-
-    if args.len() == 1 {
-        let first_line = stdin.lock().lines().next().unwrap().unwrap();
-        if first_line == "uci" {
-            println!("id name Lotisa 0.0.1");
-            println!("id author Corman");
-            println!("uciok");
-            run_uci(stdin);
-        } else if first_line == "test" {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let mut args = args.iter().map(|arg| arg.as_str()).collect::<Vec<_>>();
+
+    let variant = match take_variant(&mut args) {
+        Ok(variant) => variant,
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(1);
+        }
+    };
+    // These only know chess, so they'd quietly play chess with whatever variant was asked for.
+    if let (Some(_), Some(command)) = (&variant, args.first()) {
+        if CHESS_ONLY_COMMANDS.contains(command) {
+            eprintln!("{} doesn't take --variant", command);
+            process::exit(1);
+        }
+    }
+
+    // The chosen variant is played by default, but the built-in variants stay selectable over UCI.
+    let mut variants = Variant::built_in();
+    if let Some(variant) = variant {
+        variants.retain(|built_in| built_in.name != variant.name);
+        variants.insert(0, variant);
+    }
+
+    // UCI is the default, since that's how GUIs and our SPRT harness start the engine.
+    let result = match args.first() {
+        None | Some(&"uci") => {
+            run_uci(io::stdin(), variants);
+            Ok(())
         }
-        return;
+        Some(&"bench") => run_bench(&args[1..]),
+        Some(&"perft") => run_perft(&args[1..], &variants[0]),
+        Some(&"perft-suite") => run_perft_suite(&args[1..]),
+        Some(&"analyze") => run_analyze(&args[1..], &variants[0]),
+        Some(&"annotate") => run_annotate(&args[1..]),
+        Some(&"selfplay") => run_selfplay(&args[1..], &variants[0]),
+        Some(&"test") => {
+            if !run_self_test() {
+                process::exit(1);
+            }
+            Ok(())
+        }
+        Some(&"help") | Some(&"--help") => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        Some(command) => Err(format!("Unknown command: {}", command)),
+    };
+
+    if let Err(error) = result {
+        eprintln!("{}", error);
+        eprintln!("{}", USAGE);
+        process::exit(1);
     }
 }
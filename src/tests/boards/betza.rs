@@ -0,0 +1,158 @@
+use crate::{
+    boards::{
+        in_check, perft, BetzaDefinition, BetzaHop, BetzaMode, Board, BoardLayout, PieceLookup,
+        PieceMapLookup,
+    },
+    communication::{Communicator, UCICommunicator},
+    error::LotisaError,
+};
+
+fn betza_layout(pieces: Vec<(i16, char, &str)>, buffer_amount: i16) -> BoardLayout {
+    let definitions = pieces
+        .into_iter()
+        .map(|(piece_type, letter, betza)| {
+            (piece_type, letter, BetzaDefinition::parse(betza).unwrap())
+        })
+        .collect::<Vec<_>>();
+    let piece_types = definitions
+        .iter()
+        .map(|(piece_type, ..)| piece_type + 1)
+        .max()
+        .unwrap_or(6)
+        .max(6);
+
+    BoardLayout::new(
+        piece_types,
+        2,
+        (8, 8),
+        Box::new(move |row_gap| {
+            let mut lookup = PieceMapLookup::new(PieceMapLookup::default_map(row_gap));
+            for (piece_type, letter, definition) in &definitions {
+                lookup =
+                    lookup.with_piece(*piece_type, *letter, Box::new(definition.create(row_gap)));
+            }
+            Box::new(lookup) as Box<dyn PieceLookup>
+        }),
+    )
+    .with_buffer_amount(buffer_amount)
+}
+
+#[test]
+fn betza_parse_test() {
+    let cannon = BetzaDefinition::parse("mRcpR").unwrap();
+    assert_eq!(cannon.atoms.len(), 2);
+    assert_eq!(cannon.atoms[0].mode, BetzaMode::Move);
+    assert_eq!(cannon.atoms[1].mode, BetzaMode::Capture);
+    assert_eq!(cannon.atoms[1].hop, Some(BetzaHop::Cannon));
+    assert_eq!(cannon.atoms[1].range, None);
+
+    assert_eq!(
+        BetzaDefinition::parse("fN").unwrap().atoms[0].vectors.len(),
+        4
+    );
+    assert_eq!(
+        BetzaDefinition::parse("ffN").unwrap().atoms[0]
+            .vectors
+            .len(),
+        2
+    );
+    assert_eq!(
+        BetzaDefinition::parse("flF").unwrap().atoms[0].vectors,
+        vec![(-1, 1)]
+    );
+    assert_eq!(
+        BetzaDefinition::parse("R4").unwrap().atoms[0].range,
+        Some(4)
+    );
+    assert_eq!(BetzaDefinition::parse("NN").unwrap().atoms[0].range, None);
+    assert!(BetzaDefinition::parse("imfF2").unwrap().atoms[0].initial);
+
+    assert_eq!(BetzaDefinition::parse("N").unwrap().value, 3000);
+    assert_eq!(BetzaDefinition::parse("C").unwrap().get_reach(), 3);
+    assert_eq!(BetzaDefinition::parse("BN").unwrap().get_reach(), 2);
+
+    for invalid in ["", "X", "pN", "m", "yW", "sF"] {
+        assert!(
+            matches!(
+                BetzaDefinition::parse(invalid),
+                Err(LotisaError::InvalidPiece(_))
+            ),
+            "{} is invalid",
+            invalid
+        );
+    }
+}
+
+#[test]
+fn betza_standard_pieces_perft() {
+    // Kiwipete with every piece but the pawns and kings defined by Betza strings
+    let layout = betza_layout(
+        vec![(1, 'n', "N"), (2, 'b', "B"), (3, 'r', "R"), (4, 'q', "Q")],
+        2,
+    );
+    let mut uci = Board::try_load_variant_fen(
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        &layout,
+    )
+    .unwrap();
+
+    assert_eq!(perft(&mut uci, 1, None), 48, "Perft Test (depth = 1)");
+    assert_eq!(perft(&mut uci, 2, None), 2039, "Perft Test (depth = 2)");
+    assert_eq!(perft(&mut uci, 3, None), 97862, "Perft Test (depth = 3)");
+}
+
+#[test]
+fn betza_camel_test() {
+    // A camel leaps 3 squares, so the board needs a bigger buffer.
+    let layout = betza_layout(vec![(6, 'l', "C")], 3);
+
+    let mut uci = Board::try_load_variant_fen("7k/8/8/8/3L4/8/8/L6K w - - 0 1", &layout).unwrap();
+    let camel_moves = |uci: &mut UCICommunicator, from: &str| {
+        let from = uci.decode_pos(from.to_string());
+        uci.board
+            .generate_legal_moves()
+            .into_iter()
+            .filter(|action| action.from == from)
+            .count()
+    };
+
+    assert_eq!(camel_moves(&mut uci, "d4"), 8);
+    assert_eq!(camel_moves(&mut uci, "a1"), 2);
+}
+
+#[test]
+fn betza_hopper_test() {
+    let layout = betza_layout(vec![(6, 'c', "mRcpR"), (7, 'g', "gQ")], 2);
+
+    // The cannon moves like a rook, but has to capture over a screen.
+    let mut uci = Board::try_load_variant_fen("7k/8/r7/8/8/P7/8/C3K3 w - - 0 1", &layout).unwrap();
+    let cannon_moves = uci
+        .board
+        .generate_legal_moves()
+        .into_iter()
+        .filter(|action| action.piece_type == 6)
+        .collect::<Vec<_>>();
+    assert_eq!(cannon_moves.len(), 5);
+    assert_eq!(
+        cannon_moves.iter().filter(|action| action.capture).count(),
+        1
+    );
+
+    let mut uci = Board::try_load_variant_fen("k7/8/8/8/8/P7/8/C3K3 b - - 0 1", &layout).unwrap();
+    let row_gap = uci.board.row_gap;
+    assert!(
+        in_check(&mut uci.board, 1, row_gap),
+        "The cannon checks over the pawn"
+    );
+
+    // The grasshopper lands right behind the first piece in each direction.
+    let mut uci =
+        Board::try_load_variant_fen("8/1k1p4/8/3G1P2/8/8/8/4K3 w - - 0 1", &layout).unwrap();
+    let grasshopper_moves = uci
+        .board
+        .generate_legal_moves()
+        .into_iter()
+        .filter(|action| action.piece_type == 7)
+        .count();
+    assert_eq!(grasshopper_moves, 3);
+}
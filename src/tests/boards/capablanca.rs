@@ -0,0 +1,90 @@
+use crate::{
+    boards::{perft, Variant},
+    communication::Communicator,
+};
+
+fn get_moves_from(variant: &Variant, fen: &str, from: &str) -> Vec<String> {
+    let mut uci = variant.try_load_fen(fen).unwrap();
+    let from = uci.decode_pos(from.to_string());
+    let mut moves = uci
+        .board
+        .generate_legal_moves()
+        .iter()
+        .filter(|action| action.from == from)
+        .map(|action| uci.encode(action))
+        .collect::<Vec<_>>();
+    moves.sort();
+    moves
+}
+
+// Perft results from Fairy-Stockfish, there aren't any lichess variants with these boards to check against.
+#[test]
+fn capablanca_perft() {
+    let variant = Variant::capablanca();
+    let mut uci = variant.load_startpos();
+    assert_eq!(perft(&mut uci, 1, None), 28);
+    assert_eq!(perft(&mut uci, 2, None), 784);
+    assert_eq!(perft(&mut uci, 3, None), 25228);
+}
+
+#[test]
+fn grand_perft() {
+    let variant = Variant::grand();
+    let mut uci = variant.load_startpos();
+    assert_eq!(perft(&mut uci, 1, None), 65);
+    assert_eq!(perft(&mut uci, 2, None), 4225);
+    assert_eq!(perft(&mut uci, 3, None), 259514);
+}
+
+#[test]
+fn compound_piece_test() {
+    let variant = Variant::capablanca();
+
+    // The Archbishop moves as a Bishop and a Knight, and the Chancellor as a Rook and a Knight.
+    let archbishop = get_moves_from(&variant, "k9/10/10/10/4A5/10/10/K9 w - - 0 1", "e4");
+    assert_eq!(archbishop.len(), 13 + 8);
+    assert!(archbishop.contains(&"e4f6".to_string()));
+    assert!(archbishop.contains(&"e4h7".to_string()));
+    assert!(!archbishop.contains(&"e4e5".to_string()));
+
+    let chancellor = get_moves_from(&variant, "k9/10/10/10/4C5/10/10/K9 w - - 0 1", "e4");
+    assert_eq!(chancellor.len(), 16 + 8);
+    assert!(chancellor.contains(&"e4f6".to_string()));
+    assert!(chancellor.contains(&"e4j4".to_string()));
+    assert!(!chancellor.contains(&"e4f5".to_string()));
+
+    // A Knight check from either piece can't be blocked.
+    let mut uci = variant
+        .try_load_fen("k9/10/1C8/10/10/10/10/K9 b - - 0 1")
+        .unwrap();
+    assert_eq!(uci.board.generate_legal_moves().len(), 1);
+
+    // The king moves three squares when castling.
+    let king = get_moves_from(&variant, "r4k3r/10/10/10/10/10/10/R4K3R w KQkq - 0 1", "f1");
+    assert!(king.contains(&"f1c1".to_string()));
+    assert!(king.contains(&"f1i1".to_string()));
+}
+
+#[test]
+fn grand_promotion_test() {
+    let variant = Variant::grand();
+
+    // Pawns can stay pawns before the last rank, and can promote to anything that's been captured.
+    let moves = get_moves_from(&variant, "k9/10/4P5/10/10/10/10/10/10/K9 w - - 0 1", "e8");
+    assert_eq!(
+        moves,
+        vec!["e8e9", "e8e9b", "e8e9c", "e8e9m", "e8e9n", "e8e9q", "e8e9r"]
+    );
+
+    // Without anything captured, a pawn can't move onto the last rank at all.
+    let fen = "k9/4P5/10/10/10/10/10/10/1NBQ1MCBN1/R3K4R w - - 0 1";
+    assert!(get_moves_from(&variant, fen, "e9").is_empty());
+
+    // Once the queen is gone, it's the only promotion.
+    let fen = "k9/4P5/10/10/10/10/10/10/1NB2MCBN1/R3K4R w - - 0 1";
+    assert_eq!(get_moves_from(&variant, fen, "e9"), vec!["e9e10q"]);
+
+    // Pawns start on the third rank, and can double move from there.
+    let moves = get_moves_from(&variant, &variant.startpos, "e3");
+    assert_eq!(moves, vec!["e3e4", "e3e5"]);
+}
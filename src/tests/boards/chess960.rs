@@ -0,0 +1,133 @@
+use crate::{
+    boards::{get_chess960_back_rank, get_chess960_fen, perft, Board, CHESS960_POSITIONS},
+    communication::Communicator,
+};
+
+#[test]
+fn chess960_perft() {
+    // Perft Results sourced on the Chess Programming Wiki (https://www.chessprogramming.org/Chess960_Perft_Results)
+    let positions = [
+        (
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+            12189,
+        ),
+        (
+            "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9",
+            18002,
+        ),
+        (
+            "b1q1rrkb/pppppppp/3nn3/8/P7/1PPP4/4PPPP/BQNNRKRB w GE - 1 9",
+            10471,
+        ),
+        (
+            "qbbnnrkr/2pp2pp/p7/1p2pp2/8/P3PP2/1PPP1KPP/QBBNNR1R w hf - 0 9",
+            13440,
+        ),
+        (
+            "qnbnr1kr/ppp1b1pp/4p3/3p1p2/8/2NPP3/PPP1BPPP/QNB1R1KR w HEhe - 1 9",
+            26578,
+        ),
+    ];
+
+    for (fen, nodes) in positions {
+        let mut uci = Board::load_fen(fen);
+        assert_eq!(perft(&mut uci, 3, None), nodes, "Perft Test: {}", fen);
+    }
+}
+
+#[test]
+fn chess960_fen_test() {
+    // Shredder-FEN rights for the outermost rooks come out as the usual letters.
+    let uci = Board::load_fen("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9");
+    assert_eq!(
+        uci.board.to_fen(),
+        "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 2 9"
+    );
+
+    // A right for an inner rook keeps its file, like in X-FEN.
+    let fens = [
+        "4k3/8/8/8/8/8/8/R1R1K3 w C - 0 1",
+        "4k3/8/8/8/8/8/8/R1R1K3 w Q - 0 1",
+        "r1r1k3/8/8/8/8/8/8/4K3 b c - 0 1",
+    ];
+    for fen in fens {
+        assert_eq!(Board::load_fen(fen).board.to_fen(), fen, "FEN round trip");
+    }
+
+    let uci = Board::load_fen("1r2kr2/8/8/8/8/8/8/4K3 b bf - 0 1");
+    assert_eq!(uci.board.to_fen(), "1r2kr2/8/8/8/8/8/8/4K3 b kq - 0 1");
+
+    assert!(Board::try_load_fen("4k3/8/8/8/8/8/8/R3K2R w I - 0 1").is_err());
+}
+
+#[test]
+fn chess960_castling_test() {
+    // The king is already on g1, so only the rook moves.
+    let mut uci = Board::load_fen("4k3/8/8/8/8/8/8/R5KR w H - 0 1");
+    let castling = uci
+        .board
+        .generate_legal_moves()
+        .into_iter()
+        .find(|action| action.info == 1);
+    let castling = castling.expect("The king can castle with the h1 rook.");
+
+    uci.board.chess960 = true;
+    assert_eq!(uci.encode(&castling), "g1h1");
+    uci.board.make_move(castling);
+    assert_eq!(uci.board.to_fen(), "4k3/8/8/8/8/8/8/R4RK1 b - - 1 1");
+
+    uci.board.undo_move();
+    assert_eq!(uci.board.to_fen(), "4k3/8/8/8/8/8/8/R5KR w K - 0 1");
+
+    // Standard castling is written as the king's move, unless UCI_Chess960 is on.
+    let mut uci = Board::load_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+    assert!(uci.try_decode("e1g1").is_ok());
+    assert!(uci.try_decode("e1h1").is_err());
+    uci.board.chess960 = true;
+    assert!(uci.try_decode("e1h1").is_ok());
+    assert!(uci.try_decode("e1a1").is_ok());
+    assert!(uci.try_decode("e1g1").is_err());
+}
+
+#[test]
+fn chess960_startpos_test() {
+    assert_eq!(
+        get_chess960_fen(518).unwrap(),
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+    );
+    assert_eq!(
+        get_chess960_back_rank(0)
+            .unwrap()
+            .iter()
+            .collect::<String>(),
+        "bbqnnrkr"
+    );
+    assert!(get_chess960_fen(CHESS960_POSITIONS).is_none());
+
+    let mut back_ranks = vec![];
+    for index in 0..CHESS960_POSITIONS {
+        let back_rank = get_chess960_back_rank(index).unwrap();
+        let find = |piece: char| {
+            (0..8)
+                .filter(|col| back_rank[*col] == piece)
+                .collect::<Vec<_>>()
+        };
+
+        let bishops = find('b');
+        let rooks = find('r');
+        let king = find('k')[0];
+        assert_ne!(bishops[0] % 2, bishops[1] % 2, "Bishops on opposite colors");
+        assert!(rooks[0] < king && king < rooks[1], "King between the rooks");
+        assert_eq!(find('q').len(), 1);
+        assert_eq!(find('n').len(), 2);
+
+        back_ranks.push(back_rank);
+    }
+
+    back_ranks.sort();
+    back_ranks.dedup();
+    assert_eq!(back_ranks.len(), CHESS960_POSITIONS as usize);
+
+    let mut uci = Board::load_fen(&get_chess960_fen(0).unwrap());
+    assert_eq!(perft(&mut uci, 2, None), 400);
+}
@@ -0,0 +1,125 @@
+use crate::{
+    boards::{is_draw_by_insufficient_material, perft, Variant},
+    communication::Communicator,
+};
+
+use super::zobrist::check_hashes;
+
+#[test]
+fn crazyhouse_perft() {
+    // Node counts checked against shakmaty's crazyhouse move generation.
+    let positions = [
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[] w KQkq - 0 1",
+            3,
+            8902,
+        ),
+        (
+            "r1bqkbnr/ppp2ppp/2n5/4p3/4P3/5N2/PPP2PPP/RNBQKB1R[Pp] w KQkq - 0 4",
+            3,
+            256549,
+        ),
+        ("2k5/4P3/8/8/8/8/1q6/R3K2R[NPbp] w KQ - 0 1", 2, 15466),
+        (
+            "rnb1kbnr/ppp1pppp/8/3q4/3Q~4/8/PPP1PPPP/RNB1KBNR[p] b KQkq - 0 1",
+            3,
+            200388,
+        ),
+        (
+            "r1b1k2r/pP3ppp/8/8/4Q~3/8/PP3PPP/R3K2R[Npp] b KQkq - 0 1",
+            3,
+            34306,
+        ),
+    ];
+
+    let variant = Variant::crazyhouse();
+    for (fen, depth, nodes) in positions {
+        let mut uci = variant.try_load_fen(fen).unwrap();
+        assert_eq!(perft(&mut uci, depth, None), nodes, "Perft Test: {}", fen);
+    }
+}
+
+#[test]
+fn crazyhouse_fen_test() {
+    let variant = Variant::crazyhouse();
+    for fen in [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[] w KQkq - 0 1",
+        "r1bqkbnr/ppp2ppp/2n5/4p3/4P3/5N2/PPP2PPP/RNBQKB1R[PQpp] w KQkq - 0 4",
+        "rnb1kbnr/ppp1pppp/8/3q4/3Q~4/8/PPP1PPPP/RNB1KBNR[p] b KQkq - 0 1",
+    ] {
+        let uci = variant.try_load_fen(fen).unwrap();
+        assert_eq!(uci.board.to_fen(), fen, "FEN round trip");
+    }
+
+    // Boards without pockets can't be given one.
+    assert!(Variant::chess()
+        .try_load_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[P] w KQkq - 0 1")
+        .is_err());
+}
+
+#[test]
+fn crazyhouse_pocket_test() {
+    let variant = Variant::crazyhouse();
+    let mut uci = variant.try_load_uci_pgn("e2e4 d7d5 e4d5").unwrap();
+    assert_eq!(uci.board.get_pocket(0, 0), 1);
+    assert!(!is_draw_by_insufficient_material(&uci.board));
+
+    // Black has no queen in hand.
+    assert!(uci.try_decode("Q@e6").is_err());
+    let action = uci.decode("d8d5".to_string());
+    uci.board.make_move(action);
+    assert_eq!(uci.board.get_pocket(1, 0), 1);
+
+    // Drops read the same in UCI and SAN.
+
+    let drop = uci.decode("P@e6".to_string());
+    assert!(drop.is_drop());
+    assert_eq!(uci.encode(&drop), "P@e6");
+    assert_eq!(uci.encode_san(&drop), "P@e6");
+    assert_eq!(uci.decode_san("@e6"), Some(drop));
+
+    uci.board.make_move(drop);
+    assert_eq!(uci.board.get_pocket(0, 0), 0);
+    assert_eq!(
+        uci.board.to_fen(),
+        "rnb1kbnr/ppp1pppp/4P3/3q4/8/8/PPPP1PPP/RNBQKBNR[p] b KQkq - 0 3"
+    );
+
+    uci.board.undo_move();
+    assert_eq!(uci.board.get_pocket(0, 0), 1);
+    assert_eq!(
+        uci.board.to_fen(),
+        "rnb1kbnr/ppp1pppp/8/3q4/8/8/PPPP1PPP/RNBQKBNR[Pp] w KQkq - 0 3"
+    );
+}
+
+#[test]
+fn crazyhouse_promoted_capture_test() {
+    // The promoted queen goes back to the pocket as a pawn.
+    let variant = Variant::crazyhouse();
+    let mut uci = variant
+        .try_load_fen("rnb1kbnr/ppp1pppp/8/3q4/3Q~4/8/PPP1PPPP/RNB1KBNR[p] b KQkq - 0 1")
+        .unwrap();
+    let action = uci.decode("d5d4".to_string());
+    uci.board.make_move(action);
+    assert_eq!(
+        uci.board.to_fen(),
+        "rnb1kbnr/ppp1pppp/8/8/3q4/8/PPP1PPPP/RNB1KBNR[pp] w KQkq - 0 2"
+    );
+
+    uci.board.undo_move();
+    assert_eq!(uci.board.get_pocket(1, 0), 1);
+    assert_eq!(uci.board.get_pocket(1, 4), 0);
+}
+
+#[test]
+fn crazyhouse_hash_test() {
+    let variant = Variant::crazyhouse();
+    for fen in [
+        "r1bqkbnr/ppp2ppp/2n5/4p3/4P3/5N2/PPP2PPP/RNBQKB1R[Pp] w KQkq - 0 4",
+        "rnb1kbnr/ppp1pppp/8/3q4/3Q~4/8/PPP1PPPP/RNB1KBNR[p] b KQkq - 0 1",
+    ] {
+        let mut uci = variant.try_load_fen(fen).unwrap();
+        check_hashes(&mut uci.board, 2);
+    }
+}
@@ -0,0 +1,120 @@
+use crate::{
+    boards::{is_draw_by_insufficient_material, Board, GameResult},
+    communication::Communicator,
+};
+
+#[test]
+fn halfmove_clock_test() {
+    let mut uci = Board::load_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+
+    for (action, halfmove_clock) in [
+        ("g1f3", 1),
+        ("g8f6", 2),
+        ("e2e4", 0),
+        ("f6e4", 0),
+        ("f3g1", 1),
+    ] {
+        let action = uci.decode(action.to_string());
+        uci.board.make_move(action);
+        assert_eq!(
+            uci.board.halfmove_clock, halfmove_clock,
+            "Halfmove clock after {:?}",
+            action
+        );
+    }
+
+    for halfmove_clock in [0, 0, 2, 1, 0] {
+        uci.board.undo_move();
+        assert_eq!(
+            uci.board.halfmove_clock, halfmove_clock,
+            "Halfmove clock after undo"
+        );
+    }
+}
+
+#[test]
+fn fifty_move_draw() {
+    let mut uci = Board::load_fen("8/8/3k4/8/8/3K4/3R4/8 w - - 100 80");
+    let actions = uci.board.generate_legal_moves();
+    let win_conditions = uci.board.win_conditions.duplicate();
+
+    assert!(matches!(
+        win_conditions.compute(&mut uci.board, &actions),
+        GameResult::Draw
+    ));
+
+    let mut uci = Board::load_fen("8/8/3k4/8/8/3K4/3R4/8 w - - 99 80");
+    let actions = uci.board.generate_legal_moves();
+
+    assert!(matches!(
+        win_conditions.compute(&mut uci.board, &actions),
+        GameResult::Ongoing
+    ));
+}
+
+#[test]
+fn seventy_five_move_draw() {
+    let mut uci = Board::load_fen("8/8/3k4/8/8/3K4/3R4/8 w - - 150 100");
+    let actions = uci.board.generate_legal_moves();
+    let win_conditions = uci.board.win_conditions.duplicate();
+
+    assert!(matches!(
+        win_conditions.compute(&mut uci.board, &actions),
+        GameResult::Draw
+    ));
+
+    // A checkmate on the last move still stands.
+    let mut uci = Board::load_fen("6rk/5Npp/8/8/8/8/8/6K1 b - - 150 100");
+    let actions = uci.board.generate_legal_moves();
+
+    assert!(matches!(
+        win_conditions.compute(&mut uci.board, &actions),
+        GameResult::Lose
+    ));
+}
+
+#[test]
+fn fifty_move_checkmate() {
+    let mut uci = Board::load_fen("6rk/5Npp/8/8/8/8/8/6K1 b - - 100 80");
+    let actions = uci.board.generate_legal_moves();
+    let win_conditions = uci.board.win_conditions.duplicate();
+
+    assert!(matches!(
+        win_conditions.compute(&mut uci.board, &actions),
+        GameResult::Lose
+    ));
+}
+
+#[test]
+fn insufficient_material_draws() {
+    let draws = [
+        "8/8/3k4/8/8/3K4/8/8 w - - 0 1",
+        "8/8/3k4/8/8/3K4/3N4/8 w - - 0 1",
+        "8/8/3k4/8/8/3K4/3B4/8 w - - 0 1",
+        "8/4b3/3k4/8/8/3K4/3B4/8 w - - 0 1",
+    ];
+    let not_draws = [
+        "8/8/3k4/8/8/3K4/3P4/8 w - - 0 1",
+        "8/8/3kn3/8/8/3K4/3B4/8 w - - 0 1",
+        "8/8/3kb3/8/8/3K4/3B4/8 w - - 0 1",
+        "8/8/3kn3/8/8/3K4/3N4/8 w - - 0 1",
+    ];
+
+    for fen in draws {
+        let uci = Board::load_fen(fen);
+        assert!(
+            is_draw_by_insufficient_material(&uci.board),
+            "{} is a draw",
+            fen
+        );
+    }
+
+    for fen in not_draws {
+        let uci = Board::load_fen(fen);
+        assert!(
+            !is_draw_by_insufficient_material(&uci.board),
+            "{} isn't a draw",
+            fen
+        );
+    }
+}
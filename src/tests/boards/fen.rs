@@ -0,0 +1,196 @@
+use crate::{
+    boards::{BishopPiece, Board, BoardLayout, PieceLookup, PieceMapLookup, RookPiece},
+    communication::Communicator,
+    error::LotisaError,
+};
+
+#[test]
+fn fen_round_trip() {
+    let fens = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+    ];
+
+    for fen in fens {
+        let uci = Board::load_fen(fen);
+        assert_eq!(uci.board.to_fen(), fen, "FEN round trip");
+    }
+}
+
+#[test]
+fn fen_default_clocks() {
+    let uci = Board::load_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -");
+
+    assert_eq!(uci.board.halfmove_clock, 0);
+    assert_eq!(uci.board.fullmove_number, 1);
+}
+
+#[test]
+fn fen_after_moves() {
+    let mut uci = Board::load_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+
+    let action = uci.decode("e2e4".to_string());
+    uci.board.make_move(action);
+    assert_eq!(
+        uci.board.to_fen(),
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+    );
+
+    let action = uci.decode("e7e5".to_string());
+    uci.board.make_move(action);
+    let action = uci.decode("e1e2".to_string());
+    uci.board.make_move(action);
+    assert_eq!(
+        uci.board.to_fen(),
+        "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPPKPPP/RNBQ1BNR b kq - 1 2"
+    );
+
+    uci.board.undo_move();
+    uci.board.undo_move();
+    uci.board.undo_move();
+    assert_eq!(
+        uci.board.to_fen(),
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+    );
+}
+
+#[test]
+fn fen_errors() {
+    let invalid_fens = [
+        // Missing the castling and en passant fields
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP w KQkq - 0 1",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNRR w KQkq - 0 1",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNX w KQkq - 0 1",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkx - 0 1",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e9 0 1",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - zero 1",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - -1 1",
+        // En passant squares without a pawn that just double moved past them
+        "k7/8/8/3P4/8/8/8/7K w - e6 0 1",
+        "k7/8/8/8/8/8/3P4/7K w - e3 0 1",
+        "k7/8/4p3/4p3/8/8/8/7K w - e6 0 1",
+        "k7/8/8/8/4p3/8/8/7K w - e5 0 1",
+        // Board sizes whose positions, pieces or zobrist keys don't fit
+        "300x300 300/300/300 w - - 0 1",
+        "190x170 190 w - - 0 1",
+        "120x120:100 120 w - - 0 1",
+        "8x8:6000 8/8/8/8/8/8/8/8 w - - 0 1",
+    ];
+
+    for fen in invalid_fens {
+        assert!(
+            matches!(Board::try_load_fen(fen), Err(LotisaError::InvalidFen(_)))
+                || matches!(Board::try_load_fen(fen), Err(LotisaError::InvalidSquare(_))),
+            "{} is invalid",
+            fen
+        );
+    }
+}
+
+fn capablanca_layout() -> BoardLayout {
+    // The archbishop and chancellor only need letters here, so they move like a bishop and a rook.
+    BoardLayout::new(
+        8,
+        2,
+        (8, 10),
+        Box::new(|row_gap| {
+            Box::new(
+                PieceMapLookup::default_template(
+                    row_gap,
+                    Box::new(move |map| {
+                        map.insert(6, Box::new(BishopPiece::new(row_gap)));
+                        map.insert(7, Box::new(RookPiece::new(row_gap)));
+                    }),
+                )
+                .with_letter(6, 'a')
+                .with_letter(7, 'c'),
+            ) as Box<dyn PieceLookup>
+        }),
+    )
+}
+
+#[test]
+fn variant_fen_pieces() {
+    let fen = "10x8 rnabqkbcnr/pppppppppp/10/10/10/10/PPPPPPPPPP/RNABQKBCNR w KQkq - 0 1";
+    let layout = capablanca_layout();
+
+    let mut uci = Board::try_load_variant_fen(fen, &layout).unwrap();
+    assert_eq!(uci.board.cols, 10);
+    assert_eq!(uci.board.rows, 8);
+    assert_eq!(uci.board.to_fen(), fen, "FEN round trip");
+
+    let archbishop_pos = uci.decode_pos("c1".to_string());
+    let archbishop = uci.board.state[archbishop_pos as usize];
+    assert_eq!(archbishop, uci.board.get_piece_value(6, 0));
+    let chancellor_pos = uci.decode_pos("h8".to_string());
+    let chancellor = uci.board.state[chancellor_pos as usize];
+    assert_eq!(chancellor, uci.board.get_piece_value(7, 1));
+
+    // The layout's size is used when the FEN doesn't have a header.
+    let uci = Board::try_load_variant_fen(&fen["10x8 ".len()..], &layout).unwrap();
+    assert_eq!(uci.board.to_fen(), fen);
+
+    // The default piece set doesn't know the extra letters.
+    assert!(matches!(
+        Board::try_load_fen(fen),
+        Err(LotisaError::InvalidFen(_))
+    ));
+}
+
+#[test]
+fn variant_fen_dimensions() {
+    let fen = "12x12 rnbqkbnrnbqr/pppppppppppp/12/12/12/12/12/12/12/12/PPPPPPPPPPPP/RNBQKBNRNBQR w - - 0 1";
+
+    let mut uci = Board::load_fen(fen);
+    assert_eq!(uci.board.rows, 12);
+    assert_eq!(uci.board.cols, 12);
+    assert_eq!(uci.board.to_fen(), fen, "FEN round trip");
+
+    // 24 pawn moves and 6 knight moves
+    assert_eq!(uci.board.generate_legal_moves().len(), 30);
+
+    let action = uci.decode("l2l4".to_string());
+    uci.board.make_move(action);
+    assert_eq!(
+        uci.board.to_fen(),
+        "12x12 rnbqkbnrnbqr/pppppppppppp/12/12/12/12/12/12/11P/12/PPPPPPPPPPP1/RNBQKBNRNBQR b - l3 0 1"
+    );
+}
+
+#[test]
+fn variant_fen_teams() {
+    let fen = "8x8:4 k(2)6k(3)/8/8/8/8/8/8/K6k 2 - - 0 1";
+
+    let uci = Board::load_fen(fen);
+    assert_eq!(uci.board.teams, 4);
+    assert_eq!(uci.board.moving_team, 2);
+    assert_eq!(uci.board.to_fen(), fen, "FEN round trip");
+
+    let invalid_fens = [
+        // Team 4 doesn't exist with four teams
+        "8x8:4 k(4)7/8/8/8/8/8/8/K6k w - - 0 1",
+        "8x8:4 k(2)7/8/8/8/8/8/8/K6k 4 - - 0 1",
+        "k(2)7/8/8/8/8/8/8/K6k w - - 0 1",
+        "k(2/8/8/8/8/8/8/K6k w - - 0 1",
+        "10x8 rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "0x8 8/8/8/8/8/8/8/8 w - - 0 1",
+        "8x8:x 8/8/8/8/8/8/8/8 w - - 0 1",
+        "rnbqkbnr/pppppppp/08/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    ];
+
+    for fen in invalid_fens {
+        assert!(
+            matches!(Board::try_load_fen(fen), Err(LotisaError::InvalidFen(_))),
+            "{} is invalid",
+            fen
+        );
+    }
+}
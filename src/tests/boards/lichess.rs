@@ -0,0 +1,272 @@
+use crate::{
+    boards::{perft, GameResult, Variant},
+    communication::Communicator,
+};
+
+use super::zobrist::check_hashes;
+
+// Perft results from python-chess's variant perft suites, which shakmaty agrees with.
+fn check_perft(variant: &Variant, positions: &[(&str, i16, u64)]) {
+    for (fen, depth, nodes) in positions {
+        let mut uci = variant.try_load_fen(fen).unwrap();
+        assert_eq!(
+            perft(&mut uci, *depth, None),
+            *nodes,
+            "{} Perft Test: {}",
+            variant.name,
+            fen
+        );
+    }
+}
+
+fn compute_result(variant: &Variant, fen: &str) -> GameResult {
+    let mut uci = variant.try_load_fen(fen).unwrap();
+    let actions = uci.board.generate_legal_moves();
+    let win_conditions = uci.board.win_conditions.duplicate();
+    win_conditions.compute(&mut uci.board, &actions)
+}
+
+#[test]
+fn atomic_perft() {
+    check_perft(
+        &Variant::atomic(),
+        &[
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                4,
+                197326,
+            ),
+            (
+                "rn2kb1r/1pp1p2p/p2q1pp1/3P4/2P3b1/4PN2/PP3PPP/R2QKB1R b KQkq - 0 1",
+                3,
+                45237,
+            ),
+            (
+                "rnb1kbnr/pppp1ppp/8/4p3/4P2q/2N5/PPPP1PPP/R1BQKBNR w KQkq - 0 1",
+                3,
+                30770,
+            ),
+            ("8/8/8/3k4/3K4/8/8/8 w - - 0 1", 3, 397),
+        ],
+    );
+}
+
+#[test]
+fn atomic_explosion_test() {
+    let variant = Variant::atomic();
+    let fen = "rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2";
+    let mut uci = variant.try_load_fen(fen).unwrap();
+
+    // The pawns blow each other up, but the pawns around them are left alone.
+    let action = uci.decode("e4d5".to_string());
+    uci.board.make_move(action);
+    assert_eq!(
+        uci.board.to_fen(),
+        "rnbqkbnr/ppp1pppp/8/8/8/8/PPPP1PPP/RNBQKBNR b KQkq - 0 2"
+    );
+    uci.board.undo_move();
+    assert_eq!(uci.board.to_fen(), fen);
+
+    // Taking on f7 blows up the king next to it, which wins straight away.
+    let fen = "rnbqkbnr/pppp1ppp/8/4p3/2B1P3/8/PPPP1PPP/RNBQK1NR w KQkq - 0 3";
+    let mut uci = variant.try_load_fen(fen).unwrap();
+    let action = uci.decode("c4f7".to_string());
+    uci.board.make_move(action);
+    assert_eq!(
+        uci.board.to_fen(),
+        "rnbq3r/pppp2pp/8/4p3/4P3/8/PPPP1PPP/RNBQK1NR b KQ - 0 3"
+    );
+    assert!(uci.board.generate_legal_moves().is_empty());
+    uci.board.undo_move();
+    assert_eq!(uci.board.to_fen(), fen);
+
+    assert!(matches!(
+        compute_result(
+            &variant,
+            "rnbq3r/pppp2pp/8/4p3/4P3/8/PPPP1PPP/RNBQK1NR b KQ - 0 3"
+        ),
+        GameResult::Lose
+    ));
+
+    let mut uci = variant.try_load_fen(fen).unwrap();
+    check_hashes(&mut uci.board, 2);
+}
+
+#[test]
+fn antichess_perft() {
+    check_perft(
+        &Variant::antichess(),
+        &[
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1",
+                4,
+                153299,
+            ),
+            ("8/1p6/8/8/8/8/P7/8 w - - 0 1", 5, 1),
+            ("8/2P5/8/8/8/8/8/k7 w - - 0 1", 4, 860),
+        ],
+    );
+}
+
+#[test]
+fn antichess_test() {
+    let variant = Variant::antichess();
+
+    // Captures are forced, even for the king.
+    let mut uci = variant
+        .try_load_fen("rnbqkbnr/pppp1ppp/8/4p3/3P4/8/PPP1PPPP/RNBQKBNR w - - 0 2")
+        .unwrap();
+    let actions = uci.board.generate_legal_moves();
+    assert_eq!(actions.len(), 1);
+    assert_eq!(uci.encode(&actions[0]), "d4e5");
+
+    // Running out of pieces (or moves) wins.
+    assert!(matches!(
+        compute_result(&variant, "8/8/8/8/8/8/8/k7 w - - 0 1"),
+        GameResult::Win
+    ));
+    assert!(matches!(
+        compute_result(&variant, "8/8/8/8/8/p7/P7/8 w - - 0 1"),
+        GameResult::Win
+    ));
+}
+
+#[test]
+fn king_of_the_hill_perft() {
+    check_perft(
+        &Variant::king_of_the_hill(),
+        &[
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                4,
+                197281,
+            ),
+            (
+                "rnbq1bnr/ppppkppp/8/4p3/4P3/8/PPPPKPPP/RNBQ1BNR w - - 2 3",
+                3,
+                13337,
+            ),
+            ("8/8/8/2k5/8/8/5K2/8 w - - 0 1", 4, 2454),
+        ],
+    );
+}
+
+#[test]
+fn king_of_the_hill_test() {
+    let variant = Variant::king_of_the_hill();
+
+    // The game is over once a king reaches the hill, even though the other team could move.
+    let mut uci = variant
+        .try_load_fen("8/8/8/2k5/3K4/8/8/8 b - - 0 1")
+        .unwrap();
+    assert!(uci.board.generate_legal_moves().is_empty());
+    assert!(matches!(
+        compute_result(&variant, "8/8/8/2k5/3K4/8/8/8 b - - 0 1"),
+        GameResult::Lose
+    ));
+    assert!(matches!(
+        compute_result(&variant, "8/8/8/2k5/8/8/5K2/8 w - - 0 1"),
+        GameResult::Ongoing
+    ));
+}
+
+#[test]
+fn three_check_perft() {
+    check_perft(
+        &Variant::three_check(),
+        &[
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 3+3 0 1",
+                4,
+                197281,
+            ),
+            (
+                "r1bqkbnr/pppp1ppp/2n5/4p3/2B1P3/5Q2/PPPP1PPP/RNB1K1NR w KQkq - 1+3 0 1",
+                3,
+                49106,
+            ),
+            ("4k3/8/8/8/8/8/8/R3K3 w Q - 1+1 0 1", 4, 6741),
+        ],
+    );
+}
+
+#[test]
+fn three_check_test() {
+    let variant = Variant::three_check();
+    let fen = "rnbqkbnr/ppp2ppp/8/3pp3/4P3/5Q2/PPPP1PPP/RNB1KBNR w KQkq - 1+2 0 3";
+    let mut uci = variant.try_load_fen(fen).unwrap();
+    assert_eq!(uci.board.to_fen(), fen, "FEN round trip");
+
+    // The third check wins, and is given back when the move is undone.
+    let action = uci.decode("f1b5".to_string());
+    uci.board.make_move(action);
+    assert_eq!(uci.board.remaining_checks, Some(vec![0, 2]));
+    assert!(uci.board.generate_legal_moves().is_empty());
+    let win_conditions = uci.board.win_conditions.duplicate();
+    assert!(matches!(
+        win_conditions.compute(&mut uci.board, &vec![]),
+        GameResult::Lose
+    ));
+
+    uci.board.undo_move();
+    assert_eq!(uci.board.to_fen(), fen);
+    check_hashes(&mut uci.board, 3);
+
+    // Checks given can be written at the end instead, like Lichess does.
+    let uci = variant
+        .try_load_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 +2+1")
+        .unwrap();
+    assert_eq!(uci.board.remaining_checks, Some(vec![1, 2]));
+
+    assert!(Variant::chess()
+        .try_load_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 3+3 0 1")
+        .is_err());
+}
+
+#[test]
+fn horde_perft() {
+    check_perft(
+        &Variant::horde(),
+        &[
+            (
+                "rnbqkbnr/pppppppp/8/1PP2PP1/PPPPPPPP/PPPPPPPP/PPPPPPPP/PPPPPPPP w kq - 0 1",
+                5,
+                265223,
+            ),
+            (
+                "4k3/pp4q1/3P2p1/8/P3PP2/PPP2r2/PPP5/PPPP4 b - - 0 1",
+                4,
+                56539,
+            ),
+            (
+                "k7/5p2/4p2P/3p2P1/2p2P2/1p2P2P/p2P2P1/2P2P2 w - - 0 1",
+                4,
+                33781,
+            ),
+        ],
+    );
+}
+
+#[test]
+fn horde_test() {
+    let variant = Variant::horde();
+
+    // Pawns on the first rank can double move, but not be taken en passant afterwards.
+    let mut uci = variant
+        .try_load_fen("4k3/8/8/8/8/1p6/8/2P5 w - - 0 1")
+        .unwrap();
+    let action = uci.decode("c1c3".to_string());
+    uci.board.make_move(action);
+    assert_eq!(uci.board.to_fen(), "4k3/8/8/8/8/1pP5/8/8 b - - 0 1");
+    assert!(uci.try_decode("b3c2").is_err());
+
+    // White has no king, so it only loses once every piece is gone.
+    assert!(matches!(
+        compute_result(&variant, "4k3/8/8/8/8/8/8/8 w - - 0 1"),
+        GameResult::Lose
+    ));
+    assert!(matches!(
+        compute_result(&variant, "4k3/8/8/8/8/8/8/P7 w - - 0 1"),
+        GameResult::Ongoing
+    ));
+}
@@ -1,7 +1,15 @@
 mod bench;
+mod betza;
+mod capablanca;
+mod chess960;
+mod crazyhouse;
+mod draws;
+mod fen;
+mod lichess;
 mod moves;
+mod pawns;
 mod perft;
-
-pub use bench::*;
-pub use moves::*;
-pub use perft::*;
+mod restrictors;
+mod royalty;
+mod variant;
+mod zobrist;
@@ -0,0 +1,190 @@
+use crate::{
+    boards::{Board, Variant},
+    communication::{Communicator, UCICommunicator},
+    error::LotisaError,
+};
+
+// Pawns that move across the board, with white's going right and black's going left.
+const SIDEWAYS_VARIANT: &str = r#"
+name = "sideways"
+startpos = "k7/8/8/8/8/8/8/K7 w - - 0 1"
+castling = false
+
+[[pawns]]
+direction = "right"
+double_move_ranks = [2]
+promotion_ranks = [7, 8]
+promotions = ["q"]
+
+[[pawns]]
+direction = "left"
+double_move_ranks = [2]
+promotion_ranks = [8]
+promotions = ["n"]
+"#;
+
+// Sideways pawns without fixed double move ranks, in a drop variant.
+const SIDEWAYS_DROPS_VARIANT: &str = r#"
+name = "sidewaysdrops"
+startpos = "k7/8/8/8/8/8/8/K7[PP] w - - 0 1"
+castling = false
+drops = true
+
+[[pawns]]
+direction = "right"
+
+[[pawns]]
+direction = "left"
+"#;
+
+fn get_moves_from(uci: &mut UCICommunicator, from: &str) -> Vec<String> {
+    let from = uci.decode_pos(from.to_string());
+    let mut moves = uci
+        .board
+        .generate_legal_moves()
+        .iter()
+        .filter(|action| action.from == from)
+        .map(|action| uci.encode(action))
+        .collect::<Vec<_>>();
+    moves.sort();
+    moves
+}
+
+#[test]
+fn sideways_pawn_test() {
+    let variant = Variant::parse(SIDEWAYS_VARIANT).unwrap();
+
+    let mut uci = variant
+        .try_load_fen("k7/8/8/8/1P6/8/8/K7 w - - 0 1")
+        .unwrap();
+    assert_eq!(get_moves_from(&mut uci, "b4"), vec!["b4c4", "b4d4"]);
+
+    // Captures are diagonally forward, which is up and down the board for these pawns.
+    let mut uci = variant
+        .try_load_fen("k7/8/8/2p5/1P6/2p5/8/K7 w - - 0 1")
+        .unwrap();
+    assert_eq!(
+        get_moves_from(&mut uci, "b4"),
+        vec!["b4c3", "b4c4", "b4c5", "b4d4"]
+    );
+
+    // Black's pawns double move from the g-file, their second rank.
+    let mut uci = variant
+        .try_load_fen("k7/8/8/8/6p1/8/8/K7 b - - 0 1")
+        .unwrap();
+    assert_eq!(get_moves_from(&mut uci, "g4"), vec!["g4e4", "g4f4"]);
+}
+
+#[test]
+fn promotion_zone_test() {
+    let variant = Variant::parse(SIDEWAYS_VARIANT).unwrap();
+
+    // Promoting is optional on the g-file, forced on the h-file, and only to each team's own promotions.
+    let mut uci = variant
+        .try_load_fen("k7/8/8/8/5P2/7n/6Pp/K6n w - - 0 1")
+        .unwrap();
+    assert_eq!(get_moves_from(&mut uci, "f4"), vec!["f4g4", "f4g4q"]);
+    assert_eq!(get_moves_from(&mut uci, "g2"), vec!["g2h1q", "g2h3q"]);
+
+    let mut uci = variant
+        .try_load_fen("k7/8/8/8/1p6/8/8/K7 b - - 0 1")
+        .unwrap();
+    assert_eq!(get_moves_from(&mut uci, "b4"), vec!["b4a4n"]);
+}
+
+#[test]
+fn sideways_en_passant_test() {
+    let variant = Variant::parse(SIDEWAYS_VARIANT).unwrap();
+
+    let mut uci = variant
+        .try_load_fen("k7/8/8/6p1/4P3/8/8/K7 b - - 0 1")
+        .unwrap();
+    let action = uci.decode("g5e5".to_string());
+    uci.board.make_move(action);
+    let fen = uci.board.to_fen();
+    assert_eq!(fen, "k7/8/8/4p3/4P3/8/8/K7 w - f5 0 2");
+    assert_eq!(get_moves_from(&mut uci, "e4"), vec!["e4f4", "e4f5"]);
+
+    // The en passant square is read back from the FEN too.
+    let mut uci = variant.try_load_fen(&fen).unwrap();
+    let action = uci.decode("e4f5".to_string());
+    assert_eq!(action.info, -3, "e4f5 is en passant");
+    uci.board.make_move(action);
+    assert_eq!(uci.board.to_fen(), "k7/8/8/5P2/8/8/8/K7 b - - 0 2");
+}
+
+#[test]
+fn sideways_drop_test() {
+    let variant = Variant::parse(SIDEWAYS_DROPS_VARIANT).unwrap();
+
+    // Pawns dropped on the b-file are on their second rank, so they can still double move.
+    let mut uci = variant.load_startpos();
+    for action in ["P@b4", "a8b8", "P@c2", "b8a8"] {
+        let action = uci.decode(action.to_string());
+        uci.board.make_move(action);
+    }
+    assert_eq!(get_moves_from(&mut uci, "b4"), vec!["b4c4", "b4d4"]);
+    assert_eq!(get_moves_from(&mut uci, "c2"), vec!["c2d2"]);
+
+    // These pawns' back rank and last rank are the a-file and h-file, so the first and last rows are fine to drop on.
+    let mut uci = variant.load_startpos();
+    let drops = uci
+        .board
+        .generate_legal_moves()
+        .iter()
+        .filter(|action| action.is_drop())
+        .map(|action| uci.encode(action))
+        .collect::<Vec<_>>();
+    assert_eq!(drops.len(), 48);
+    for drop in ["P@b1", "P@d8", "P@g1"] {
+        assert!(drops.contains(&drop.to_string()), "{} is a drop", drop);
+    }
+    for drop in ["P@a4", "P@h1", "P@h8"] {
+        assert!(!drops.contains(&drop.to_string()), "{} isn't a drop", drop);
+    }
+}
+
+#[test]
+fn sideways_first_move_test() {
+    // Without double move ranks, pawns loaded from a FEN double move from their own second rank (or back rank).
+    let variant = Variant::parse(SIDEWAYS_DROPS_VARIANT).unwrap();
+    let mut uci = variant
+        .try_load_fen("k7/8/8/8/1P4p1/8/P7/K7 w - - 0 1")
+        .unwrap();
+    assert_eq!(get_moves_from(&mut uci, "b4"), vec!["b4c4", "b4d4"]);
+    assert_eq!(get_moves_from(&mut uci, "a2"), vec!["a2b2", "a2c2"]);
+
+    let action = uci.decode("b4c4".to_string());
+    uci.board.make_move(action);
+    assert_eq!(get_moves_from(&mut uci, "g4"), vec!["g4e4", "g4f4"]);
+}
+
+#[test]
+fn invalid_pawns_test() {
+    let invalid_pawns = [
+        "[[pawns]]\ndouble_move_ranks = [2]",
+        "[[pawns]]\ndirection = \"forward\"",
+        "[[pawns]]\ndirection = \"up\"\npromotion_ranks = [9]",
+        "[[pawns]]\ndirection = \"up\"\npromotions = [\"x\"]",
+        "[[pawns]]\ndirection = \"up\"\nspeed = 2",
+        "pawns = []",
+    ];
+
+    for pawns in invalid_pawns {
+        let variant = format!(
+            "name = \"invalid\"\nstartpos = \"{}\"\n{}",
+            Board::load_fen("k7/8/8/8/8/8/8/K7 w - - 0 1")
+                .board
+                .to_fen(),
+            pawns
+        );
+        assert!(
+            matches!(
+                Variant::parse(&variant),
+                Err(LotisaError::InvalidVariant(_))
+            ),
+            "{} is invalid",
+            pawns
+        );
+    }
+}
@@ -1,4 +1,7 @@
-use crate::boards::{perft, Board};
+use crate::boards::{
+    check_perft_entry, parse_perft_epd, parse_perft_epd_line, perft, perft_divide, Board,
+    PerftSuiteEntry,
+};
 
 #[test]
 fn startpos_perft() {
@@ -28,7 +31,7 @@ fn en_passant_uci_test() {
         This is meant to address a bug where my UCI implementation incorrectly believes the previous move was a double move and allows for en passant.
     */
 
-    let mut uci = Board::load_uci_pgn("1. d2d4 d7d5 2. c2c4 c7c6 3. c1c3 g8f6 4. e2e3 g7g6 5. f1e2 b8d7 6. b2b4 f6e4 7. c3e4 d5e4");
+    let mut uci = Board::load_uci_pgn("1. d2d4 d7d5 2. c2c4 c7c6 3. b1c3 g8f6 4. e2e3 g7g6 5. f1e2 b8d7 6. b2b4 f6e4 7. c3e4 d5e4");
     assert!(
         !uci.board
             .generate_legal_moves()
@@ -55,3 +58,61 @@ fn white_promotion_test() {
 
     assert_eq!(perft(&mut uci, 3, None), 299, "Perft Test (depth = 3)");
 }
+
+#[test]
+fn perft_divide_test() {
+    let mut uci = Board::load_fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1");
+
+    // Perft Results sourced on WebPerft (https://analog-hors.github.io/webperft/)
+
+    let divide = perft_divide(&mut uci, 2);
+    assert_eq!(divide.len(), 14, "Divide has one entry per legal move");
+    assert!(
+        divide.contains(&("b4f4".to_string(), 2)),
+        "b4f4 leads to 2 nodes"
+    );
+    assert!(
+        divide.contains(&("e2e4".to_string(), 16)),
+        "e2e4 leads to 16 nodes"
+    );
+    assert_eq!(divide.iter().map(|(_, nodes)| nodes).sum::<u64>(), 191);
+}
+
+#[test]
+fn perft_epd_parse_test() {
+    let entries = parse_perft_epd(
+        "# comment\n\nrnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 ;D1 20 ;D2 400\n",
+    )
+    .unwrap();
+
+    assert_eq!(
+        entries,
+        vec![PerftSuiteEntry {
+            fen: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_string(),
+            depths: vec![(1, 20), (2, 400)],
+        }]
+    );
+
+    assert!(parse_perft_epd_line("8/8/8/8/8/8/8/8 w - - ;D1").is_err());
+    assert!(parse_perft_epd_line("8/8/8/8/8/8/8/8 w - - ;depth 1 20").is_err());
+}
+
+#[test]
+fn perft_suite_test() {
+    let entries = parse_perft_epd(include_str!("../../../perft/standard.epd")).unwrap();
+
+    for entry in &entries {
+        let mismatches = check_perft_entry(entry, Some(3)).unwrap();
+        assert!(mismatches.is_empty(), "Perft mismatches: {:?}", mismatches);
+    }
+}
+
+#[test]
+fn chess960_perft_suite_test() {
+    let entries = parse_perft_epd(include_str!("../../../perft/chess960.epd")).unwrap();
+
+    for entry in &entries {
+        let mismatches = check_perft_entry(entry, Some(3)).unwrap();
+        assert!(mismatches.is_empty(), "Perft mismatches: {:?}", mismatches);
+    }
+}
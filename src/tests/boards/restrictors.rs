@@ -0,0 +1,196 @@
+use crate::{
+    boards::{perft, GameResult, Variant},
+    communication::{Communicator, UCICommunicator},
+};
+
+const STARTPOS: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+fn create_variant(restrictors: &str, win_conditions: &str) -> Variant {
+    Variant::parse(&format!(
+        "name = \"restricted\"\nstartpos = \"{}\"\nwin_conditions = \"{}\"\nrestrictors = {}",
+        STARTPOS, win_conditions, restrictors
+    ))
+    .unwrap()
+}
+
+fn get_legal_moves(uci: &mut UCICommunicator) -> Vec<String> {
+    let mut moves = uci
+        .board
+        .generate_legal_moves()
+        .iter()
+        .map(|action| uci.encode(action))
+        .collect::<Vec<_>>();
+    moves.sort();
+    moves
+}
+
+fn play(uci: &mut UCICommunicator, moves: &[&str]) {
+    for action in moves {
+        let action = uci.decode(action.to_string());
+        uci.board.make_move(action);
+    }
+}
+
+fn compute_result(uci: &mut UCICommunicator) -> GameResult {
+    let actions = uci.board.generate_legal_moves();
+    let win_conditions = uci.board.win_conditions.duplicate();
+    win_conditions.compute(&mut uci.board, &actions)
+}
+
+#[test]
+fn forced_capture_test() {
+    let variant = create_variant("[\"default\", \"captures\"]", "checkmate");
+    let mut uci = variant.load_startpos();
+    assert_eq!(perft(&mut uci, 2, None), 400);
+
+    // En passant is the only capture, and it's only there right after the double move.
+    let mut uci = variant
+        .try_load_fen("k7/3p4/8/4P3/8/8/8/K7 b - - 0 1")
+        .unwrap();
+    play(&mut uci, &["d7d5"]);
+    assert_eq!(get_legal_moves(&mut uci), vec!["e5d6"]);
+
+    uci.board.undo_move();
+    play(&mut uci, &["d7d6"]);
+    assert_eq!(get_legal_moves(&mut uci), vec!["e5d6"]);
+
+    uci.board.undo_move();
+    play(&mut uci, &["a8b8"]);
+    assert_eq!(get_legal_moves(&mut uci).len(), 4);
+}
+
+#[test]
+fn forced_legal_capture_test() {
+    let variant = create_variant("[\"default\", \"captures\"]", "checkmate");
+
+    // The rook's only capture would leave the king in check from the bishop, so it doesn't force anything.
+    let mut uci = variant
+        .try_load_fen("k7/8/8/8/1b6/8/p2R4/4K3 w - - 0 1")
+        .unwrap();
+    let moves = get_legal_moves(&mut uci);
+    assert!(!moves.contains(&"d2a2".to_string()));
+    assert!(moves.contains(&"e1e2".to_string()));
+
+    // Castling is written as capturing the rook, but it isn't a capture.
+    let mut uci = variant
+        .try_load_fen("k7/8/8/8/8/8/8/4K2R w K - 0 1")
+        .unwrap();
+    let moves = get_legal_moves(&mut uci);
+    assert!(moves.contains(&"e1g1".to_string()));
+    assert!(moves.contains(&"h1h8".to_string()));
+}
+
+#[test]
+fn no_check_test() {
+    // The only checks in the first three plies are the 12 queen and bishop checks on the third.
+    let variant = create_variant("[\"default\", \"nocheck\"]", "checkmate");
+    let mut uci = variant.load_startpos();
+    assert_eq!(perft(&mut uci, 3, None), 8890);
+
+    let mut uci = variant
+        .try_load_fen("k7/8/8/8/8/8/8/KR6 w - - 0 1")
+        .unwrap();
+    let moves = get_legal_moves(&mut uci);
+    assert!(moves.contains(&"b1b7".to_string()));
+    assert!(!moves.contains(&"b1b8".to_string()));
+}
+
+#[test]
+fn king_proximity_test() {
+    let variant = create_variant("[\"kingcapture\", \"kingsapart\"]", "kingcapture");
+    let mut uci = variant
+        .try_load_fen("8/8/8/3k4/8/3K4/8/8 w - - 0 1")
+        .unwrap();
+    assert_eq!(
+        get_legal_moves(&mut uci),
+        vec!["d3c2", "d3c3", "d3d2", "d3e2", "d3e3"]
+    );
+}
+
+#[test]
+fn palace_test() {
+    let variant = create_variant("[\"default\", \"palace\"]", "checkmate");
+
+    // Each team's palace is on its own side of the board.
+    let mut uci = variant
+        .try_load_fen("8/8/4k3/8/8/4K3/8/8 w - - 0 1")
+        .unwrap();
+    assert_eq!(get_legal_moves(&mut uci), vec!["e3d2", "e3d3", "e3e2"]);
+
+    play(&mut uci, &["e3e2"]);
+    assert_eq!(get_legal_moves(&mut uci), vec!["e6d6", "e6d7", "e6e7"]);
+
+    // Castling is kept in by where the king ends up, while the rooks can go anywhere.
+    let mut uci = variant
+        .try_load_fen("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1")
+        .unwrap();
+    let moves = get_legal_moves(&mut uci);
+    assert!(moves.contains(&"e1c1".to_string()));
+    assert!(!moves.contains(&"e1g1".to_string()));
+    assert!(moves.contains(&"h1h8".to_string()));
+}
+
+#[test]
+fn sideways_palace_test() {
+    // White's pawns move right and black's move left, so their palaces are on the left and right edges.
+    let variant = Variant::parse(
+        r#"
+name = "sidewayspalace"
+startpos = "k7/8/8/8/8/8/8/K7 w - - 0 1"
+castling = false
+restrictors = ["default", "palace"]
+
+[[pawns]]
+direction = "right"
+
+[[pawns]]
+direction = "left"
+"#,
+    )
+    .unwrap();
+    let mut uci = variant
+        .try_load_fen("8/8/8/8/2K2k2/8/8/8 w - - 0 1")
+        .unwrap();
+    assert_eq!(
+        get_legal_moves(&mut uci),
+        vec!["c4b3", "c4b4", "c4b5", "c4c3", "c4c5"]
+    );
+
+    play(&mut uci, &["c4b4"]);
+    assert_eq!(
+        get_legal_moves(&mut uci),
+        vec!["f4f3", "f4f5", "f4g3", "f4g4", "f4g5"]
+    );
+}
+
+#[test]
+fn no_repetition_test() {
+    let variant = create_variant("[\"default\", \"norepetition\"]", "checkmate");
+    let mut uci = variant
+        .try_load_fen("k7/7r/8/8/8/8/7R/K7 w - - 0 1")
+        .unwrap();
+    play(&mut uci, &["a1b1", "a8b8", "b1a1"]);
+
+    let moves = get_legal_moves(&mut uci);
+    assert!(!moves.contains(&"b8a8".to_string()));
+    assert!(moves.contains(&"b8c8".to_string()));
+    assert!(moves.contains(&"h7h2".to_string()));
+}
+
+#[test]
+fn king_capture_test() {
+    let variant = create_variant("[\"kingcapture\"]", "kingcapture");
+    let mut uci = variant.load_startpos();
+    assert_eq!(perft(&mut uci, 3, None), 8902);
+
+    // Checks can be ignored, but losing the king loses the game.
+    let mut uci = variant
+        .try_load_fen("k7/8/8/8/8/8/8/K6r w - - 0 1")
+        .unwrap();
+    assert_eq!(get_legal_moves(&mut uci), vec!["a1a2", "a1b1", "a1b2"]);
+    assert!(matches!(compute_result(&mut uci), GameResult::Ongoing));
+
+    play(&mut uci, &["a1b1", "h1b1"]);
+    assert!(matches!(compute_result(&mut uci), GameResult::Lose));
+    assert!(uci.board.generate_legal_moves().is_empty());
+}
@@ -0,0 +1,145 @@
+use crate::{
+    boards::{in_check, perft, Board, GameResult, Variant},
+    communication::{Communicator, UCICommunicator},
+    engine::evaluate,
+    error::LotisaError,
+};
+
+// Spartan chess's duple check, without the Spartan pieces.
+const DUPLE_CHECK_VARIANT: &str = r#"
+name = "duple"
+startpos = "k7/8/8/8/8/8/8/K6K w - - 0 1"
+castling = false
+duple_check = true
+"#;
+
+// A queen that has to be protected like a king, so pawns can't promote to it.
+const ROYAL_QUEEN_VARIANT: &str = r#"
+name = "royalqueen"
+startpos = "k7/8/8/8/8/8/8/K2Q4 w - - 0 1"
+castling = false
+royal = ["k", "q"]
+"#;
+
+fn is_in_check(uci: &mut UCICommunicator) -> bool {
+    let moving_team = uci.board.moving_team;
+    let row_gap = uci.board.row_gap;
+    in_check(&mut uci.board, moving_team, row_gap)
+}
+
+fn get_legal_moves(uci: &mut UCICommunicator) -> Vec<String> {
+    let mut moves = uci
+        .board
+        .generate_legal_moves()
+        .iter()
+        .map(|action| uci.encode(action))
+        .collect::<Vec<_>>();
+    moves.sort();
+    moves
+}
+
+fn compute_result(uci: &mut UCICommunicator) -> GameResult {
+    let actions = uci.board.generate_legal_moves();
+    let win_conditions = uci.board.win_conditions.duplicate();
+    win_conditions.compute(&mut uci.board, &actions)
+}
+
+#[test]
+fn no_king_test() {
+    let mut uci = Board::load_fen("8/8/8/8/8/8/PPPPPPPP/8 w - - 0 1");
+    assert!(!is_in_check(&mut uci));
+    assert_eq!(uci.board.generate_legal_moves().len(), 16);
+    assert!(evaluate(&mut uci.board, 0) > 0);
+}
+
+#[test]
+fn multiple_royals_test() {
+    // With two kings, either one being attacked is check.
+    let mut uci = Board::load_fen("k6r/8/8/8/8/8/8/K6K w - - 0 1");
+    assert!(is_in_check(&mut uci));
+
+    // With duple check, one of them can be left attacked.
+    let variant = Variant::parse(DUPLE_CHECK_VARIANT).unwrap();
+    let mut uci = variant
+        .try_load_fen("k6r/8/8/8/8/8/8/K6K w - - 0 1")
+        .unwrap();
+    assert!(!is_in_check(&mut uci));
+    assert!(get_legal_moves(&mut uci).contains(&"a1a2".to_string()));
+
+    let mut uci = variant
+        .try_load_fen("k7/8/8/8/8/8/8/K5rK w - - 0 1")
+        .unwrap();
+    assert!(is_in_check(&mut uci));
+}
+
+#[test]
+fn royal_piece_test() {
+    let variant = Variant::parse(ROYAL_QUEEN_VARIANT).unwrap();
+
+    let mut uci = variant
+        .try_load_fen("k7/8/8/8/8/8/8/K2Q3r w - - 0 1")
+        .unwrap();
+    assert!(is_in_check(&mut uci), "The queen is attacked");
+
+    // Royal pieces can't be captured, or promoted to.
+    let mut uci = variant
+        .try_load_fen("k7/8/8/8/8/8/3q3R/K7 w - - 0 1")
+        .unwrap();
+    assert!(!get_legal_moves(&mut uci).contains(&"h2d2".to_string()));
+
+    let mut uci = variant
+        .try_load_fen("k7/4P3/8/8/8/8/8/K2Q4 w - - 0 1")
+        .unwrap();
+    let promotions = get_legal_moves(&mut uci)
+        .into_iter()
+        .filter(|action| action.starts_with("e7e8"))
+        .collect::<Vec<_>>();
+    assert_eq!(promotions, vec!["e7e8b", "e7e8n", "e7e8r"]);
+}
+
+#[test]
+fn extinction_test() {
+    let variant = Variant::extinction();
+    let mut uci = variant.load_startpos();
+    assert_eq!(perft(&mut uci, 3, None), 8902);
+
+    // Kings can be captured, and losing the last piece of any type loses the game.
+    let mut uci = variant
+        .try_load_fen("krbqn3/p7/8/3Q4/8/8/P7/KRB1N3 w - - 0 1")
+        .unwrap();
+    assert!(get_legal_moves(&mut uci).contains(&"d5a8".to_string()));
+    assert!(matches!(compute_result(&mut uci), GameResult::Ongoing));
+
+    for capture in ["d5a8", "d5d8"] {
+        let action = uci.decode(capture.to_string());
+        uci.board.make_move(action);
+        assert!(matches!(compute_result(&mut uci), GameResult::Lose));
+        assert!(uci.board.generate_legal_moves().is_empty());
+        uci.board.undo_move();
+    }
+}
+
+#[test]
+fn invalid_royalty_test() {
+    let invalid_royalties = [
+        "royal = [\"x\"]",
+        "royal = \"k\"",
+        "extinction = [\"k\"]",
+        "duple_check = 1",
+    ];
+
+    for royalty in invalid_royalties {
+        let variant = format!(
+            "name = \"invalid\"\nstartpos = \"k7/8/8/8/8/8/8/K7 w - - 0 1\"\n{}",
+            royalty
+        );
+        assert!(
+            matches!(
+                Variant::parse(&variant),
+                Err(LotisaError::InvalidVariant(_))
+            ),
+            "{} is invalid",
+            royalty
+        );
+    }
+}
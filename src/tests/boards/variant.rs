@@ -0,0 +1,129 @@
+use crate::{
+    boards::{perft, Variant},
+    communication::Communicator,
+    error::LotisaError,
+};
+
+const CAPABLANCA_VARIANT: &str = r#"
+name = "capablanca"
+rows = 8
+cols = 10
+startpos = "rnabqkbcnr/pppppppppp/10/10/10/10/PPPPPPPPPP/RNABQKBCNR w KQkq - 0 1"
+promotions = ["n", "b", "r", "q", "a", "c"]
+
+[[pieces]]
+letter = "a"
+betza = "BN"
+value = 8000
+
+[[pieces]]
+letter = "c"
+betza = "RN"
+value = 9000
+"#;
+
+#[test]
+fn chess_variant_test() {
+    let chess = Variant::chess();
+    assert_eq!(chess.name, "chess");
+
+    let mut uci = chess.load_startpos();
+    assert_eq!(uci.board.to_fen(), chess.startpos);
+    assert_eq!(perft(&mut uci, 3, None), 8902);
+}
+
+#[test]
+fn custom_variant_test() {
+    let capablanca = Variant::parse(CAPABLANCA_VARIANT).unwrap();
+    assert_eq!(capablanca.layout.piece_types, 8);
+
+    let mut uci = capablanca.load_startpos();
+    assert_eq!(uci.board.cols, 10);
+    assert_eq!(uci.board.to_fen(), format!("10x8 {}", capablanca.startpos));
+
+    let archbishop_pos = uci.decode_pos("c1".to_string());
+    assert_eq!(
+        uci.board.state[archbishop_pos as usize],
+        uci.board.get_piece_value(6, 0)
+    );
+    assert_eq!(perft(&mut uci, 2, None), 784);
+
+    // Pawns only promote to the listed pieces, which include the archbishop and chancellor.
+    let mut uci = capablanca
+        .try_load_fen("4k5/P9/10/10/10/10/10/4K5 w - - 0 1")
+        .unwrap();
+    let promotions = uci
+        .board
+        .generate_legal_moves()
+        .into_iter()
+        .filter(|action| action.piece_type == 0)
+        .count();
+    assert_eq!(promotions, 6);
+    assert!(uci.try_decode("a7a8a").is_ok());
+    assert!(uci.try_decode("a7a8c").is_ok());
+}
+
+#[test]
+fn variant_error_test() {
+    let invalid_variants = [
+        // Missing name
+        r#"startpos = "4k3/8/8/8/8/8/8/4K3 w - - 0 1""#,
+        // Unknown key
+        r#"name = "x"
+startpos = "4k3/8/8/8/8/8/8/4K3 w - - 0 1"
+gravity = true"#,
+        // Unknown promotion
+        r#"name = "x"
+startpos = "4k3/8/8/8/8/8/8/4K3 w - - 0 1"
+promotions = ["z"]"#,
+        // Bad Betza string
+        r#"name = "x"
+startpos = "4k3/8/8/8/8/8/8/4K3 w - - 0 1"
+[[pieces]]
+letter = "z"
+betza = "sF""#,
+        // Unknown named piece
+        r#"name = "x"
+startpos = "4k3/8/8/8/8/8/8/4K3 w - - 0 1"
+[[pieces]]
+letter = "z"
+piece = "dragon""#,
+        // The starting position doesn't fit the board
+        r#"name = "x"
+cols = 10
+startpos = "4k3/8/8/8/8/8/8/4K3 w - - 0 1""#,
+        "not toml",
+    ];
+
+    for contents in invalid_variants {
+        assert!(
+            matches!(
+                Variant::parse(contents),
+                Err(LotisaError::InvalidVariant(_))
+                    | Err(LotisaError::InvalidPiece(_))
+                    | Err(LotisaError::InvalidFen(_))
+            ),
+            "{} is invalid",
+            contents
+        );
+    }
+}
+
+#[test]
+fn variant_castling_rights_test() {
+    let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+
+    // Extinction kings aren't royal, but they still castle.
+    let extinction = Variant::extinction();
+    assert_eq!(extinction.try_load_fen(fen).unwrap().board.to_fen(), fen);
+
+    // Kings that can't castle don't have any castling rights to keep.
+    let no_castling = Variant::parse(
+        "name = \"nocastling\"\nstartpos = \"4k3/8/8/8/8/8/8/4K3 w - - 0 1\"\ncastling = false",
+    )
+    .unwrap();
+    assert_eq!(
+        no_castling.try_load_fen(fen).unwrap().board.to_fen(),
+        "r3k2r/8/8/8/8/8/8/R3K2R w - - 0 1"
+    );
+}
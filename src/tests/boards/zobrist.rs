@@ -0,0 +1,138 @@
+use crate::{
+    boards::{
+        hash_board, is_draw_by_repetition, is_polyglot_board, polyglot_hash, Board, ZobristKeys,
+    },
+    communication::Communicator,
+};
+
+pub fn check_hashes(board: &mut Board, depth: i16) {
+    let hash = board.hash;
+    assert_eq!(
+        hash,
+        hash_board(board, board.moving_team, &board.zobrist),
+        "Incremental hash doesn't match a full rehash"
+    );
+
+    if depth == 0 {
+        return;
+    }
+
+    for action in board.generate_legal_moves() {
+        board.make_move(action);
+        check_hashes(board, depth - 1);
+        board.undo_move();
+        assert_eq!(
+            board.hash, hash,
+            "Hash wasn't restored after undoing {:?}",
+            action
+        );
+    }
+}
+
+#[test]
+fn incremental_hash_test() {
+    for fen in [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+        "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+    ] {
+        let mut uci = Board::load_fen(fen);
+        check_hashes(&mut uci.board, 3);
+    }
+}
+
+#[test]
+fn repetition_test() {
+    let mut uci = Board::load_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+
+    for action in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+        assert!(!is_draw_by_repetition(&uci.board));
+        let action = uci.decode(action.to_string());
+        uci.board.make_move(action);
+    }
+
+    // The knights have lost their first move, so this isn't quite the starting position.
+    assert!(!is_draw_by_repetition(&uci.board));
+
+    for action in ["g1f3", "g8f6"] {
+        let action = uci.decode(action.to_string());
+        uci.board.make_move(action);
+    }
+    assert!(is_draw_by_repetition(&uci.board));
+
+    let action = uci.decode("e2e4".to_string());
+    uci.board.make_move(action);
+    assert!(!is_draw_by_repetition(&uci.board));
+}
+
+#[test]
+fn deterministic_zobrist_test() {
+    let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    let first = Board::load_fen(fen);
+    let second = Board::load_fen(fen);
+    assert_eq!(first.board.zobrist, second.board.zobrist);
+    assert_eq!(first.board.hash, second.board.hash);
+
+    let seeded = Board::load_fen(fen)
+        .board
+        .with_zobrist(ZobristKeys::Seed(1));
+    assert_ne!(seeded.zobrist, first.board.zobrist);
+    assert_ne!(seeded.hash, first.board.hash);
+
+    let table = Board::load_fen(fen)
+        .board
+        .with_zobrist(ZobristKeys::Table(seeded.zobrist.clone()));
+    assert_eq!(table.hash, seeded.hash);
+}
+
+#[test]
+fn polyglot_hash_test() {
+    let mut uci = Board::load_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    assert!(is_polyglot_board(&uci.board));
+    assert_eq!(polyglot_hash(&uci.board), 0x463b96181691fc9c);
+    assert!(!is_polyglot_board(
+        &Board::load_fen("10x8 4k5/10/10/10/10/10/10/4K5 w - - 0 1").board
+    ));
+
+    // Test positions from the Polyglot book format specification.
+    for (action, hash) in [
+        ("e2e4", 0x823c9b50fd114196),
+        ("d7d5", 0x0756b94461c50fb0),
+        ("e4e5", 0x662fafb965db29d4),
+        ("f7f5", 0x22a48b5a8e47ff78),
+        ("e1e2", 0x652a607ca3f242c1),
+        ("e8f7", 0x00fdd303c946bdd9),
+    ] {
+        let action = uci.decode(action.to_string());
+        uci.board.make_move(action);
+        assert_eq!(
+            polyglot_hash(&uci.board),
+            hash,
+            "Polyglot hash after {:?}",
+            action
+        );
+    }
+
+    let mut uci = Board::load_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    for (action, hash) in [
+        ("a2a4", None),
+        ("b7b5", None),
+        ("h2h4", None),
+        ("b5b4", None),
+        ("c2c4", Some(0x3c8123ea7b067637)),
+        ("b4c3", None),
+        ("a1a3", Some(0x5c3f9b829b279560)),
+    ] {
+        let action = uci.decode(action.to_string());
+        uci.board.make_move(action);
+        if let Some(hash) = hash {
+            assert_eq!(
+                polyglot_hash(&uci.board),
+                hash,
+                "Polyglot hash after {:?}",
+                action
+            );
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use std::sync::Arc;
+
+use crate::{
+    boards::Variant,
+    cli::{
+        create_info, parse_go, parse_position, parse_setoption, set_option, UCIState, MAX_MULTI_PV,
+    },
+    engine::{SearchOptions, SearchSignals},
+};
+
+fn tokenize(line: &str) -> Vec<&str> {
+    line.split_whitespace().collect()
+}
+
+#[test]
+fn position_command_test() {
+    let chess = Variant::chess();
+    let uci = parse_position(&tokenize("position startpos"), &chess, false).unwrap();
+    assert_eq!(
+        uci.board.to_fen(),
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+    );
+
+    let uci = parse_position(
+        &tokenize("position startpos moves e2e4 e7e5 g1f3"),
+        &chess,
+        false,
+    )
+    .unwrap();
+    assert_eq!(
+        uci.board.to_fen(),
+        "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"
+    );
+
+    let uci = parse_position(
+        &tokenize("position fen 4k3/8/8/8/8/8/4P3/4K3 w - - 5 40 moves e2e4"),
+        &chess,
+        false,
+    )
+    .unwrap();
+    assert_eq!(uci.board.to_fen(), "4k3/8/8/8/4P3/8/8/4K3 b - e3 0 40");
+
+    assert!(parse_position(&tokenize("position"), &chess, false).is_err());
+
+    // With UCI_Chess960, castling is the king taking its own rook.
+    let command = "position fen r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1 moves e1h1";
+    let uci = parse_position(&tokenize(command), &chess, true).unwrap();
+    assert_eq!(uci.board.to_fen(), "r3k2r/8/8/8/8/8/8/R4RK1 b kq - 1 1");
+    assert!(parse_position(&tokenize(command), &chess, false).is_err());
+}
+
+#[test]
+fn position_command_error_test() {
+    let chess = Variant::chess();
+    let invalid_commands = [
+        "position fen 4k3/8/8/8/8/8/4P3/4K3",
+        "position fen 4k3/8/8/8/8/8/4P3/4K3 w - - 0 1 moves e2e5",
+        "position startpos moves e2e4 e2e4",
+        "position startpos moves garbage",
+        "position startpos moves e7e8q",
+    ];
+
+    for command in invalid_commands {
+        assert!(
+            parse_position(&tokenize(command), &chess, false).is_err(),
+            "{} is invalid",
+            command
+        );
+    }
+}
+
+#[test]
+fn go_command_test() {
+    let (limits, ponder) = parse_go(&tokenize("go depth 7"), 0);
+    assert_eq!(limits.depth, Some(7));
+    assert_eq!(limits.move_time, None);
+    assert!(!ponder);
+
+    let (limits, _) = parse_go(&tokenize("go nodes 10000 mate 3"), 0);
+    assert_eq!(limits.nodes, Some(10000));
+    assert_eq!(limits.mate, Some(3));
+
+    let (limits, _) = parse_go(&tokenize("go movetime 250"), 1);
+    assert_eq!(limits.move_time, Some(250));
+
+    let (limits, _) = parse_go(&tokenize("go infinite"), 0);
+    assert!(limits.infinite);
+    assert_eq!(limits.move_time, None);
+
+    // Only the clock of the moving team is used.
+    let line = "go wtime 30000 btime 60000 winc 0 binc 1000 movestogo 10";
+    let (limits, _) = parse_go(&tokenize(line), 0);
+    assert_eq!(limits.time, Some(30000));
+    assert_eq!(limits.inc, Some(0));
+    assert_eq!(limits.moves_to_go, Some(10));
+    let (limits, _) = parse_go(&tokenize(line), 1);
+    assert_eq!(limits.time, Some(60000));
+    assert_eq!(limits.inc, Some(1000));
+
+    let (limits, ponder) = parse_go(&tokenize("go ponder wtime 1000 btime 1000"), 0);
+    assert!(ponder);
+    assert_eq!(limits.time, Some(1000));
+
+    assert!(parse_go(&tokenize("go"), 0).0.move_time.is_some());
+}
+
+#[test]
+fn setoption_command_test() {
+    assert_eq!(
+        parse_setoption(&tokenize("setoption name Hash value 64")),
+        Some(("Hash".to_string(), "64".to_string()))
+    );
+    assert_eq!(
+        parse_setoption(&tokenize("setoption name Clear Hash")),
+        Some(("Clear Hash".to_string(), String::new()))
+    );
+    assert_eq!(
+        parse_setoption(&tokenize("setoption name Move Overhead value 10")),
+        Some(("Move Overhead".to_string(), "10".to_string()))
+    );
+    assert!(parse_setoption(&tokenize("setoption")).is_none());
+}
+
+#[test]
+fn set_option_test() {
+    let mut state = UCIState::new(
+        vec![Variant::chess()],
+        create_info(SearchOptions::default(), Arc::new(SearchSignals::default())),
+    );
+
+    assert!(set_option(&mut state, "MultiPV", "3"));
+    assert_eq!(state.info.options.multi_pv, 3);
+    assert!(set_option(&mut state, "MultiPV", "1000"));
+    assert_eq!(state.info.options.multi_pv, MAX_MULTI_PV);
+
+    assert!(set_option(&mut state, "Ponder", "true"));
+    assert!(state.info.options.ponder);
+
+    assert!(set_option(&mut state, "Move Overhead", "100"));
+    assert_eq!(state.info.options.move_overhead, 100);
+
+    let buckets = state.info.transposition_table.buckets.len();
+    assert!(set_option(&mut state, "Hash", "1"));
+    assert_eq!(state.info.options.hash_mb, 1);
+    assert!(state.info.transposition_table.buckets.len() < buckets);
+
+    assert!(set_option(&mut state, "UCI_Chess960", "true"));
+    assert!(state.uci.board.chess960);
+    assert!(set_option(&mut state, "UCI_Variant", "chess"));
+    assert!(state.uci.board.chess960);
+    assert!(!set_option(&mut state, "UCI_Variant", "shogi"));
+    assert!(!set_option(&mut state, "UCI_Variant", "missing.toml"));
+    assert_eq!(state.variants.len(), 1);
+    assert!(!set_option(&mut state, "MultiPV", "many"));
+    assert!(!set_option(&mut state, "Contempt", "10"));
+}
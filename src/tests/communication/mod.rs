@@ -0,0 +1,3 @@
+mod pgn;
+mod san;
+mod uci;
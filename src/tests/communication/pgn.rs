@@ -0,0 +1,146 @@
+use crate::{
+    communication::{format_eval, parse_pgn, PgnGame},
+    engine::{MAX_VALUE, MIN_VALUE},
+};
+
+const GAME: &str = r#"[Event "Casual \"Game\""]
+[Site "?"]
+[Result "1-0"]
+
+{Opening comment} 1. e4 e5 2. Nf3 (2. f4 exf4 (2... d5!? 3. exd5) 3. Nf3 $1) 2... Nc6 3. Bc4!
+{Italian} Bc5 4. O-O Nf6?? ; rest of line comment
+5. d3 {[%eval 0.25] solid} 1-0
+"#;
+
+#[test]
+fn pgn_parse_test() {
+    let game = PgnGame::parse(GAME).unwrap();
+
+    assert_eq!(game.get_tag("Event"), Some("Casual \"Game\""));
+    assert_eq!(game.get_tag("Result"), Some("1-0"));
+    assert_eq!(game.result, "1-0");
+
+    let mainline = game
+        .moves
+        .iter()
+        .map(|pgn_move| pgn_move.san.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        mainline,
+        vec!["e4", "e5", "Nf3", "Nc6", "Bc4", "Bc5", "O-O", "Nf6", "d3"]
+    );
+
+    assert_eq!(
+        game.moves[0].comment_before.as_deref(),
+        Some("Opening comment")
+    );
+    assert_eq!(game.moves[4].nags, vec![1]);
+    assert_eq!(game.moves[4].comment.as_deref(), Some("Italian"));
+    assert_eq!(game.moves[7].nags, vec![4]);
+    assert_eq!(
+        game.moves[7].comment.as_deref(),
+        Some("rest of line comment")
+    );
+    assert_eq!(game.moves[8].comment.as_deref(), Some("solid"));
+    assert_eq!(game.moves[8].eval, Some(250));
+
+    // 2. f4 replaces 2. Nf3, and 2... d5 replaces 2... exf4 inside it.
+    let variation = &game.moves[2].variations[0];
+    let variation_moves = variation
+        .iter()
+        .map(|pgn_move| pgn_move.san.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(variation_moves, vec!["f4", "exf4", "Nf3"]);
+    assert_eq!(variation[2].nags, vec![1]);
+
+    let nested = &variation[1].variations[0];
+    assert_eq!(nested[0].san, "d5");
+    assert_eq!(nested[0].nags, vec![5]);
+    assert_eq!(nested[1].san, "exd5");
+
+    assert_eq!(
+        game.final_position().unwrap().board.to_fen(),
+        "r1bqk2r/pppp1ppp/2n2n2/2b1p3/2B1P3/3P1N2/PPP2PPP/RNBQ1RK1 b kq - 0 5"
+    );
+}
+
+#[test]
+fn pgn_write_test() {
+    let game = PgnGame::parse(GAME).unwrap();
+
+    assert_eq!(
+        game.to_pgn(),
+        r#"[Event "Casual \"Game\""]
+[Site "?"]
+[Result "1-0"]
+
+{Opening comment} 1. e4 e5 2. Nf3 (2. f4 exf4 (2... d5 $5 3. exd5) 3. Nf3 $1)
+2... Nc6 3. Bc4 $1 {Italian} 3... Bc5 4. O-O Nf6 $4 {rest of line comment}
+5. d3 {[%eval 0.25] solid} 1-0
+"#
+    );
+
+    // Writing a game and reading it back gives the same game.
+    assert_eq!(PgnGame::parse(&game.to_pgn()).unwrap(), game);
+}
+
+#[test]
+fn pgn_fen_tag_test() {
+    let mut game =
+        PgnGame::parse("[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 12\"]\n\n12... Kd7 13. e4 Kc6 *")
+            .unwrap();
+
+    assert_eq!(game.moves.len(), 3);
+    assert_eq!(game.result, "*");
+
+    game.moves[0].eval = Some(MAX_VALUE - 3);
+    game.moves[1].eval = Some(-1500);
+    assert_eq!(
+        game.to_pgn(),
+        "[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 0 12\"]\n\n12... Kd7 {[%eval #2]} 13. e4 {[%eval -1.50]} 13... Kc6 *\n"
+    );
+}
+
+#[test]
+fn pgn_multiple_games_test() {
+    let games = parse_pgn("1. e4 e5 1-0\n\n[White \"Lotisa\"]\n\n1. d4 d5 2. c4 *").unwrap();
+
+    assert_eq!(games.len(), 2);
+    assert_eq!(games[0].moves.len(), 2);
+    assert_eq!(games[0].result, "1-0");
+    assert_eq!(games[1].get_tag("White"), Some("Lotisa"));
+    assert_eq!(games[1].moves.len(), 3);
+}
+
+#[test]
+fn pgn_error_test() {
+    assert!(PgnGame::parse("1. e4 e4 *").is_err(), "Illegal move");
+    assert!(
+        PgnGame::parse("1. e4 (1. d4 *").is_err(),
+        "Unterminated variation"
+    );
+    assert!(
+        PgnGame::parse("1. e4 ) *").is_err(),
+        "Unexpected end of variation"
+    );
+    assert!(
+        PgnGame::parse("1. e4 {unterminated").is_err(),
+        "Unterminated comment"
+    );
+    assert!(
+        PgnGame::parse("[Event unquoted]\n\n*").is_err(),
+        "Unquoted tag"
+    );
+}
+
+#[test]
+fn pgn_eval_test() {
+    assert_eq!(format_eval(250), "0.25");
+    assert_eq!(format_eval(-1500), "-1.50");
+    assert_eq!(format_eval(MAX_VALUE - 1), "#1");
+    assert_eq!(format_eval(MIN_VALUE + 4), "#-2");
+
+    let game = PgnGame::parse("1. e4 {[%eval #-2]} *").unwrap();
+    assert_eq!(game.moves[0].eval, Some(MIN_VALUE + 4));
+    assert_eq!(game.moves[0].comment, None);
+}
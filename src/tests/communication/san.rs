@@ -0,0 +1,103 @@
+use crate::{
+    boards::Board,
+    communication::{Communicator, UCICommunicator},
+};
+
+fn san_for(uci: &mut UCICommunicator, uci_move: &str) -> String {
+    let action = uci.decode(uci_move.to_string());
+    uci.encode_san(&action)
+}
+
+fn uci_for(uci: &mut UCICommunicator, san: &str) -> Option<String> {
+    uci.decode_san(san).map(|action| uci.encode(&action))
+}
+
+#[test]
+fn encode_san_test() {
+    let mut uci = Board::load_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    assert_eq!(san_for(&mut uci, "e2e4"), "e4");
+    assert_eq!(san_for(&mut uci, "g1f3"), "Nf3");
+
+    // Kiwipete has castling on both sides.
+    let mut uci =
+        Board::load_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+    assert_eq!(san_for(&mut uci, "e1g1"), "O-O");
+    assert_eq!(san_for(&mut uci, "e1c1"), "O-O-O");
+    assert_eq!(san_for(&mut uci, "c3d1"), "Nd1");
+    assert_eq!(san_for(&mut uci, "d5e6"), "dxe6");
+    assert_eq!(san_for(&mut uci, "e5f7"), "Nxf7");
+    assert_eq!(san_for(&mut uci, "f3f6"), "Qxf6");
+
+    // Knights on the same rank are told apart by file.
+    let mut uci = Board::load_fen("4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1");
+    assert_eq!(san_for(&mut uci, "b1d2"), "Nbd2");
+    assert_eq!(san_for(&mut uci, "f1d2"), "Nfd2");
+    assert_eq!(san_for(&mut uci, "f1g3"), "Ng3");
+
+    // Rooks on the same file are told apart by rank.
+    let mut uci = Board::load_fen("7k/8/8/R7/8/8/8/R6K w - - 0 1");
+    assert_eq!(san_for(&mut uci, "a1a3"), "R1a3");
+    assert_eq!(san_for(&mut uci, "a5a3"), "R5a3");
+
+    // Three queens that can reach the same square can need both the file and the rank.
+    let mut uci = Board::load_fen("5k2/8/8/8/Q7/8/8/Q2Q3K w - - 0 1");
+    assert_eq!(san_for(&mut uci, "a1d4"), "Qa1d4");
+    assert_eq!(san_for(&mut uci, "a4d4"), "Q4d4");
+    assert_eq!(san_for(&mut uci, "d1d4"), "Qdd4");
+}
+
+#[test]
+fn encode_san_suffix_test() {
+    let mut uci = Board::load_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+    assert_eq!(san_for(&mut uci, "a1a8"), "Ra8#");
+    assert_eq!(san_for(&mut uci, "a1a7"), "Ra7");
+
+    let mut uci = Board::load_fen("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1");
+    assert_eq!(san_for(&mut uci, "b7b8q"), "b8=Q+");
+    assert_eq!(san_for(&mut uci, "b7b8n"), "b8=N");
+
+    let mut uci = Board::load_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1");
+    assert_eq!(san_for(&mut uci, "e5d6"), "exd6");
+}
+
+#[test]
+fn decode_san_test() {
+    let mut uci =
+        Board::load_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+    assert_eq!(uci_for(&mut uci, "O-O").as_deref(), Some("e1g1"));
+    assert_eq!(uci_for(&mut uci, "0-0-0").as_deref(), Some("e1c1"));
+    assert_eq!(uci_for(&mut uci, "Nd1").as_deref(), Some("c3d1"));
+    assert_eq!(uci_for(&mut uci, "Nc3d1").as_deref(), Some("c3d1"));
+    assert_eq!(uci_for(&mut uci, "Nxf7+").as_deref(), Some("e5f7"));
+    assert_eq!(uci_for(&mut uci, "dxe6").as_deref(), Some("d5e6"));
+    assert_eq!(uci_for(&mut uci, "Qxf6!?").as_deref(), Some("f3f6"));
+
+    // Illegal and malformed moves don't decode.
+    assert_eq!(uci_for(&mut uci, "Ke3"), None);
+    assert_eq!(uci_for(&mut uci, "Zz9"), None);
+
+    // Neither does an ambiguous one.
+    let mut uci = Board::load_fen("4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1");
+    assert_eq!(uci_for(&mut uci, "Nd2"), None);
+    assert_eq!(uci_for(&mut uci, "Nbd2").as_deref(), Some("b1d2"));
+    assert_eq!(uci_for(&mut uci, "N1d2"), None);
+
+    let mut uci = Board::load_fen("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1");
+    assert_eq!(uci_for(&mut uci, "b8=Q+").as_deref(), Some("b7b8q"));
+    assert_eq!(uci_for(&mut uci, "b8N").as_deref(), Some("b7b8n"));
+    assert_eq!(uci_for(&mut uci, "b8"), None);
+
+    let mut uci = Board::load_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1");
+    assert_eq!(uci_for(&mut uci, "exd6").as_deref(), Some("e5d6"));
+}
+
+#[test]
+fn san_round_trip_test() {
+    let mut uci =
+        Board::load_fen("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1");
+
+    for action in uci.board.generate_legal_moves() {
+        let san = uci.encode_san(&action);
+        assert_eq!(uci.decode_san(&san), Some(action), "{} round trips", san);
+    }
+}
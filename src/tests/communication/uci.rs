@@ -0,0 +1,130 @@
+use crate::{
+    boards::Board,
+    communication::{decode_file, encode_file, split_uci_move, Communicator},
+    error::LotisaError,
+};
+
+#[test]
+fn try_decode_test() {
+    let mut uci = Board::load_fen("4k3/P7/8/8/8/8/8/4K3 w - - 0 1");
+    let action = uci.try_decode("a7a8q").unwrap();
+    assert_eq!(action.info, 4, "Promotes to a queen");
+    assert_eq!(uci.encode(&action), "a7a8q");
+    assert_eq!(
+        uci.try_decode("a7a8"),
+        Err(LotisaError::IllegalMove("a7a8".to_string())),
+        "Promotions need a piece"
+    );
+
+    // White is in check from the bishop on b6.
+    let mut uci =
+        Board::load_fen("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1");
+    assert!(uci.try_decode("g1h1").is_ok());
+    assert_eq!(
+        uci.try_decode("a7b8q"),
+        Err(LotisaError::IllegalMove("a7b8q".to_string())),
+        "The promotion leaves the king in check"
+    );
+    assert_eq!(
+        uci.try_decode("e2e4"),
+        Err(LotisaError::IllegalMove("e2e4".to_string())),
+        "There's no piece on e2"
+    );
+
+    for invalid in ["", "e2", "e2e4e5", "z1a1", "a0a1", "a9a1", "é2e4"] {
+        assert_eq!(
+            uci.try_decode(invalid),
+            Err(LotisaError::InvalidMove(invalid.to_string())),
+            "{} isn't a move",
+            invalid
+        );
+    }
+}
+
+#[test]
+fn try_decode_pos_test() {
+    let mut uci = Board::load_fen("8/8/8/8/8/8/8/8 w - - 0 1");
+
+    let pos = uci.try_decode_pos("e4").unwrap();
+    assert_eq!(uci.encode_pos(pos), "e4");
+
+    for invalid in ["", "e", "4", "i1", "a0", "a9", "E4"] {
+        assert_eq!(
+            uci.try_decode_pos(invalid),
+            Err(LotisaError::InvalidSquare(invalid.to_string()))
+        );
+    }
+}
+
+#[test]
+fn file_codec_test() {
+    let files = [
+        (0, "a"),
+        (25, "z"),
+        (26, "aa"),
+        (27, "ab"),
+        (51, "az"),
+        (52, "ba"),
+    ];
+    for (col, file) in files {
+        assert_eq!(encode_file(col), file);
+        assert_eq!(decode_file(file), Some(col));
+    }
+
+    for invalid in ["", "A", "a1", "é"] {
+        assert_eq!(decode_file(invalid), None, "{} isn't a file", invalid);
+    }
+
+    assert_eq!(split_uci_move("e7e8q"), Some(("e7", "e8", "q")));
+    assert_eq!(split_uci_move("a10ab10"), Some(("a10", "ab10", "")));
+    assert_eq!(split_uci_move("e2"), None);
+    assert_eq!(split_uci_move("e2e4-"), None);
+}
+
+#[test]
+fn large_board_uci_test() {
+    let rows = ["k27"]
+        .into_iter()
+        .chain(["28"; 10])
+        .chain(["K26R"])
+        .collect::<Vec<_>>();
+    let mut uci = Board::load_fen(&format!("28x12 {} w - - 0 1", rows.join("/")));
+
+    let pos = uci.try_decode_pos("ab12").unwrap();
+    assert_eq!(uci.encode_pos(pos), "ab12");
+    assert!(uci.try_decode_pos("ac1").is_err());
+    assert!(uci.try_decode_pos("a13").is_err());
+
+    for action in ["ab1ab12", "ab1aa1", "ab1b1", "a1a2"] {
+        let decoded = uci.try_decode(action).unwrap();
+        assert_eq!(uci.encode(&decoded), action);
+    }
+
+    let action = uci.decode("ab1ab12".to_string());
+    uci.board.make_move(action);
+    // The rook checks along the 12th rank, so the king has to leave it.
+    assert!(uci.try_decode("a12a11").is_ok());
+    assert!(uci.try_decode("a12b12").is_err());
+}
+
+#[test]
+fn huge_board_uci_test() {
+    // Boards this big used to overflow the zobrist indexing.
+    let rows = ["k39"]
+        .into_iter()
+        .chain(["40"; 38])
+        .chain(["K38R"])
+        .collect::<Vec<_>>();
+    let mut uci = Board::load_fen(&format!("40x40 {} w - - 0 1", rows.join("/")));
+    let hash = uci.board.hash;
+
+    let action = uci.decode("an1an40".to_string());
+    uci.board.make_move(action);
+    assert_eq!(uci.encode_pos(action.to), "an40");
+    assert_ne!(uci.board.hash, hash);
+    assert!(uci.try_decode("a40a39").is_ok());
+    assert!(uci.try_decode("a40b40").is_err());
+
+    uci.board.undo_move();
+    assert_eq!(uci.board.hash, hash);
+}
@@ -0,0 +1,4 @@
+mod multiplayer;
+mod search;
+mod time_manager;
+mod transposition_table;
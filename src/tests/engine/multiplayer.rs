@@ -0,0 +1,77 @@
+use std::sync::Arc;
+
+use crate::{
+    boards::Board,
+    cli::create_info,
+    communication::Communicator,
+    engine::{
+        evaluate_teams, root_search, MultiplayerSearch, SearchLimits, SearchOptions, SearchSignals,
+    },
+};
+
+const THREE_TEAMS: &str = "8x8:3 4k3/8/8/3q(2)4/8/8/4P3/4K2k(2) w - - 0 1";
+
+#[test]
+fn team_cycling() {
+    let uci = Board::load_fen(THREE_TEAMS);
+    let board = &uci.board;
+    assert_eq!(board.get_next_team(0), 1);
+    assert_eq!(board.get_next_team(2), 0);
+    assert_eq!(board.get_previous_team(0), 2);
+    assert_eq!(board.get_previous_team(2), 1);
+    assert_eq!(board.previous_team(), 2);
+}
+
+#[test]
+fn multiplayer_pawn_test() {
+    // Team 2 moves up the board like white.
+    let mut uci = Board::load_fen("8x8:3 4k3/8/8/8/8/8/3p(2)4/K6k(2) 2 - - 0 1");
+    let from = uci.decode_pos("d2".to_string());
+    let mut moves = uci
+        .board
+        .generate_legal_moves()
+        .iter()
+        .filter(|action| action.from == from)
+        .map(|action| uci.encode(action))
+        .collect::<Vec<_>>();
+    moves.sort();
+    assert_eq!(moves, vec!["d2d3", "d2d4"]);
+}
+
+#[test]
+fn multiplayer_eval_test() {
+    let mut uci = Board::load_fen(THREE_TEAMS);
+    let scores = evaluate_teams(&mut uci.board);
+    assert_eq!(scores.len(), 3);
+    assert!(
+        scores[2] > scores[0] && scores[2] > scores[1],
+        "The team with the queen is ahead"
+    );
+}
+
+#[test]
+fn multiplayer_search_test() {
+    for multiplayer_search in [MultiplayerSearch::Paranoid, MultiplayerSearch::MaxN] {
+        let mut uci = Board::load_fen(THREE_TEAMS);
+        let options = SearchOptions {
+            multiplayer_search,
+            ..Default::default()
+        };
+        let mut info = create_info(options, Arc::new(SearchSignals::default()));
+        info.limits = SearchLimits {
+            depth: Some(3),
+            ..Default::default()
+        };
+        info.quiet = true;
+
+        let moving_team = uci.board.moving_team;
+        root_search(&mut info, &mut uci, moving_team);
+
+        let best_move = info.pv_table.table[0][0].expect("Search should find a move");
+        assert!(uci.board.is_legal(best_move, moving_team));
+        assert_eq!(
+            uci.board.moving_team, 0,
+            "Search leaves the board as it was"
+        );
+    }
+}
@@ -0,0 +1,30 @@
+use std::sync::Arc;
+
+use crate::{
+    boards::Board,
+    cli::create_info,
+    communication::Communicator,
+    engine::{format_score, root_search, SearchLimits, SearchOptions, SearchSignals},
+};
+
+#[test]
+fn mate_in_one_test() {
+    let mut uci = Board::load_fen("6rk/6pp/8/6N1/8/8/8/6K1 w - - 0 1");
+    let mut info = create_info(SearchOptions::default(), Arc::new(SearchSignals::default()));
+    info.limits = SearchLimits {
+        depth: Some(4),
+        ..Default::default()
+    };
+    info.quiet = true;
+
+    let moving_team = uci.board.moving_team;
+    let score = root_search(&mut info, &mut uci, moving_team);
+
+    let best_move = info.pv_table.table[0][0].expect("Search should find a move");
+    assert_eq!(uci.encode(&best_move), "g5f7", "Nf7 is smothered mate");
+    assert!(
+        info.pv_table.table[0][1].is_none(),
+        "Nothing comes after mate in the PV"
+    );
+    assert_eq!(format_score(score), "mate 1");
+}
@@ -0,0 +1,89 @@
+use std::sync::Arc;
+
+use crate::{
+    boards::{Board, STARTPOS_FEN},
+    cli::create_info,
+    engine::{root_search, SearchLimits, SearchOptions, SearchSignals, TimeManager},
+};
+
+#[test]
+fn time_manager_limits_test() {
+    let time_manager = TimeManager::new(&SearchLimits::default(), 10);
+    assert_eq!(time_manager.soft_limit, None);
+    assert_eq!(time_manager.hard_limit, None);
+
+    let limits = SearchLimits {
+        move_time: Some(500),
+        ..Default::default()
+    };
+    let time_manager = TimeManager::new(&limits, 10);
+    assert_eq!(time_manager.soft_limit, Some(490));
+    assert_eq!(time_manager.hard_limit, Some(490));
+
+    let limits = SearchLimits {
+        time: Some(60_000),
+        inc: Some(1_000),
+        ..Default::default()
+    };
+    let time_manager = TimeManager::new(&limits, 0);
+    let soft_limit = time_manager.soft_limit.unwrap();
+    let hard_limit = time_manager.hard_limit.unwrap();
+    assert!(soft_limit > 1_000 && soft_limit < hard_limit);
+    assert!(hard_limit < 60_000);
+
+    // Fewer moves until the time control means more time for each of them.
+    let limits = SearchLimits {
+        time: Some(60_000),
+        moves_to_go: Some(5),
+        ..Default::default()
+    };
+    assert!(TimeManager::new(&limits, 0).soft_limit.unwrap() > soft_limit);
+
+    let limits = SearchLimits {
+        time: Some(60_000),
+        infinite: true,
+        ..Default::default()
+    };
+    assert_eq!(TimeManager::new(&limits, 0).hard_limit, None);
+}
+
+#[test]
+fn time_manager_extension_test() {
+    let limits = SearchLimits {
+        time: Some(60_000),
+        ..Default::default()
+    };
+    let mut time_manager = TimeManager::new(&limits, 0);
+
+    time_manager.update(false, 0);
+    assert_eq!(time_manager.soft_scale, 100);
+
+    time_manager.update(true, 0);
+    let best_move_changed = time_manager.soft_scale;
+    assert!(best_move_changed > 100);
+
+    time_manager.update(true, 1_000);
+    assert!(time_manager.soft_scale > best_move_changed);
+
+    // Even extended, the soft limit never goes past the hard limit.
+    time_manager.start -= time_manager.hard_limit.unwrap();
+    assert!(time_manager.soft_limit_reached());
+    assert!(time_manager.hard_limit_reached());
+}
+
+#[test]
+fn search_hard_limit_test() {
+    let mut uci = Board::load_fen(STARTPOS_FEN);
+    let mut info = create_info(SearchOptions::default(), Arc::new(SearchSignals::default()));
+    info.limits = SearchLimits {
+        move_time: Some(200),
+        ..Default::default()
+    };
+
+    let moving_team = uci.board.moving_team;
+    root_search(&mut info, &mut uci, moving_team);
+
+    // The first iteration always finishes, and the clock is polled every few nodes after that.
+    assert!(info.time_manager.elapsed() < 1_000);
+    assert!(info.pv_table.table[0][0].is_some());
+}
@@ -0,0 +1,71 @@
+use crate::engine::{
+    score_from_tt, score_to_tt, TranspositionBound, TranspositionTable, BUCKET_SIZE, MAX_VALUE,
+    MIN_VALUE,
+};
+
+#[test]
+fn transposition_key_test() {
+    // A tiny table only has one bucket, so every key collides on the same index.
+    let mut table = TranspositionTable::new(0);
+    assert_eq!(table.buckets.len(), 1);
+
+    table.store(1, 50, 3, TranspositionBound::Exact, None, 0);
+    assert!(table.probe(2).is_none(), "Collisions shouldn't be trusted");
+
+    let entry = table.probe(1).unwrap();
+    assert_eq!(entry.eval, 50);
+    assert_eq!(entry.depth, 3);
+    assert_eq!(entry.bound, TranspositionBound::Exact);
+
+    table.store(1, 70, 5, TranspositionBound::Lower, None, 0);
+    let entry = table.probe(1).unwrap();
+    assert_eq!(entry.eval, 70);
+    assert_eq!(entry.bound, TranspositionBound::Lower);
+    assert_eq!(
+        table.buckets[0].iter().flatten().count(),
+        1,
+        "The same position should only be stored once"
+    );
+}
+
+#[test]
+fn transposition_replacement_test() {
+    let mut table = TranspositionTable::new(0);
+    for key in 0..BUCKET_SIZE {
+        table.store(key, 0, 10 - key as i16, TranspositionBound::Exact, None, 0);
+    }
+
+    // The bucket is full, so the shallowest entry is replaced.
+    table.store(100, 0, 8, TranspositionBound::Exact, None, 0);
+    assert!(table.probe(BUCKET_SIZE - 1).is_none());
+    assert!(table.probe(100).is_some());
+
+    // Entries from older searches get replaced before deeper ones from this search.
+    table.new_search();
+    table.store(200, 0, 1, TranspositionBound::Exact, None, 0);
+    table.new_search();
+    table.store(300, 0, 1, TranspositionBound::Exact, None, 0);
+    assert!(table.probe(200).is_some());
+    assert!(table.probe(300).is_some());
+}
+
+#[test]
+fn transposition_mate_score_test() {
+    // A mate found 5 plies from the root, stored at ply 3, is mate in 2 from that position.
+    let mate = MAX_VALUE - 5;
+    assert_eq!(score_to_tt(mate, 3), MAX_VALUE - 2);
+    assert_eq!(score_from_tt(score_to_tt(mate, 3), 7), MAX_VALUE - 9);
+
+    let mated = MIN_VALUE + 5;
+    assert_eq!(score_to_tt(mated, 3), MIN_VALUE + 2);
+    assert_eq!(score_from_tt(score_to_tt(mated, 3), 3), mated);
+
+    assert_eq!(score_to_tt(150, 12), 150);
+
+    let mut table = TranspositionTable::new(0);
+    table.store(1, mate, 4, TranspositionBound::Exact, None, 3);
+    assert_eq!(
+        score_from_tt(table.probe(1).unwrap().eval, 1),
+        MAX_VALUE - 3
+    );
+}
@@ -1,3 +1,4 @@
 mod boards;
-
-pub use boards::*;
+mod cli;
+mod communication;
+mod engine;